@@ -0,0 +1,46 @@
+//Times compute_swap_step for the step shapes of a swap: exact input and output, capped at the target or not.
+//Run with `cargo bench --bench compute_swap_step`
+use alloy::primitives::{I256, U256};
+use cw_uniswap_v3::{swap_math::compute_swap_step, tick_math::get_sqrt_ratio_at_tick};
+use std::{error::Error, hint::black_box, str::FromStr, time::Instant};
+
+const ITERATIONS: u32 = 200_000;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let current = get_sqrt_ratio_at_tick(0)?;
+    let target = get_sqrt_ratio_at_tick(-60)?;
+    let liquidity = 2_000_000_000_000_000_000_u128;
+
+    for (name, amount_remaining) in [
+        (
+            "exact in, capped at target",
+            I256::from_str("1000000000000000000")?,
+        ),
+        ("exact in, partial", I256::from_str("1000000000000000")?),
+        (
+            "exact out, capped at target",
+            I256::from_str("-1000000000000000000")?,
+        ),
+        ("exact out, partial", I256::from_str("-1000000000000000")?),
+    ] {
+        let start = Instant::now();
+        let mut checksum = U256::ZERO;
+        for _ in 0..ITERATIONS {
+            let (sqrt_price_next, amount_in, amount_out, fee_amount) = compute_swap_step(
+                black_box(current),
+                black_box(target),
+                black_box(liquidity),
+                black_box(amount_remaining),
+                black_box(3000),
+            )?;
+            checksum ^= sqrt_price_next ^ amount_in ^ amount_out ^ fee_amount;
+        }
+        let elapsed = start.elapsed();
+        black_box(checksum);
+        println!(
+            "{name}: {:.0} ns/step",
+            elapsed.as_nanos() as f64 / ITERATIONS as f64
+        );
+    }
+    Ok(())
+}
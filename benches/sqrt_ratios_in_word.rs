@@ -0,0 +1,41 @@
+//Times sqrt_ratios_in_word against calling get_sqrt_ratio_at_tick for each tick of the word.
+//Run with `cargo bench --bench sqrt_ratios_in_word`
+use alloy::primitives::U256;
+use cw_uniswap_v3::tick_math::{get_sqrt_ratio_at_tick, sqrt_ratios_in_word};
+use std::{error::Error, hint::black_box, time::Instant};
+
+const ITERATIONS: u32 = 2_000;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    for (word_pos, tick_spacing) in [(-1_i16, 1), (2, 60)] {
+        let start = Instant::now();
+        let mut checksum = U256::ZERO;
+        for _ in 0..ITERATIONS {
+            for bit_pos in 0..256 {
+                let tick = (black_box(word_pos) as i32 * 256 + bit_pos) * black_box(tick_spacing);
+                checksum ^= get_sqrt_ratio_at_tick(tick)?;
+            }
+        }
+        let scalar = start.elapsed();
+        black_box(checksum);
+
+        let start = Instant::now();
+        let mut checksum = U256::ZERO;
+        for _ in 0..ITERATIONS {
+            for (_, sqrt_ratio) in
+                sqrt_ratios_in_word(black_box(word_pos), black_box(tick_spacing))?
+            {
+                checksum ^= sqrt_ratio;
+            }
+        }
+        let batch = start.elapsed();
+        black_box(checksum);
+
+        println!(
+            "word {word_pos}, spacing {tick_spacing}: scalar {:.0} ns/word, batch {:.0} ns/word",
+            scalar.as_nanos() as f64 / ITERATIONS as f64,
+            batch.as_nanos() as f64 / ITERATIONS as f64
+        );
+    }
+    Ok(())
+}
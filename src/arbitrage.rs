@@ -0,0 +1,202 @@
+use crate::error::UniswapV3MathError;
+use crate::full_math::mul_div;
+use crate::pool::Pool;
+use crate::tick_math;
+use crate::U256_1;
+use alloy::primitives::{I256, U256};
+
+// a profitable cycle between two pools of the same pair, starting and ending in token1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArbOpportunity {
+    // true to buy token0 with token1 on pool_a and sell it on pool_b, false for the reverse
+    pub buy_on_a: bool,
+    // the token1 swapped into the pool token0 is bought on
+    pub amount_in: U256,
+    // the token0 bought and sold on the other pool
+    pub amount_intermediate: U256,
+    // the token1 received from the pool token0 is sold on
+    pub amount_out: U256,
+    pub profit: U256,
+}
+
+//Detects an arbitrage between two pools trading the same pair. A cycle is only profitable if the spot price of the
+//expensive pool net of both fees is above the spot price of the cheap one; if so the input is sized by searching for
+//the amount maximizing the simulated profit, doubling it until the profit drops and then narrowing down with a
+//ternary search. The profit is not strictly concave because of rounding, so the input is an estimate of the optimum
+pub fn detect_arbitrage(
+    pool_a: &Pool,
+    pool_b: &Pool,
+) -> Result<Option<ArbOpportunity>, UniswapV3MathError> {
+    let price_a = pool_a.price_x_128()?;
+    let price_b = pool_b.price_x_128()?;
+    let (buy_on_a, cheap, expensive) = if price_a < price_b {
+        (true, pool_a, pool_b)
+    } else {
+        (false, pool_b, pool_a)
+    };
+
+    // price_expensive * (1 - fee_expensive) * (1 - fee_cheap) > price_cheap
+    let price_net_of_fees = mul_div(
+        mul_div(
+            expensive.price_x_128()?,
+            fee_complement(expensive),
+            U256::from(1_000_000),
+        )?,
+        fee_complement(cheap),
+        U256::from(1_000_000),
+    )?;
+    if price_net_of_fees <= cheap.price_x_128()? {
+        return Ok(None);
+    }
+
+    let max_amount_in = U256::from(u128::MAX);
+    let mut amount_in = U256_1;
+    let mut previous_profit: Option<I256> = None;
+    while amount_in < max_amount_in {
+        let profit = simulate_cycle(cheap, expensive, amount_in)?.profit;
+        match previous_profit {
+            Some(previous_profit) if previous_profit.is_positive() && profit < previous_profit => {
+                break
+            }
+            _ => previous_profit = Some(profit),
+        }
+        amount_in <<= 1;
+    }
+
+    let mut lo = amount_in >> 2;
+    let mut hi = amount_in;
+    let three = U256::from(3);
+    while hi - lo > U256::from(2) {
+        let m1 = lo + (hi - lo) / three;
+        let m2 = hi - (hi - lo) / three;
+        if simulate_cycle(cheap, expensive, m1)?.profit
+            < simulate_cycle(cheap, expensive, m2)?.profit
+        {
+            lo = m1;
+        } else {
+            hi = m2;
+        }
+    }
+
+    let mut best: Option<Cycle> = None;
+    let mut amount_in = lo;
+    while amount_in <= hi {
+        let cycle = simulate_cycle(cheap, expensive, amount_in)?;
+        match &best {
+            Some(best) if best.profit >= cycle.profit => {}
+            _ => best = Some(cycle),
+        }
+        amount_in += U256_1;
+    }
+
+    Ok(best
+        .filter(|cycle| cycle.profit.is_positive())
+        .map(|cycle| ArbOpportunity {
+            buy_on_a,
+            amount_in: cycle.amount_in,
+            amount_intermediate: cycle.amount_intermediate,
+            amount_out: cycle.amount_out,
+            profit: cycle.profit.into_raw(),
+        }))
+}
+
+// the outcome of swapping token1 for token0 on one pool and the token0 back on the other
+struct Cycle {
+    amount_in: U256,
+    amount_intermediate: U256,
+    amount_out: U256,
+    profit: I256,
+}
+
+fn simulate_cycle(
+    cheap: &Pool,
+    expensive: &Pool,
+    amount_in: U256,
+) -> Result<Cycle, UniswapV3MathError> {
+    let buy = cheap.simulate(
+        false,
+        I256::from_raw(amount_in),
+        tick_math::MAX_SQRT_RATIO - U256_1,
+    )?;
+    // the swap stops at the price limit before the amount in is used up for very large amounts
+    let amount_in = buy.amount1_delta.into_raw();
+    let amount_intermediate = (-buy.amount0_delta).into_raw();
+
+    let amount_out = if amount_intermediate.is_zero() {
+        U256::ZERO
+    } else {
+        let sell = expensive.simulate(
+            true,
+            I256::from_raw(amount_intermediate),
+            tick_math::MIN_SQRT_RATIO + U256_1,
+        )?;
+        (-sell.amount1_delta).into_raw()
+    };
+
+    Ok(Cycle {
+        amount_in,
+        amount_intermediate,
+        amount_out,
+        profit: I256::from_raw(amount_out) - I256::from_raw(amount_in),
+    })
+}
+
+fn fee_complement(pool: &Pool) -> U256 {
+    U256::from(1_000_000 - pool.fee().min(1_000_000))
+}
+
+#[cfg(test)]
+mod test {
+    use super::detect_arbitrage;
+    use crate::{pool::Pool, swap::Slot0, tick_math};
+    use alloy::primitives::U256;
+    use std::{collections::HashMap, str::FromStr};
+
+    // a 0.3% pool at the given tick with constant liquidity over the whole price range
+    fn constant_liquidity_pool(tick: i32) -> eyre::Result<Pool> {
+        Ok(Pool::new(
+            HashMap::new(),
+            HashMap::new(),
+            60,
+            3000,
+            Slot0 {
+                sqrt_price: tick_math::get_sqrt_ratio_at_tick(tick)?,
+                liquidity: 1_000_000_000_000_000_000,
+                tick,
+            },
+        )?)
+    }
+
+    #[test]
+    fn test_detect_arbitrage() -> eyre::Result<()> {
+        // token0 is ~2% cheaper on the pool at tick 0, well above the 0.6% paid in fees
+        let cheap = constant_liquidity_pool(0)?;
+        let expensive = constant_liquidity_pool(200)?;
+
+        let opportunity = detect_arbitrage(&cheap, &expensive)?.expect("an opportunity");
+        assert!(opportunity.buy_on_a);
+        assert_eq!(opportunity.amount_in, U256::from_str("3508004922008590")?);
+        assert_eq!(
+            opportunity.amount_intermediate,
+            U256::from_str("3485291167926559")?
+        );
+        assert_eq!(opportunity.amount_out, U256::from_str("3532629410918729")?);
+        assert_eq!(opportunity.profit, U256::from_str("24624488910139")?);
+
+        let reversed = detect_arbitrage(&expensive, &cheap)?.expect("an opportunity");
+        assert!(!reversed.buy_on_a);
+        assert_eq!(reversed.profit, opportunity.profit);
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_arbitrage_none() -> eyre::Result<()> {
+        let pool = constant_liquidity_pool(0)?;
+        assert_eq!(detect_arbitrage(&pool, &pool)?, None);
+
+        // a 0.1% price difference does not cover the fees
+        let close = constant_liquidity_pool(10)?;
+        assert_eq!(detect_arbitrage(&pool, &close)?, None);
+        Ok(())
+    }
+}
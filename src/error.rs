@@ -26,22 +26,88 @@ pub enum UniswapV3MathError {
     ProductDivAmount,
     #[error("Denominator is less than or equal to prod_1")]
     DenominatorIsLteProdOne,
+    #[error("Overflow when accounting swap amounts")]
+    Overflow,
     #[error("Liquidity Sub")]
     LiquiditySub,
+    #[error("Liquidity underflow when adding {delta} to {current}")]
+    LiquidityUnderflow { current: u128, delta: i128 },
+    #[error("Liquidity underflow when subtracting {delta} from {current}")]
+    LiquiditySubUnderflow { current: u128, delta: u128 },
     #[error("Liquidity Add")]
     LiquidityAdd,
-    #[error("The given tick must be less than, or equal to, the maximum tick")]
-    T,
+    #[error("Liquidity gross of the tick exceeds the max liquidity per tick")]
+    LO,
+    #[error(
+        "The given tick {0} must be less than, or equal to, the maximum tick in absolute value"
+    )]
+    TickOutOfRange(i32),
     #[error(
         "Second inequality must be < because the price can never reach the price at the max tick"
     )]
     R,
     #[error("Overflow when casting to U160")]
     SafeCastToU160Overflow,
+    #[error("Overflow when casting to U128")]
+    SafeCastToU128Overflow,
+    #[error("Transfer fee must be less than 10000 bps")]
+    InvalidTransferFee,
+    #[error("Price {0} is outside of the prices of the tick range")]
+    PriceOutOfRange(f64),
+    #[error("Tick lower must be less than tick upper")]
+    InvalidTickRange,
+    #[error("Range order must not contain the current tick")]
+    RangeOrderInRange,
+    #[error("The pool can not pay out the full exact output amount")]
+    InsufficientOutput,
+    #[error("Tick liquidity_net {liquidity_net} exceeds liquidity_gross {liquidity_gross}")]
+    InvalidTick {
+        liquidity_gross: u128,
+        liquidity_net: i128,
+    },
     #[error("Tick spacing error")]
     TickSpacingError,
+    #[error("Tick {tick} is not a multiple of the tick spacing {tick_spacing}")]
+    MisalignedTick { tick: i32, tick_spacing: i32 },
+    #[error("Slot0 tick {tick} does not contain its sqrt price, expected tick {expected}")]
+    InconsistentTick { tick: i32, expected: i32 },
+    #[error("Initialized tick {0} is not set in the tick bitmap")]
+    TickNotInBitmap(i32),
+    #[error("Tick {0} is set in the tick bitmap but not initialized")]
+    UninitializedBitmapTick(i32),
+    #[error("Tick {0} has an initialized flag that does not match its liquidity_gross")]
+    TickInitializedMismatch(i32),
+    #[error("Slot0 liquidity {liquidity} is not the liquidity {expected} of the ticks at or below the current tick")]
+    InconsistentLiquidity { liquidity: u128, expected: i128 },
+    #[error("Swap scanned more than {0} tick bitmap words")]
+    ScanBudgetExceeded(u32),
+    #[error("Swap crossed more ticks than fit in the {0} tick crossing buffer")]
+    TickCrossingBufferFull(usize),
+    #[error("Observation is older than the oldest stored observation")]
+    OLD,
+    #[error("Initialized tick {0} not found")]
+    TickNotFound(i32),
     #[error("Middleware error when getting next_initialized_tick_within_one_word")]
     MiddlewareError(String),
+    #[error("Failed to decode Swap event log")]
+    SwapLogDecodeError(String),
+    #[error("Failed to read or write swap JSON")]
+    SwapJsonError(String),
+    #[error("Unknown fee tier {0}")]
+    UnknownFeeTier(String),
+    #[error("Fee {fee} with tick spacing {tick_spacing} is not a fee tier")]
+    FeeTierMismatch { fee: u32, tick_spacing: i32 },
+    #[error("Unknown swap direction {0}")]
+    UnknownSwapDirection(String),
+    #[error("Swap {index} of the replay failed: {source}")]
+    ReplayFailed {
+        index: usize,
+        source: Box<UniswapV3MathError>,
+    },
+    // a message attached by an integrator, for errors of higher layers without a separate error type
+    #[cfg(feature = "alloc")]
+    #[error("{0}")]
+    Custom(String),
     #[error("Parse error")]
     ParseError(#[from] ParseError),
     #[error("SqrtPrice Lower Than Min")]
@@ -53,3 +119,35 @@ pub enum UniswapV3MathError {
     #[error("SqrtPrice Upper Than Current")]
     SpuC,
 }
+
+impl UniswapV3MathError {
+    //Returns a Custom error with the message, e.g. UniswapV3MathError::custom(format!("pool {address} is stale"))
+    #[cfg(feature = "alloc")]
+    pub fn custom(message: impl Into<String>) -> Self {
+        UniswapV3MathError::Custom(message.into())
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod test {
+    use super::UniswapV3MathError;
+
+    #[test]
+    fn test_custom_error() {
+        let err = UniswapV3MathError::custom(format!("pool {} is stale", 42));
+        assert!(
+            matches!(&err, UniswapV3MathError::Custom(message) if message == "pool 42 is stale")
+        );
+        assert_eq!(err.to_string(), "pool 42 is stale");
+
+        // wraps into the other errors like any variant
+        let replay = UniswapV3MathError::ReplayFailed {
+            index: 3,
+            source: Box::new(UniswapV3MathError::custom("missing tick data")),
+        };
+        assert_eq!(
+            replay.to_string(),
+            "Swap 3 of the replay failed: missing tick data"
+        );
+    }
+}
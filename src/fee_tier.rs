@@ -0,0 +1,83 @@
+use crate::error::UniswapV3MathError;
+use std::str::FromStr;
+
+// the fee tiers enabled by the Uniswap V3 factory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FeeTier {
+    // 0.01%
+    Lowest,
+    // 0.05%
+    Low,
+    // 0.3%
+    Medium,
+    // 1%
+    High,
+}
+
+impl FeeTier {
+    pub const ALL: [FeeTier; 4] = [
+        FeeTier::Lowest,
+        FeeTier::Low,
+        FeeTier::Medium,
+        FeeTier::High,
+    ];
+
+    // the fee in hundredths of a bip, as passed to swap
+    pub fn fee(&self) -> u32 {
+        match self {
+            FeeTier::Lowest => 100,
+            FeeTier::Low => 500,
+            FeeTier::Medium => 3000,
+            FeeTier::High => 10000,
+        }
+    }
+
+    // the tick spacing the factory assigns to the fee
+    pub fn tick_spacing(&self) -> i32 {
+        match self {
+            FeeTier::Lowest => 1,
+            FeeTier::Low => 10,
+            FeeTier::Medium => 60,
+            FeeTier::High => 200,
+        }
+    }
+}
+
+impl FromStr for FeeTier {
+    type Err = UniswapV3MathError;
+
+    //Accepts the percentage ("0.3%"), the fee in hundredths of a bip ("3000") or the name ("medium")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "0.01%" | "100" | "lowest" => Ok(FeeTier::Lowest),
+            "0.05%" | "500" | "low" => Ok(FeeTier::Low),
+            "0.3%" | "0.30%" | "3000" | "medium" => Ok(FeeTier::Medium),
+            "1%" | "1.00%" | "10000" | "high" => Ok(FeeTier::High),
+            _ => Err(UniswapV3MathError::UnknownFeeTier(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FeeTier;
+    use crate::error::UniswapV3MathError;
+
+    #[test]
+    fn test_fee_tier_from_str() -> eyre::Result<()> {
+        for s in ["0.3%", "0.30%", "3000", "medium", "Medium", " medium "] {
+            assert_eq!(s.parse::<FeeTier>()?, FeeTier::Medium);
+        }
+        assert_eq!("0.01%".parse::<FeeTier>()?, FeeTier::Lowest);
+        assert_eq!("500".parse::<FeeTier>()?, FeeTier::Low);
+        assert_eq!("high".parse::<FeeTier>()?, FeeTier::High);
+        assert_eq!("1%".parse::<FeeTier>()?.fee(), 10000);
+        assert_eq!("low".parse::<FeeTier>()?.tick_spacing(), 10);
+
+        assert!(matches!(
+            "0.25%".parse::<FeeTier>(),
+            Err(UniswapV3MathError::UnknownFeeTier(s)) if s == "0.25%"
+        ));
+        Ok(())
+    }
+}
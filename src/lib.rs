@@ -1,16 +1,32 @@
 use alloy::primitives::U256;
 
+pub mod arbitrage;
 pub mod bit_math;
 pub mod error;
+pub mod fee_tier;
 pub mod full_math;
+pub mod liquidity_amounts;
 pub mod liquidity_math;
+pub mod oracle;
+pub mod pool;
+pub mod position;
+pub mod quoter;
+pub mod range_order;
+pub mod router;
 pub mod sqrt_price_math;
 pub mod swap;
+pub mod swap_event;
+#[cfg(feature = "serde")]
+pub mod swap_json;
 pub mod swap_math;
 pub mod tick;
 pub mod tick_bitmap;
 pub mod tick_math;
+pub mod tick_provider;
+pub mod tick_set;
 pub mod unsafe_math;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 const U256_1: U256 = U256::from_limbs([1, 0, 0, 0]);
 const U256_2: U256 = U256::from_limbs([2, 0, 0, 0]);
@@ -0,0 +1,247 @@
+use crate::error::UniswapV3MathError;
+use crate::full_math::mul_div;
+use crate::sqrt_price_math::{_get_amount_0_delta, _get_amount_1_delta, Q96};
+use alloy::primitives::U256;
+
+//Computes the liquidity received for a given amount of token0 and a price range,
+//amount0 * (sqrt(upper) * sqrt(lower)) / (sqrt(upper) - sqrt(lower)). Both products go through the 512-bit mul_div, so
+//only a liquidity that does not fit in 256 or 128 bits errors
+pub fn get_liquidity_for_amount0(
+    mut sqrt_ratio_a_x_96: U256,
+    mut sqrt_ratio_b_x_96: U256,
+    amount0: U256,
+) -> Result<u128, UniswapV3MathError> {
+    if sqrt_ratio_a_x_96 > sqrt_ratio_b_x_96 {
+        (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) = (sqrt_ratio_b_x_96, sqrt_ratio_a_x_96)
+    };
+    if sqrt_ratio_a_x_96 == sqrt_ratio_b_x_96 {
+        return Err(UniswapV3MathError::DenominatorIsZero);
+    }
+
+    let intermediate = mul_div(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, Q96)?;
+    to_u128(mul_div(
+        amount0,
+        intermediate,
+        sqrt_ratio_b_x_96 - sqrt_ratio_a_x_96,
+    )?)
+}
+
+//Computes the liquidity received for a given amount of token1 and a price range,
+//amount1 / (sqrt(upper) - sqrt(lower))
+pub fn get_liquidity_for_amount1(
+    mut sqrt_ratio_a_x_96: U256,
+    mut sqrt_ratio_b_x_96: U256,
+    amount1: U256,
+) -> Result<u128, UniswapV3MathError> {
+    if sqrt_ratio_a_x_96 > sqrt_ratio_b_x_96 {
+        (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) = (sqrt_ratio_b_x_96, sqrt_ratio_a_x_96)
+    };
+    if sqrt_ratio_a_x_96 == sqrt_ratio_b_x_96 {
+        return Err(UniswapV3MathError::DenominatorIsZero);
+    }
+
+    to_u128(mul_div(
+        amount1,
+        Q96,
+        sqrt_ratio_b_x_96 - sqrt_ratio_a_x_96,
+    )?)
+}
+
+//Computes the maximum liquidity received for the given amounts of token0 and token1, the current pool price and the
+//prices at the tick boundaries
+pub fn get_liquidity_for_amounts(
+    sqrt_ratio_x_96: U256,
+    mut sqrt_ratio_a_x_96: U256,
+    mut sqrt_ratio_b_x_96: U256,
+    amount0: U256,
+    amount1: U256,
+) -> Result<u128, UniswapV3MathError> {
+    if sqrt_ratio_a_x_96 > sqrt_ratio_b_x_96 {
+        (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) = (sqrt_ratio_b_x_96, sqrt_ratio_a_x_96)
+    };
+
+    if sqrt_ratio_x_96 <= sqrt_ratio_a_x_96 {
+        get_liquidity_for_amount0(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, amount0)
+    } else if sqrt_ratio_x_96 < sqrt_ratio_b_x_96 {
+        Ok(
+            get_liquidity_for_amount0(sqrt_ratio_x_96, sqrt_ratio_b_x_96, amount0)?.min(
+                get_liquidity_for_amount1(sqrt_ratio_a_x_96, sqrt_ratio_x_96, amount1)?,
+            ),
+        )
+    } else {
+        get_liquidity_for_amount1(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, amount1)
+    }
+}
+
+// SafeCast.toUint128
+fn to_u128(value: U256) -> Result<u128, UniswapV3MathError> {
+    u128::try_from(value).map_err(|_| UniswapV3MathError::SafeCastToU128Overflow)
+}
+
+//Computes the token0 and token1 value for a given amount of liquidity, the current pool prices and the prices at the
+//tick boundaries. Use round_up = true for the amounts owed to the pool on mint and round_up = false for the amounts
+//paid out on burn, so the pool never gives out more than it received
+// returns (amount0, amount1)
+pub fn get_amounts_for_liquidity(
+    sqrt_ratio_x_96: U256,
+    mut sqrt_ratio_a_x_96: U256,
+    mut sqrt_ratio_b_x_96: U256,
+    liquidity: u128,
+    round_up: bool,
+) -> Result<(U256, U256), UniswapV3MathError> {
+    if sqrt_ratio_a_x_96 > sqrt_ratio_b_x_96 {
+        (sqrt_ratio_a_x_96, sqrt_ratio_b_x_96) = (sqrt_ratio_b_x_96, sqrt_ratio_a_x_96)
+    };
+
+    if sqrt_ratio_x_96 <= sqrt_ratio_a_x_96 {
+        Ok((
+            _get_amount_0_delta(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, liquidity, round_up)?,
+            U256::ZERO,
+        ))
+    } else if sqrt_ratio_x_96 < sqrt_ratio_b_x_96 {
+        Ok((
+            _get_amount_0_delta(sqrt_ratio_x_96, sqrt_ratio_b_x_96, liquidity, round_up)?,
+            _get_amount_1_delta(sqrt_ratio_a_x_96, sqrt_ratio_x_96, liquidity, round_up)?,
+        ))
+    } else {
+        Ok((
+            U256::ZERO,
+            _get_amount_1_delta(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, liquidity, round_up)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        get_amounts_for_liquidity, get_liquidity_for_amount0, get_liquidity_for_amount1,
+        get_liquidity_for_amounts,
+    };
+    use crate::{
+        error::UniswapV3MathError, sqrt_price_math::Q96, tick_math::get_sqrt_ratio_at_tick,
+    };
+    use alloy::primitives::U256;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_get_amounts_for_liquidity() -> eyre::Result<()> {
+        let liquidity = 1_000_000_000_000_000_000;
+        let sqrt_ratio_a_x_96 = get_sqrt_ratio_at_tick(-600)?;
+        let sqrt_ratio_b_x_96 = get_sqrt_ratio_at_tick(600)?;
+
+        //in range, mint takes one more wei of each token than burn pays out
+        let sqrt_ratio_x_96 = get_sqrt_ratio_at_tick(-20)?;
+        let (mint0, mint1) = get_amounts_for_liquidity(
+            sqrt_ratio_x_96,
+            sqrt_ratio_a_x_96,
+            sqrt_ratio_b_x_96,
+            liquidity,
+            true,
+        )?;
+        let (burn0, burn1) = get_amounts_for_liquidity(
+            sqrt_ratio_x_96,
+            sqrt_ratio_a_x_96,
+            sqrt_ratio_b_x_96,
+            liquidity,
+            false,
+        )?;
+        assert_eq!(mint0, U256::from_str("30553460999158173")?);
+        assert_eq!(mint1, U256::from_str("28553560659208650")?);
+        assert_eq!(burn0, U256::from_str("30553460999158172")?);
+        assert_eq!(burn1, U256::from_str("28553560659208649")?);
+
+        //mint is never less than burn, below, inside and above the range and with swapped bounds
+        for tick in [-887272, -601, -600, -1, 0, 17, 599, 600, 887271] {
+            let sqrt_ratio_x_96 = get_sqrt_ratio_at_tick(tick)?;
+            for liquidity in [1, 3, 1_000_000_007, u64::MAX as u128] {
+                let (mint0, mint1) = get_amounts_for_liquidity(
+                    sqrt_ratio_x_96,
+                    sqrt_ratio_b_x_96,
+                    sqrt_ratio_a_x_96,
+                    liquidity,
+                    true,
+                )?;
+                let (burn0, burn1) = get_amounts_for_liquidity(
+                    sqrt_ratio_x_96,
+                    sqrt_ratio_a_x_96,
+                    sqrt_ratio_b_x_96,
+                    liquidity,
+                    false,
+                )?;
+                assert!(mint0 >= burn0);
+                assert!(mint1 >= burn1);
+                assert!(mint0 - burn0 <= U256::from(1));
+                assert!(mint1 - burn1 <= U256::from(1));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_liquidity_for_amounts() -> eyre::Result<()> {
+        let sqrt_ratio_a_x_96 = get_sqrt_ratio_at_tick(-600)?;
+        let sqrt_ratio_b_x_96 = get_sqrt_ratio_at_tick(600)?;
+        let amount = U256::from(1_000_000_000_000_000_000_u128);
+
+        let liquidity0 = get_liquidity_for_amount0(sqrt_ratio_b_x_96, sqrt_ratio_a_x_96, amount)?;
+        let liquidity1 = get_liquidity_for_amount1(sqrt_ratio_a_x_96, sqrt_ratio_b_x_96, amount)?;
+        assert_eq!(liquidity0, 16665000373539200203);
+        assert_eq!(liquidity1, 16665000373539200203);
+        assert_eq!(
+            get_liquidity_for_amounts(
+                Q96,
+                sqrt_ratio_a_x_96,
+                sqrt_ratio_b_x_96,
+                amount,
+                U256::from(1)
+            )?,
+            get_liquidity_for_amount1(sqrt_ratio_a_x_96, Q96, U256::from(1))?
+        );
+
+        //the liquidity never needs more than the amount it was computed from
+        let (amount0, _) = get_amounts_for_liquidity(
+            sqrt_ratio_a_x_96,
+            sqrt_ratio_a_x_96,
+            sqrt_ratio_b_x_96,
+            liquidity0,
+            true,
+        )?;
+        assert!(amount0 <= amount);
+
+        assert!(matches!(
+            get_liquidity_for_amount0(sqrt_ratio_a_x_96, sqrt_ratio_a_x_96, amount),
+            Err(UniswapV3MathError::DenominatorIsZero)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_liquidity_for_amount0_overflow() -> eyre::Result<()> {
+        //over the full range amount0 * sqrt(upper) * sqrt(lower) / Q96 is 273 bits, the liquidity only 113
+        assert_eq!(
+            get_liquidity_for_amount0(
+                get_sqrt_ratio_at_tick(-887272)?,
+                get_sqrt_ratio_at_tick(887272)?,
+                U256::MAX >> 80,
+            )?,
+            5192492031145923673108348418329345
+        );
+
+        //the liquidity itself does not fit in 256 bits
+        assert!(matches!(
+            get_liquidity_for_amount0(
+                get_sqrt_ratio_at_tick(-600)?,
+                get_sqrt_ratio_at_tick(600)?,
+                U256::MAX
+            ),
+            Err(UniswapV3MathError::DenominatorIsLteProdOne)
+        ));
+        //it fits in 256 bits but not in 128
+        assert!(matches!(
+            get_liquidity_for_amount0(get_sqrt_ratio_at_tick(-887272)?, Q96, U256::MAX),
+            Err(UniswapV3MathError::SafeCastToU128Overflow)
+        ));
+        Ok(())
+    }
+}
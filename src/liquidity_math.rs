@@ -3,10 +3,13 @@ use crate::error::UniswapV3MathError;
 // returns (uint128 z)
 pub fn add_delta(x: u128, y: i128) -> Result<u128, UniswapV3MathError> {
     if y < 0 {
-        let z = x.overflowing_sub(-y as u128);
+        let z = x.overflowing_sub(y.unsigned_abs());
 
         if z.1 {
-            Err(UniswapV3MathError::LiquiditySub)
+            Err(UniswapV3MathError::LiquidityUnderflow {
+                current: x,
+                delta: y,
+            })
         } else {
             Ok(z.0)
         }
@@ -20,10 +23,20 @@ pub fn add_delta(x: u128, y: i128) -> Result<u128, UniswapV3MathError> {
     }
 }
 
+// returns (uint128 z), x - y for removing liquidity without converting y to a negative i128
+pub fn sub_delta(x: u128, y: u128) -> Result<u128, UniswapV3MathError> {
+    x.checked_sub(y)
+        .ok_or(UniswapV3MathError::LiquiditySubUnderflow {
+            current: x,
+            delta: y,
+        })
+}
+
 #[cfg(test)]
 mod test {
 
-    use crate::liquidity_math::add_delta;
+    use crate::error::UniswapV3MathError;
+    use crate::liquidity_math::{add_delta, sub_delta};
 
     #[test]
     fn test_add_delta() {
@@ -45,10 +58,73 @@ mod test {
 
         // 0 + -1 underflows
         let result = add_delta(0, -1);
-        assert_eq!(result.err().unwrap().to_string(), "Liquidity Sub");
+        assert!(matches!(
+            result,
+            Err(UniswapV3MathError::LiquidityUnderflow {
+                current: 0,
+                delta: -1
+            })
+        ));
 
         // 3 + -4 underflows
         let result = add_delta(3, -4);
-        assert_eq!(result.err().unwrap().to_string(), "Liquidity Sub");
+        assert!(matches!(
+            result,
+            Err(UniswapV3MathError::LiquidityUnderflow {
+                current: 3,
+                delta: -4
+            })
+        ));
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Liquidity underflow when adding -4 to 3"
+        );
+
+        // 2**128-1 + -2**127
+        let result = add_delta(u128::MAX, i128::MIN);
+        assert_eq!(result.unwrap(), (1 << 127) - 1);
+    }
+
+    #[test]
+    fn test_sub_delta() {
+        // 3 - 1
+        let result = sub_delta(3, 1);
+        assert_eq!(result.unwrap(), 2);
+
+        // 1 - 1 exactly to zero
+        let result = sub_delta(1, 1);
+        assert_eq!(result.unwrap(), 0);
+
+        // 2**128-1 - 2**128-1, y does not fit an i128
+        let result = sub_delta(u128::MAX, u128::MAX);
+        assert_eq!(result.unwrap(), 0);
+
+        // 2**128-1 - 2**127
+        let result = sub_delta(u128::MAX, 1 << 127);
+        assert_eq!(result.unwrap(), (1 << 127) - 1);
+
+        // 0 - 1 underflows
+        let result = sub_delta(0, 1);
+        assert!(matches!(
+            result,
+            Err(UniswapV3MathError::LiquiditySubUnderflow {
+                current: 0,
+                delta: 1
+            })
+        ));
+
+        // 3 - 4 underflows
+        let result = sub_delta(3, 4);
+        assert!(matches!(
+            result,
+            Err(UniswapV3MathError::LiquiditySubUnderflow {
+                current: 3,
+                delta: 4
+            })
+        ));
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Liquidity underflow when subtracting 4 from 3"
+        );
     }
 }
@@ -0,0 +1,464 @@
+use crate::error::UniswapV3MathError;
+use crate::pool::Pool;
+use crate::swap::{Amount, SwapResult};
+use alloy::primitives::U256;
+
+// an oracle observation, Oracle.Observation. Timestamps are uint32 seconds that wrap every ~136 years and the
+// accumulators are unchecked in Solidity, so all the arithmetic on them wraps at the width of the Solidity type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Observation {
+    // the block timestamp of the observation, modulo 2**32
+    pub block_timestamp: u32,
+    // the tick accumulator, tick * time elapsed since the pool was first initialized, an int56
+    pub tick_cumulative: i64,
+    // the seconds per liquidity, seconds elapsed / max(1, liquidity) since the pool was first initialized, a uint160
+    pub seconds_per_liquidity_cumulative_x_128: U256,
+    // whether or not the observation is initialized
+    pub initialized: bool,
+}
+
+impl Observation {
+    //Returns the observation at `block_timestamp` following this one with `tick` and `liquidity` in between,
+    //Oracle.transform. The time elapsed wraps over the uint32 overflow of the timestamp
+    pub fn transform(&self, block_timestamp: u32, tick: i32, liquidity: u128) -> Observation {
+        let delta = block_timestamp.wrapping_sub(self.block_timestamp);
+        Observation {
+            block_timestamp,
+            tick_cumulative: wrap_i56(
+                self.tick_cumulative
+                    .wrapping_add(tick as i64 * delta as i64),
+            ),
+            seconds_per_liquidity_cumulative_x_128: (self.seconds_per_liquidity_cumulative_x_128
+                + (U256::from(delta) << 128) / U256::from(liquidity.max(1)))
+                & U160_MAX,
+            initialized: true,
+        }
+    }
+}
+
+const U160_MAX: U256 = U256::from_limbs([u64::MAX, u64::MAX, u32::MAX as u64, 0]);
+
+// truncates to an int56 like the unchecked Solidity arithmetic
+fn wrap_i56(value: i64) -> i64 {
+    (value << 8) >> 8
+}
+
+//Comparator for timestamps that may have wrapped, Oracle.lte. `time` is the current timestamp and a, b are at most
+//2**32 seconds before it, a timestamp above `time` is from before the last wrap
+pub fn lte(time: u32, a: u32, b: u32) -> bool {
+    if a <= time && b <= time {
+        return a <= b;
+    }
+    let a_adjusted = if a > time {
+        a as u64
+    } else {
+        a as u64 + (1 << 32)
+    };
+    let b_adjusted = if b > time {
+        b as u64
+    } else {
+        b as u64 + (1 << 32)
+    };
+    a_adjusted <= b_adjusted
+}
+
+//Returns the time weighted average tick between two observations, rounded towards negative infinity like
+//OracleLibrary.consult. Both differences wrap, so the average is correct across a timestamp or accumulator overflow
+pub fn time_weighted_average_tick(
+    older: &Observation,
+    newer: &Observation,
+) -> Result<i32, UniswapV3MathError> {
+    let seconds = newer.block_timestamp.wrapping_sub(older.block_timestamp) as i64;
+    if seconds == 0 {
+        return Err(UniswapV3MathError::DenominatorIsZero);
+    }
+    let tick_cumulative_delta = wrap_i56(newer.tick_cumulative.wrapping_sub(older.tick_cumulative));
+    let mut tick = tick_cumulative_delta / seconds;
+    if tick_cumulative_delta < 0 && tick_cumulative_delta % seconds != 0 {
+        tick -= 1;
+    }
+    Ok(tick as i32)
+}
+
+// a pool with the ring buffer of observations of UniswapV3Pool, written by swap_and_record and read by observe and
+// twap. Built by Pool::with_observations
+#[derive(Debug, Clone)]
+pub struct ObservedPool {
+    pool: Pool,
+    // the ring buffer, all of it allocated up front like after increaseObservationCardinalityNext
+    observations: Vec<Observation>,
+    // the most recently written observation
+    index: usize,
+}
+
+impl ObservedPool {
+    //Wraps the pool with room for `cardinality` observations, at least one, and writes the first one at `time` as
+    //Oracle.initialize
+    pub fn new(pool: Pool, cardinality: u16, time: u32) -> Self {
+        let mut observations = vec![Observation::default(); cardinality.max(1) as usize];
+        observations[0] = Observation {
+            block_timestamp: time,
+            initialized: true,
+            ..Default::default()
+        };
+        ObservedPool {
+            pool,
+            observations,
+            index: 0,
+        }
+    }
+
+    pub fn pool(&self) -> &Pool {
+        &self.pool
+    }
+
+    pub fn into_pool(self) -> Pool {
+        self.pool
+    }
+
+    pub fn observations(&self) -> &[Observation] {
+        &self.observations
+    }
+
+    //Swaps against the pool at `time`. As in UniswapV3Pool.swap an observation of the tick and liquidity before the
+    //swap is written if the swap moves the tick, at most one per timestamp
+    pub fn swap_and_record(
+        &mut self,
+        time: u32,
+        zero_for_one: bool,
+        amount_specified: impl Into<Amount>,
+        sqrt_price_limit: U256,
+    ) -> Result<SwapResult, UniswapV3MathError> {
+        let tick = self.pool.current_tick();
+        let liquidity = self.pool.current_liquidity();
+        let result = self
+            .pool
+            .swap(zero_for_one, amount_specified, sqrt_price_limit)?;
+        if result.tick_after != tick {
+            self.write(time, tick, liquidity);
+        }
+        Ok(result)
+    }
+
+    // Oracle.write, the buffer is always at its full cardinality
+    fn write(&mut self, time: u32, tick: i32, liquidity: u128) {
+        let last = self.observations[self.index];
+        if last.block_timestamp == time {
+            return;
+        }
+        self.index = (self.index + 1) % self.observations.len();
+        self.observations[self.index] = last.transform(time, tick, liquidity);
+    }
+
+    //Returns the observation `seconds_ago` before `time`, Oracle.observeSingle. Between two stored observations the
+    //accumulators are interpolated, after the last one they are extrapolated with the current tick and liquidity.
+    //Fails with OLD if the target is before the oldest stored observation
+    pub fn observe(&self, time: u32, seconds_ago: u32) -> Result<Observation, UniswapV3MathError> {
+        let tick = self.pool.current_tick();
+        let liquidity = self.pool.current_liquidity();
+        let last = self.observations[self.index];
+        let target = time.wrapping_sub(seconds_ago);
+
+        // at or after the newest observation
+        if lte(time, last.block_timestamp, target) {
+            if last.block_timestamp == target {
+                return Ok(last);
+            }
+            return Ok(last.transform(target, tick, liquidity));
+        }
+
+        let cardinality = self.observations.len();
+        let mut oldest = self.observations[(self.index + 1) % cardinality];
+        if !oldest.initialized {
+            oldest = self.observations[0];
+        }
+        if !lte(time, oldest.block_timestamp, target) {
+            return Err(UniswapV3MathError::OLD);
+        }
+
+        let (before, at) = self.binary_search(time, target);
+        if target == before.block_timestamp {
+            return Ok(before);
+        }
+        if target == at.block_timestamp {
+            return Ok(at);
+        }
+        let observation_time_delta = at.block_timestamp.wrapping_sub(before.block_timestamp);
+        let target_delta = target.wrapping_sub(before.block_timestamp);
+        let tick_cumulative_delta =
+            wrap_i56(at.tick_cumulative.wrapping_sub(before.tick_cumulative));
+        let seconds_per_liquidity_delta = at
+            .seconds_per_liquidity_cumulative_x_128
+            .wrapping_sub(before.seconds_per_liquidity_cumulative_x_128)
+            & U160_MAX;
+        Ok(Observation {
+            block_timestamp: target,
+            tick_cumulative: wrap_i56(before.tick_cumulative.wrapping_add(
+                tick_cumulative_delta / observation_time_delta as i64 * target_delta as i64,
+            )),
+            seconds_per_liquidity_cumulative_x_128: (before.seconds_per_liquidity_cumulative_x_128
+                + (seconds_per_liquidity_delta * U256::from(target_delta)
+                    / U256::from(observation_time_delta)
+                    & U160_MAX))
+                & U160_MAX,
+            initialized: true,
+        })
+    }
+
+    // Oracle.binarySearch, the observations at or before and at or after the target, which is known to be within
+    // the stored observations
+    fn binary_search(&self, time: u32, target: u32) -> (Observation, Observation) {
+        let cardinality = self.observations.len();
+        // oldest and newest observations
+        let mut l = (self.index + 1) % cardinality;
+        let mut r = l + cardinality - 1;
+        loop {
+            let i = (l + r) / 2;
+            let before = self.observations[i % cardinality];
+            // we've landed on an uninitialized tick, keep searching higher (more recently)
+            if !before.initialized {
+                l = i + 1;
+                continue;
+            }
+            let at = self.observations[(i + 1) % cardinality];
+            let target_at_or_after = lte(time, before.block_timestamp, target);
+            // check if we've found the answer
+            if target_at_or_after && lte(time, target, at.block_timestamp) {
+                return (before, at);
+            }
+            if !target_at_or_after {
+                r = i - 1;
+            } else {
+                l = i + 1;
+            }
+        }
+    }
+
+    //Returns the time weighted average tick over the `seconds_ago` seconds before `time`, see
+    //time_weighted_average_tick
+    pub fn twap(&self, time: u32, seconds_ago: u32) -> Result<i32, UniswapV3MathError> {
+        let older = self.observe(time, seconds_ago)?;
+        let newer = self.observe(time, 0)?;
+        time_weighted_average_tick(&older, &newer)
+    }
+}
+
+// the ticks after a series of swaps with the time they happened at, a simpler alternative to the observations for
+// off-chain tooling that only needs averages over recent swaps
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PriceLog {
+    // (timestamp, tick) in ascending order of timestamp
+    entries: Vec<(u64, i32)>,
+}
+
+impl PriceLog {
+    pub fn new() -> Self {
+        PriceLog::default()
+    }
+
+    //Records the tick at `timestamp`, an entry older than the last one is inserted in timestamp order
+    pub fn record(&mut self, timestamp: u64, tick: i32) {
+        let index = self
+            .entries
+            .partition_point(|(entry_timestamp, _)| *entry_timestamp <= timestamp);
+        self.entries.insert(index, (timestamp, tick));
+    }
+
+    //Records the tick the swap left the pool at
+    pub fn record_swap(&mut self, timestamp: u64, result: &SwapResult) {
+        self.record(timestamp, result.tick_after);
+    }
+
+    pub fn entries(&self) -> &[(u64, i32)] {
+        &self.entries
+    }
+
+    //Returns the simple moving average of the ticks recorded in the last `window_secs` seconds up to and including
+    //the latest entry, rounded towards negative infinity, or None if nothing was recorded. Every swap weighs the
+    //same regardless of how long the price stayed there, see time_weighted_average_tick for a time weighted average
+    pub fn sma(&self, window_secs: u64) -> Option<i32> {
+        let (latest, _) = *self.entries.last()?;
+        let start = latest.saturating_sub(window_secs);
+        let window = &self.entries[self
+            .entries
+            .partition_point(|(timestamp, _)| *timestamp < start)..];
+        let sum: i64 = window.iter().map(|(_, tick)| *tick as i64).sum();
+        Some(sum.div_euclid(window.len() as i64) as i32)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{lte, time_weighted_average_tick, Observation, PriceLog, U160_MAX};
+    use crate::{
+        error::UniswapV3MathError, pool::Pool, sqrt_price_math::Q96, swap::Slot0, tick_math,
+    };
+    use alloy::primitives::U256;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_transform_across_timestamp_overflow() -> eyre::Result<()> {
+        let last = Observation {
+            block_timestamp: u32::MAX - 9,
+            tick_cumulative: 1000,
+            seconds_per_liquidity_cumulative_x_128: U256::from(7),
+            initialized: true,
+        };
+        //15 seconds later, 5 seconds after the wrap
+        let next = last.transform(5, 100, 1 << 64);
+        assert_eq!(next.block_timestamp, 5);
+        assert_eq!(next.tick_cumulative, 1000 + 100 * 15);
+        assert_eq!(
+            next.seconds_per_liquidity_cumulative_x_128,
+            U256::from(7) + (U256::from(15) << 64)
+        );
+        assert!(next.initialized);
+
+        //right on the boundary
+        let at_wrap = last.transform(0, -100, 0);
+        assert_eq!(at_wrap.tick_cumulative, 1000 - 100 * 10);
+        assert_eq!(
+            at_wrap.seconds_per_liquidity_cumulative_x_128,
+            U256::from(7) + (U256::from(10) << 128)
+        );
+
+        assert_eq!(time_weighted_average_tick(&last, &next)?, 100);
+        assert_eq!(time_weighted_average_tick(&last, &at_wrap)?, -100);
+        Ok(())
+    }
+
+    #[test]
+    fn test_transform_accumulator_overflow() -> eyre::Result<()> {
+        let int56_max = (1_i64 << 55) - 1;
+        let last = Observation {
+            block_timestamp: 0,
+            tick_cumulative: int56_max,
+            seconds_per_liquidity_cumulative_x_128: U160_MAX,
+            initialized: true,
+        };
+        let next = last.transform(1, 1, 1);
+        assert_eq!(next.tick_cumulative, -(1_i64 << 55));
+        assert_eq!(
+            next.seconds_per_liquidity_cumulative_x_128,
+            (U256::from(1) << 128) - U256::from(1)
+        );
+        //the difference of the wrapped accumulators is still the tick
+        assert_eq!(time_weighted_average_tick(&last, &next)?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_time_weighted_average_tick() -> eyre::Result<()> {
+        let older = Observation::default();
+        //-15 over 4 seconds rounds down to -4
+        let newer = Observation {
+            block_timestamp: 4,
+            tick_cumulative: -15,
+            ..Default::default()
+        };
+        assert_eq!(time_weighted_average_tick(&older, &newer)?, -4);
+        let newer = Observation {
+            block_timestamp: 4,
+            tick_cumulative: 15,
+            ..Default::default()
+        };
+        assert_eq!(time_weighted_average_tick(&older, &newer)?, 3);
+        assert!(matches!(
+            time_weighted_average_tick(&older, &older),
+            Err(UniswapV3MathError::DenominatorIsZero)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_lte() {
+        assert!(lte(100, 10, 20));
+        assert!(!lte(100, 20, 10));
+        assert!(lte(100, 10, 10));
+        //a timestamp above the current time is from before the wrap
+        assert!(lte(5, u32::MAX, 3));
+        assert!(!lte(5, 3, u32::MAX));
+        assert!(lte(5, u32::MAX - 1, u32::MAX));
+        assert!(lte(5, u32::MAX, 5));
+    }
+
+    #[test]
+    fn test_observed_pool_twap() -> eyre::Result<()> {
+        let pool = Pool::new(
+            HashMap::new(),
+            HashMap::new(),
+            60,
+            3000,
+            Slot0 {
+                sqrt_price: Q96,
+                liquidity: 1_000_000_000_000_000_000,
+                tick: 0,
+            },
+        )?;
+        let sqrt_price_limit = tick_math::MIN_SQRT_RATIO + U256::from(1);
+        let mut observed = pool.clone().with_observations(10, 1000);
+        //at tick 0 from 1000, -199 from 1100 and -395 from 1200
+        observed.swap_and_record(1100, true, 10_000_000_000_000_000u128, sqrt_price_limit)?;
+        observed.swap_and_record(1200, true, 10_000_000_000_000_000u128, sqrt_price_limit)?;
+        assert_eq!(observed.pool().current_tick(), -395);
+        assert_eq!(observed.observe(1200, 0)?.tick_cumulative, -199 * 100);
+
+        //(0 * 100 - 199 * 100 - 395 * 100) / 300
+        assert_eq!(observed.twap(1300, 300)?, -198);
+        //interpolated at 1150 between the observations at 1100 and 1200, (-199 * 50 - 395 * 50) / 100
+        assert_eq!(observed.observe(1250, 100)?.tick_cumulative, -199 * 50);
+        assert_eq!(observed.twap(1250, 100)?, -297);
+        assert!(matches!(
+            observed.twap(1300, 301),
+            Err(UniswapV3MathError::OLD)
+        ));
+
+        //a swap that does not move the tick writes nothing
+        observed.swap_and_record(1250, true, 1u128, sqrt_price_limit)?;
+        assert_eq!(
+            observed
+                .observations()
+                .iter()
+                .filter(|o| o.initialized)
+                .count(),
+            3
+        );
+
+        //with room for two observations the first one is overwritten
+        let mut observed = pool.with_observations(2, 1000);
+        observed.swap_and_record(1100, true, 10_000_000_000_000_000u128, sqrt_price_limit)?;
+        observed.swap_and_record(1200, true, 10_000_000_000_000_000u128, sqrt_price_limit)?;
+        assert!(matches!(
+            observed.twap(1300, 300),
+            Err(UniswapV3MathError::OLD)
+        ));
+        //(-199 * 100 - 395 * 100) / 200
+        assert_eq!(observed.twap(1300, 200)?, -297);
+        Ok(())
+    }
+
+    #[test]
+    fn test_price_log_sma() {
+        let mut log = PriceLog::new();
+        assert_eq!(log.sma(60), None);
+
+        for (timestamp, tick) in [(100, 10), (130, 20), (160, -40), (190, 5)] {
+            log.record(timestamp, tick);
+        }
+        //a late entry goes before the ones after it
+        log.record(120, 25);
+        assert_eq!(
+            log.entries(),
+            &[(100_u64, 10), (120, 25), (130, 20), (160, -40), (190, 5)]
+        );
+
+        //130, 160 and 190, the window includes its start
+        assert_eq!(log.sma(60), Some(-5));
+        //all of them, (10 + 25 + 20 - 40 + 5) / 5
+        assert_eq!(log.sma(1000), Some(4));
+        //only the latest entry
+        assert_eq!(log.sma(0), Some(5));
+        //160 and 190, -35 / 2 rounds down
+        assert_eq!(log.sma(30), Some(-18));
+    }
+}
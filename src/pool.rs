@@ -0,0 +1,2546 @@
+use crate::error::UniswapV3MathError;
+use crate::fee_tier::FeeTier;
+use crate::full_math::{mul_div, mul_div_rounding_up};
+use crate::liquidity_amounts::get_amounts_for_liquidity;
+use crate::liquidity_math;
+use crate::oracle::ObservedPool;
+use crate::position::Position;
+use crate::quoter::quote_exact_input_single;
+use crate::sqrt_price_math::{_get_amount_0_delta, _get_amount_1_delta, Q96};
+use crate::swap::{
+    exact_swap_params, swap, unsigned_swap_result, Amount, Slot0, SwapAmount, SwapDirection,
+    SwapResult, UnsignedSwapResult,
+};
+use crate::swap_event::SwapEventData;
+use crate::tick::{get_fee_growth_inside, tick_spacing_to_max_liquidity_per_tick, Tick};
+use crate::tick_bitmap::{flip_tick, next_initialized_tick_within_one_word, position};
+use crate::tick_math;
+use crate::tick_set::TickSet;
+use crate::U256_1;
+use alloy::primitives::{I256, U256};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// the swap relevant state of a pool: the initialized ticks, the tick bitmap and slot0
+#[derive(Debug, Clone)]
+pub struct Pool {
+    // shared between clones of the pool and copied on the first write, see clone_for_simulation
+    ticks: Arc<HashMap<i32, Tick>>,
+    tick_bitmap: Arc<HashMap<i16, U256>>,
+    tick_spacing: i32,
+    // the pool fee in hundredths of a bip, i.e. 1e-6
+    fee: u32,
+    slot0: Slot0,
+    // the all-time fee growth per unit of liquidity of token0 and token1, these wrap on overflow
+    fee_growth_global_0_x_128: U256,
+    fee_growth_global_1_x_128: U256,
+}
+
+// how mint or burn changed one of the ticks of the range, so an integrator mirroring the pool in storage only
+// writes or deletes what changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickUpdate {
+    pub tick: i32,
+    // the tick was initialized or uninitialized, its bit in the tick bitmap was flipped
+    pub flipped: bool,
+    // the tick was left without liquidity and removed from the ticks, its storage can be deleted
+    pub removed: bool,
+}
+
+// a copy of the state of a pool, e.g. before and after applying a swap or as fetched from chain
+#[derive(Debug, Clone)]
+pub struct PoolSnapshot {
+    pub ticks: HashMap<i32, Tick>,
+    pub tick_bitmap: HashMap<i16, U256>,
+    pub slot0: Slot0,
+}
+
+// the mutable state of a pool saved by checkpoint. The ticks and the tick bitmap are shared with the pool rather than
+// copied, the pool only copies them if it writes to them before being restored
+#[derive(Debug, Clone)]
+pub struct PoolState {
+    ticks: Arc<HashMap<i32, Tick>>,
+    tick_bitmap: Arc<HashMap<i16, U256>>,
+    slot0: Slot0,
+    fee_growth_global_0_x_128: U256,
+    fee_growth_global_1_x_128: U256,
+}
+
+// the differences between two snapshots, slot0 fields are reported as (before, after)
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct PoolDiff {
+    // ticks that were added, removed or changed, in ascending order
+    pub ticks: Vec<i32>,
+    // bitmap words whose value changed, a missing word is equivalent to a zero word
+    pub tick_bitmap_words: Vec<i16>,
+    pub sqrt_price: Option<(U256, U256)>,
+    pub liquidity: Option<(u128, u128)>,
+    pub tick: Option<(i32, i32)>,
+}
+
+impl PoolDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == PoolDiff::default()
+    }
+}
+
+impl PoolSnapshot {
+    pub fn diff(&self, other: &PoolSnapshot) -> PoolDiff {
+        let mut ticks: Vec<i32> = self
+            .ticks
+            .keys()
+            .chain(other.ticks.keys())
+            .filter(|&&tick| self.ticks.get(&tick) != other.ticks.get(&tick))
+            .copied()
+            .collect();
+        ticks.sort_unstable();
+        ticks.dedup();
+
+        let mut tick_bitmap_words: Vec<i16> = self
+            .tick_bitmap
+            .keys()
+            .chain(other.tick_bitmap.keys())
+            .filter(|&&word_pos| {
+                self.tick_bitmap.get(&word_pos).unwrap_or(&U256::ZERO)
+                    != other.tick_bitmap.get(&word_pos).unwrap_or(&U256::ZERO)
+            })
+            .copied()
+            .collect();
+        tick_bitmap_words.sort_unstable();
+        tick_bitmap_words.dedup();
+
+        PoolDiff {
+            ticks,
+            tick_bitmap_words,
+            sqrt_price: (self.slot0.sqrt_price != other.slot0.sqrt_price)
+                .then_some((self.slot0.sqrt_price, other.slot0.sqrt_price)),
+            liquidity: (self.slot0.liquidity != other.slot0.liquidity)
+                .then_some((self.slot0.liquidity, other.slot0.liquidity)),
+            tick: (self.slot0.tick != other.slot0.tick)
+                .then_some((self.slot0.tick, other.slot0.tick)),
+        }
+    }
+}
+
+impl Pool {
+    //Builds a pool from its ticks and tick bitmap, with no fees collected yet. Fails with TickSpacingError for a tick
+    //spacing that is not positive
+    pub fn new(
+        ticks: HashMap<i32, Tick>,
+        tick_bitmap: HashMap<i16, U256>,
+        tick_spacing: i32,
+        fee: u32,
+        slot0: Slot0,
+    ) -> Result<Self, UniswapV3MathError> {
+        if tick_spacing <= 0 {
+            return Err(UniswapV3MathError::TickSpacingError);
+        }
+        Ok(Pool {
+            ticks: Arc::new(ticks),
+            tick_bitmap: Arc::new(tick_bitmap),
+            tick_spacing,
+            fee,
+            slot0,
+            fee_growth_global_0_x_128: U256::ZERO,
+            fee_growth_global_1_x_128: U256::ZERO,
+        })
+    }
+
+    //Builds a pool from its ticks, deriving the tick bitmap from the initialized ones. Fails with the first tick, in
+    //ascending order, that is not a multiple of the tick spacing, as it could never be reached by a swap
+    pub fn from_ticks(
+        ticks: HashMap<i32, Tick>,
+        tick_spacing: i32,
+        fee: u32,
+        slot0: Slot0,
+    ) -> Result<Self, UniswapV3MathError> {
+        if tick_spacing <= 0 {
+            return Err(UniswapV3MathError::TickSpacingError);
+        }
+        let mut initialized_ticks: Vec<i32> = ticks
+            .iter()
+            .filter(|(_, info)| info.initialized)
+            .map(|(tick, _)| *tick)
+            .collect();
+        initialized_ticks.sort_unstable();
+
+        let mut tick_bitmap = HashMap::new();
+        for tick in initialized_ticks {
+            if tick % tick_spacing != 0 {
+                return Err(UniswapV3MathError::MisalignedTick { tick, tick_spacing });
+            }
+            flip_tick(&mut tick_bitmap, tick, tick_spacing)?;
+        }
+        Pool::new(ticks, tick_bitmap, tick_spacing, fee, slot0)
+    }
+
+    //Builds a pool from its ticks with the fee and tick spacing of a factory fee tier, see from_ticks
+    pub fn from_fee_tier(
+        ticks: HashMap<i32, Tick>,
+        fee_tier: FeeTier,
+        slot0: Slot0,
+    ) -> Result<Self, UniswapV3MathError> {
+        Pool::from_ticks(ticks, fee_tier.tick_spacing(), fee_tier.fee(), slot0)
+    }
+
+    pub fn get_tick(&self, tick: i32) -> Option<&Tick> {
+        self.ticks.get(&tick)
+    }
+
+    // the liquidity_net of the tick, zero if it is not initialized
+    pub fn liquidity_net_at(&self, tick: i32) -> i128 {
+        self.ticks
+            .get(&tick)
+            .filter(|info| info.initialized)
+            .map_or(0, |info| info.liquidity_net)
+    }
+
+    // the initialized ticks in arbitrary order
+    pub fn ticks_iter(&self) -> impl Iterator<Item = (&i32, &Tick)> {
+        self.ticks.iter()
+    }
+
+    // the ticks and the tick bitmap, iterating over the ticks in ascending order without copying them
+    pub fn tick_set(&self) -> TickSet<'_> {
+        TickSet::new(&self.ticks, &self.tick_bitmap)
+    }
+
+    // the pool fee in hundredths of a bip
+    pub fn fee(&self) -> u32 {
+        self.fee
+    }
+
+    pub fn current_tick(&self) -> i32 {
+        self.slot0.tick
+    }
+
+    pub fn current_liquidity(&self) -> u128 {
+        self.slot0.liquidity
+    }
+
+    pub fn current_sqrt_price(&self) -> U256 {
+        self.slot0.sqrt_price
+    }
+
+    //Returns the position of the bitmap word holding the current tick and the word, zero if no tick in it is
+    //initialized. The word is the one searched first by a swap from the current tick
+    pub fn current_word(&self) -> (i16, U256) {
+        let (word_pos, _) = position(self.slot0.tick.div_euclid(self.tick_spacing));
+        (
+            word_pos,
+            self.tick_bitmap.get(&word_pos).copied().unwrap_or_default(),
+        )
+    }
+
+    // returns (fee_growth_global_0_x_128, fee_growth_global_1_x_128)
+    pub fn fee_growth_global(&self) -> (U256, U256) {
+        (
+            self.fee_growth_global_0_x_128,
+            self.fee_growth_global_1_x_128,
+        )
+    }
+
+    pub fn set_fee_growth_global(
+        &mut self,
+        fee_growth_global_0_x_128: U256,
+        fee_growth_global_1_x_128: U256,
+    ) {
+        self.fee_growth_global_0_x_128 = fee_growth_global_0_x_128;
+        self.fee_growth_global_1_x_128 = fee_growth_global_1_x_128;
+    }
+
+    //Moves slot0 to the state a Swap event left the pool in, to keep a local pool in sync with the chain without
+    //replaying the swap. Fails with InconsistentTick without changing the pool if the tick does not match the price.
+    //Only slot0 is updated, the ticks crossed by the swap keep their fee growth outside
+    pub fn apply_event(&mut self, event: &SwapEventData) -> Result<(), UniswapV3MathError> {
+        self.slot0 = Slot0::new(event.sqrt_price_x96, event.liquidity, event.tick)?;
+        Ok(())
+    }
+
+    //Attaches the observation ring buffer of the oracle with room for `cardinality` observations, the first one
+    //written at `time`, see ObservedPool
+    pub fn with_observations(self, cardinality: u16, time: u32) -> ObservedPool {
+        ObservedPool::new(self, cardinality, time)
+    }
+
+    //Returns an independently mutable copy of the pool for what-if simulations. The ticks and the tick bitmap are
+    //shared with the original until the copy writes to them: minting and burning copy them, and so does a swap that
+    //crosses an initialized tick, as it flips the tick's fee growth outside. Cloning, simulating and swapping within
+    //the initialized ticks around the price are cheap regardless of the number of ticks
+    pub fn clone_for_simulation(&self) -> Pool {
+        self.clone()
+    }
+
+    // returns (fee_growth_inside_0_x_128, fee_growth_inside_1_x_128) of the range at the current tick
+    pub fn fee_growth_inside(&self, tick_lower: i32, tick_upper: i32) -> (U256, U256) {
+        get_fee_growth_inside(
+            &self.ticks,
+            tick_lower,
+            tick_upper,
+            self.slot0.tick,
+            self.fee_growth_global_0_x_128,
+            self.fee_growth_global_1_x_128,
+        )
+    }
+
+    pub fn snapshot(&self) -> PoolSnapshot {
+        PoolSnapshot {
+            ticks: self.ticks.as_ref().clone(),
+            tick_bitmap: self.tick_bitmap.as_ref().clone(),
+            slot0: self.slot0.clone(),
+        }
+    }
+
+    //Saves the state of the pool so it can be rolled back with restore after trying out swaps, mints or burns. Unlike
+    //snapshot this copies nothing but slot0 and the fee growth, the ticks are only copied if the pool later changes them
+    pub fn checkpoint(&self) -> PoolState {
+        PoolState {
+            ticks: Arc::clone(&self.ticks),
+            tick_bitmap: Arc::clone(&self.tick_bitmap),
+            slot0: self.slot0.clone(),
+            fee_growth_global_0_x_128: self.fee_growth_global_0_x_128,
+            fee_growth_global_1_x_128: self.fee_growth_global_1_x_128,
+        }
+    }
+
+    //Rolls the pool back to a state saved by checkpoint
+    pub fn restore(&mut self, state: PoolState) {
+        self.ticks = state.ticks;
+        self.tick_bitmap = state.tick_bitmap;
+        self.slot0 = state.slot0;
+        self.fee_growth_global_0_x_128 = state.fee_growth_global_0_x_128;
+        self.fee_growth_global_1_x_128 = state.fee_growth_global_1_x_128;
+    }
+
+    //Cross-checks the whole state of the pool, e.g. after loading it from an indexer, and returns the first
+    //inconsistency found. The tick spacing has to be positive, every tick has to be valid and initialized exactly when
+    //it holds liquidity, the initialized ticks have to be exactly the ones set in the tick bitmap, slot0.tick has to
+    //contain slot0.sqrt_price and slot0.liquidity has to be the sum of the liquidity_net of the initialized ticks at
+    //or below slot0.tick. Ticks are checked in ascending order, so the error is always the same one
+    pub fn validate(&self) -> Result<(), UniswapV3MathError> {
+        if self.tick_spacing <= 0 {
+            return Err(UniswapV3MathError::TickSpacingError);
+        }
+
+        let mut ticks: Vec<(i32, &Tick)> = self
+            .ticks
+            .iter()
+            .map(|(tick, info)| (*tick, info))
+            .collect();
+        ticks.sort_unstable_by_key(|(tick, _)| *tick);
+        for (tick, info) in &ticks {
+            Tick::new(info.liquidity_gross, info.liquidity_net)?;
+            if info.initialized != (info.liquidity_gross != 0) {
+                return Err(UniswapV3MathError::TickInitializedMismatch(*tick));
+            }
+            if !info.initialized {
+                continue;
+            }
+            let (word_pos, bit_pos) = position(tick / self.tick_spacing);
+            let word = self.tick_bitmap.get(&word_pos).copied().unwrap_or_default();
+            if tick % self.tick_spacing != 0 || !word.bit(bit_pos as usize) {
+                return Err(UniswapV3MathError::TickNotInBitmap(*tick));
+            }
+        }
+
+        let mut words: Vec<(i16, U256)> = self
+            .tick_bitmap
+            .iter()
+            .map(|(word_pos, word)| (*word_pos, *word))
+            .collect();
+        words.sort_unstable_by_key(|(word_pos, _)| *word_pos);
+        for (word_pos, word) in words {
+            for bit_pos in (0..256).filter(|bit_pos| word.bit(*bit_pos)) {
+                // a bit of a corrupted bitmap can be beyond any tick
+                let tick = (word_pos as i32 * 256 + bit_pos as i32)
+                    .checked_mul(self.tick_spacing)
+                    .ok_or(UniswapV3MathError::Overflow)?;
+                if !self.get_tick(tick).is_some_and(|info| info.initialized) {
+                    return Err(UniswapV3MathError::UninitializedBitmapTick(tick));
+                }
+            }
+        }
+
+        self.slot0.tick_consistency_check()?;
+
+        let mut expected: i128 = 0;
+        for (_, info) in ticks
+            .iter()
+            .take_while(|(tick, _)| *tick <= self.slot0.tick)
+        {
+            // uninitialized ticks hold no liquidity_net, as checked above
+            expected = expected
+                .checked_add(info.liquidity_net)
+                .ok_or(UniswapV3MathError::Overflow)?;
+        }
+        if i128::try_from(self.slot0.liquidity).ok() != Some(expected) {
+            return Err(UniswapV3MathError::InconsistentLiquidity {
+                liquidity: self.slot0.liquidity,
+                expected,
+            });
+        }
+        Ok(())
+    }
+
+    //Checks that the fee and the tick spacing are one of the fee tiers enabled by the factory or one of the
+    //`allowed_custom` (fee, tick_spacing) pairs, e.g. a tier enabled by governance on a fork
+    pub fn validate_fee_tier(
+        &self,
+        allowed_custom: &[(u32, i32)],
+    ) -> Result<(), UniswapV3MathError> {
+        let standard = FeeTier::ALL
+            .iter()
+            .any(|tier| tier.fee() == self.fee && tier.tick_spacing() == self.tick_spacing);
+        if standard || allowed_custom.contains(&(self.fee, self.tick_spacing)) {
+            return Ok(());
+        }
+        Err(UniswapV3MathError::FeeTierMismatch {
+            fee: self.fee,
+            tick_spacing: self.tick_spacing,
+        })
+    }
+
+    //Adds `liquidity` to the range [tick_lower, tick_upper), initializing the ticks and flipping them in the bitmap
+    //if needed. By convention all fee growth before a tick is initialized happened below it, so new ticks at or below
+    //the current tick start with the fee growth global as their fee growth outside. Returns (amount0, amount1,
+    //tick_updates): the amounts owed for the liquidity rounded up and how the lower and upper tick changed. Fails with
+    //LO without changing the pool if a tick would hold more than the max liquidity per tick of the tick spacing, and
+    //with LiquidityIsZero for a zero `liquidity`, which UniswapV3Pool rejects as well
+    pub fn mint(
+        &mut self,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity: u128,
+    ) -> Result<(U256, U256, Vec<TickUpdate>), UniswapV3MathError> {
+        self.check_ticks(tick_lower, tick_upper)?;
+        if liquidity == 0 {
+            return Err(UniswapV3MathError::LiquidityIsZero);
+        }
+        let liquidity_delta =
+            i128::try_from(liquidity).map_err(|_| UniswapV3MathError::LiquidityAdd)?;
+        let max_liquidity_per_tick = tick_spacing_to_max_liquidity_per_tick(self.tick_spacing);
+        for tick in [tick_lower, tick_upper] {
+            let liquidity_gross_after = self
+                .ticks
+                .get(&tick)
+                .map_or(0, |info| info.liquidity_gross)
+                .checked_add(liquidity)
+                .ok_or(UniswapV3MathError::LiquidityAdd)?;
+            if liquidity_gross_after > max_liquidity_per_tick {
+                return Err(UniswapV3MathError::LO);
+            }
+        }
+
+        let mut tick_updates = Vec::with_capacity(2);
+        for (tick, liquidity_net_delta) in [
+            (tick_lower, liquidity_delta),
+            (tick_upper, -liquidity_delta),
+        ] {
+            let mut info = self.ticks.get(&tick).cloned().unwrap_or_default();
+            let flipped = info.liquidity_gross == 0;
+            if flipped && tick <= self.slot0.tick {
+                info.fee_growth_outside_0_x_128 = self.fee_growth_global_0_x_128;
+                info.fee_growth_outside_1_x_128 = self.fee_growth_global_1_x_128;
+            }
+            info.liquidity_gross = info
+                .liquidity_gross
+                .checked_add(liquidity)
+                .ok_or(UniswapV3MathError::LiquidityAdd)?;
+            info.liquidity_net = info
+                .liquidity_net
+                .checked_add(liquidity_net_delta)
+                .ok_or(UniswapV3MathError::LiquidityAdd)?;
+            info.initialized = true;
+            if flipped {
+                flip_tick(
+                    Arc::make_mut(&mut self.tick_bitmap),
+                    tick,
+                    self.tick_spacing,
+                )?;
+            }
+            Arc::make_mut(&mut self.ticks).insert(tick, info);
+            tick_updates.push(TickUpdate {
+                tick,
+                flipped,
+                removed: false,
+            });
+        }
+
+        if tick_lower <= self.slot0.tick && self.slot0.tick < tick_upper {
+            self.slot0.liquidity = self
+                .slot0
+                .liquidity
+                .checked_add(liquidity)
+                .ok_or(UniswapV3MathError::LiquidityAdd)?;
+        }
+        let (amount0, amount1) =
+            self.amounts_for_liquidity(tick_lower, tick_upper, liquidity, true)?;
+        Ok((amount0, amount1, tick_updates))
+    }
+
+    //Removes `liquidity` from the range [tick_lower, tick_upper). A tick left without liquidity is flipped in the
+    //bitmap and removed from the ticks, as UniswapV3Pool clears it. Returns (amount0, amount1, tick_updates): the
+    //amounts the liquidity is worth rounded down and how the lower and upper tick changed. Fails without changing
+    //the pool with LiquiditySubUnderflow if a tick or the in range liquidity holds less than `liquidity`
+    pub fn burn(
+        &mut self,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity: u128,
+    ) -> Result<(U256, U256, Vec<TickUpdate>), UniswapV3MathError> {
+        self.check_ticks(tick_lower, tick_upper)?;
+        let liquidity_delta =
+            i128::try_from(liquidity).map_err(|_| UniswapV3MathError::LiquiditySub)?;
+        let in_range = tick_lower <= self.slot0.tick && self.slot0.tick < tick_upper;
+
+        let mut updated = Vec::with_capacity(2);
+        for (tick, liquidity_net_delta) in [
+            (tick_lower, -liquidity_delta),
+            (tick_upper, liquidity_delta),
+        ] {
+            let mut info = self
+                .ticks
+                .get(&tick)
+                .filter(|info| info.initialized)
+                .cloned()
+                .ok_or(UniswapV3MathError::TickNotFound(tick))?;
+            info.liquidity_gross = liquidity_math::sub_delta(info.liquidity_gross, liquidity)?;
+            info.liquidity_net = info
+                .liquidity_net
+                .checked_add(liquidity_net_delta)
+                .ok_or(UniswapV3MathError::LiquiditySub)?;
+            updated.push((tick, info));
+        }
+        let liquidity_after = if in_range {
+            liquidity_math::sub_delta(self.slot0.liquidity, liquidity)?
+        } else {
+            self.slot0.liquidity
+        };
+        let (amount0, amount1) =
+            self.amounts_for_liquidity(tick_lower, tick_upper, liquidity, false)?;
+
+        let mut tick_updates = Vec::with_capacity(2);
+        for (tick, info) in updated {
+            let flipped = info.liquidity_gross == 0;
+            if flipped {
+                flip_tick(
+                    Arc::make_mut(&mut self.tick_bitmap),
+                    tick,
+                    self.tick_spacing,
+                )?;
+                Arc::make_mut(&mut self.ticks).remove(&tick);
+            } else {
+                Arc::make_mut(&mut self.ticks).insert(tick, info);
+            }
+            tick_updates.push(TickUpdate {
+                tick,
+                flipped,
+                removed: flipped,
+            });
+        }
+        self.slot0.liquidity = liquidity_after;
+        Ok((amount0, amount1, tick_updates))
+    }
+
+    //Exits a position in one call: credits the fees the position earned since its fee growth inside was last
+    //recorded, burns all of its `liquidity` and returns (amount0, amount1, fees0, fees1), the principal rounded down
+    //and the fees a collect of everything owed would transfer. The fee growth inside is read before the burn since
+    //burning the last liquidity of a tick clears it
+    pub fn burn_all(
+        &mut self,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity: u128,
+        fee_growth_inside_0_last_x_128: U256,
+        fee_growth_inside_1_last_x_128: U256,
+    ) -> Result<(U256, U256, U256, U256), UniswapV3MathError> {
+        let mut position = Position {
+            liquidity,
+            fee_growth_inside_0_last_x_128,
+            fee_growth_inside_1_last_x_128,
+            ..Default::default()
+        };
+        let (fee_growth_inside_0_x_128, fee_growth_inside_1_x_128) =
+            self.fee_growth_inside(tick_lower, tick_upper);
+        // a zero liquidity delta credits the fees without the LiquidityIsZero check of an empty poke
+        if liquidity > 0 {
+            position.update(0, fee_growth_inside_0_x_128, fee_growth_inside_1_x_128)?;
+        }
+        let (amount0, amount1, _) = self.burn(tick_lower, tick_upper, liquidity)?;
+        Ok((
+            amount0,
+            amount1,
+            position.tokens_owed_0,
+            position.tokens_owed_1,
+        ))
+    }
+
+    fn check_ticks(&self, tick_lower: i32, tick_upper: i32) -> Result<(), UniswapV3MathError> {
+        if tick_lower >= tick_upper {
+            return Err(UniswapV3MathError::InvalidTickRange);
+        }
+        if tick_lower < tick_math::MIN_TICK {
+            return Err(UniswapV3MathError::TickOutOfRange(tick_lower));
+        }
+        if tick_upper > tick_math::MAX_TICK {
+            return Err(UniswapV3MathError::TickOutOfRange(tick_upper));
+        }
+        if tick_lower % self.tick_spacing != 0 || tick_upper % self.tick_spacing != 0 {
+            return Err(UniswapV3MathError::TickSpacingError);
+        }
+        Ok(())
+    }
+
+    // the token amounts of `liquidity` on [tick_lower, tick_upper) at the current price, as in
+    // UniswapV3Pool._modifyPosition
+    fn amounts_for_liquidity(
+        &self,
+        tick_lower: i32,
+        tick_upper: i32,
+        liquidity: u128,
+        round_up: bool,
+    ) -> Result<(U256, U256), UniswapV3MathError> {
+        let sqrt_ratio_lower_x_96 = tick_math::get_sqrt_ratio_at_tick(tick_lower)?;
+        let sqrt_ratio_upper_x_96 = tick_math::get_sqrt_ratio_at_tick(tick_upper)?;
+        if self.slot0.tick < tick_lower {
+            Ok((
+                _get_amount_0_delta(
+                    sqrt_ratio_lower_x_96,
+                    sqrt_ratio_upper_x_96,
+                    liquidity,
+                    round_up,
+                )?,
+                U256::ZERO,
+            ))
+        } else if self.slot0.tick < tick_upper {
+            Ok((
+                _get_amount_0_delta(
+                    self.slot0.sqrt_price,
+                    sqrt_ratio_upper_x_96,
+                    liquidity,
+                    round_up,
+                )?,
+                _get_amount_1_delta(
+                    sqrt_ratio_lower_x_96,
+                    self.slot0.sqrt_price,
+                    liquidity,
+                    round_up,
+                )?,
+            ))
+        } else {
+            Ok((
+                U256::ZERO,
+                _get_amount_1_delta(
+                    sqrt_ratio_lower_x_96,
+                    sqrt_ratio_upper_x_96,
+                    liquidity,
+                    round_up,
+                )?,
+            ))
+        }
+    }
+
+    //Returns (tick_lower, tick_upper), the initialized ticks bracketing the current tick. slot0.liquidity
+    //is constant while tick_lower <= tick < tick_upper, a side without initialized ticks is bounded by MIN_TICK/MAX_TICK
+    pub fn active_liquidity_range(&self) -> Result<(i32, i32), UniswapV3MathError> {
+        let tick_lower = self.initialized_tick_at_or_below(self.slot0.tick)?;
+
+        let mut tick = self.slot0.tick;
+        let tick_upper = loop {
+            let (next, initialized) = next_initialized_tick_within_one_word(
+                &self.tick_bitmap,
+                tick,
+                self.tick_spacing,
+                false,
+            )?;
+            if initialized || next >= tick_math::MAX_TICK {
+                break next.min(tick_math::MAX_TICK);
+            }
+            tick = next;
+        };
+
+        Ok((tick_lower, tick_upper))
+    }
+
+    //Returns the initialized tick at the lower end of the liquidity range a swap toward `target_sqrt_price` would end
+    //in, found by walking the bitmap, or MIN_TICK if there is no initialized tick below. A swap down to exactly the
+    //price of an initialized tick crosses it and ends below it, like the swap loop
+    pub fn find_tick_for_price(&self, target_sqrt_price: U256) -> Result<i32, UniswapV3MathError> {
+        let tick = if target_sqrt_price == self.slot0.sqrt_price {
+            self.slot0.tick
+        } else {
+            let tick = tick_math::get_tick_at_sqrt_ratio(target_sqrt_price)?;
+            if target_sqrt_price < self.slot0.sqrt_price
+                && tick_math::get_sqrt_ratio_at_tick(tick)? == target_sqrt_price
+            {
+                tick - 1
+            } else {
+                tick
+            }
+        };
+        self.initialized_tick_at_or_below(tick)
+    }
+
+    // the greatest initialized tick less than or equal to tick, or MIN_TICK
+    fn initialized_tick_at_or_below(&self, mut tick: i32) -> Result<i32, UniswapV3MathError> {
+        loop {
+            let (next, initialized) = next_initialized_tick_within_one_word(
+                &self.tick_bitmap,
+                tick,
+                self.tick_spacing,
+                true,
+            )?;
+            if initialized || next <= tick_math::MIN_TICK {
+                return Ok(next.max(tick_math::MIN_TICK));
+            }
+            tick = next - 1;
+        }
+    }
+
+    //Returns the sqrt_price_limit for a swap that may move the price by at most `slippage_bps` from the current price,
+    //below it for zero_for_one swaps and above it otherwise. The limit is rounded towards the current price and kept
+    //within (MIN_SQRT_RATIO, MAX_SQRT_RATIO) so it is always accepted by swap
+    pub fn limit_for_slippage(
+        &self,
+        zero_for_one: bool,
+        slippage_bps: u32,
+    ) -> Result<U256, UniswapV3MathError> {
+        let sqrt_price = self.slot0.sqrt_price;
+        if zero_for_one {
+            // sqrt(1 - slippage) in X96, rounded up
+            let ratio_x_192 =
+                (U256::from(10000_u32.saturating_sub(slippage_bps)) << 192) / U256::from(10000);
+            let mut sqrt_ratio_x_96 = ratio_x_192.root(2);
+            if sqrt_ratio_x_96 * sqrt_ratio_x_96 < ratio_x_192 {
+                sqrt_ratio_x_96 += U256_1;
+            }
+            Ok(mul_div_rounding_up(sqrt_price, sqrt_ratio_x_96, Q96)?
+                .max(tick_math::MIN_SQRT_RATIO + U256_1))
+        } else {
+            // sqrt(1 + slippage) in X96, rounded down
+            let ratio_x_192 =
+                (U256::from(10000_u64 + slippage_bps as u64) << 192) / U256::from(10000);
+            Ok(mul_div(sqrt_price, ratio_x_192.root(2), Q96)?
+                .min(tick_math::MAX_SQRT_RATIO - U256_1))
+        }
+    }
+
+    //Returns the input, fee included, of the exact input swap moving the price by `bps` from the current price, down
+    //for zero_for_one and up otherwise, as a measure of the depth of the pool. The price limit is the one of
+    //limit_for_slippage, so the move stops at the price bounds
+    pub fn quote_for_price_movement(
+        &self,
+        zero_for_one: bool,
+        bps: u32,
+    ) -> Result<U256, UniswapV3MathError> {
+        let sqrt_price_limit = self.limit_for_slippage(zero_for_one, bps)?;
+        let result = self.simulate(zero_for_one, I256::MAX, sqrt_price_limit)?;
+        Ok(unsigned_swap_result(&result, zero_for_one).amount_in)
+    }
+
+    //Returns an iterator swapping successive exact input chunks of `chunk` on a copy of the pool and yielding the
+    //state after each one, until the price reaches `sqrt_price_target` or `max_amount_in` is used up. The last chunk
+    //is cut to what is left of `max_amount_in`. Pass tick_math::MIN_SQRT_RATIO + 1/MAX_SQRT_RATIO - 1 or U256::MAX
+    //to only stop on the amount or the price
+    pub fn simulate_until_price(
+        &self,
+        zero_for_one: bool,
+        chunk: U256,
+        sqrt_price_target: U256,
+        max_amount_in: U256,
+    ) -> PricePath {
+        PricePath {
+            pool: self.clone_for_simulation(),
+            zero_for_one,
+            chunk: chunk.min(I256::MAX.into_raw()),
+            sqrt_price_target,
+            remaining: max_amount_in,
+            amount_in: U256::ZERO,
+            amount_out: U256::ZERO,
+            done: false,
+        }
+    }
+
+    //Returns an order-book style depth table of `levels` price levels on each side of the current price, spaced by
+    //`bps_step`, as (bps, volume) in ascending order of price. The volume of a level at -bps is the token0 input
+    //moving the price down to it and the one at +bps the token1 input moving the price up to it, both cumulative
+    //from the current price. Each level is a separate swap capped at its price, see quote_for_price_movement
+    pub fn depth_table(
+        &self,
+        bps_step: u32,
+        levels: u32,
+    ) -> Result<Vec<(i32, U256)>, UniswapV3MathError> {
+        let mut table = Vec::with_capacity(2 * levels as usize);
+        for (zero_for_one, level) in (1..=levels)
+            .rev()
+            .map(|level| (true, level))
+            .chain((1..=levels).map(|level| (false, level)))
+        {
+            let bps = bps_step
+                .checked_mul(level)
+                .filter(|bps| *bps <= i32::MAX as u32)
+                .ok_or(UniswapV3MathError::Overflow)?;
+            let volume = self.quote_for_price_movement(zero_for_one, bps)?;
+            table.push((
+                if zero_for_one {
+                    -(bps as i32)
+                } else {
+                    bps as i32
+                },
+                volume,
+            ));
+        }
+        Ok(table)
+    }
+
+    //Returns (amount0, amount1), the token reserves of all positions computed from the tick data alone by walking the
+    //initialized tick ranges at the current price. The amounts are rounded down per range, so they can be a few wei
+    //below the sum of the amounts of the individual positions
+    pub fn tvl_amounts(&self) -> Result<(U256, U256), UniswapV3MathError> {
+        let mut ticks: Vec<(i32, i128)> = self
+            .ticks
+            .iter()
+            .filter(|(_, info)| info.initialized)
+            .map(|(tick, info)| (*tick, info.liquidity_net))
+            .collect();
+        ticks.sort_unstable();
+
+        let mut amount0 = U256::ZERO;
+        let mut amount1 = U256::ZERO;
+        let mut liquidity = 0;
+        for range in ticks.windows(2) {
+            let (tick_lower, liquidity_net) = range[0];
+            let tick_upper = range[1].0;
+            liquidity = liquidity_math::add_delta(liquidity, liquidity_net)?;
+            if liquidity == 0 {
+                continue;
+            }
+
+            let (range_amount0, range_amount1) = get_amounts_for_liquidity(
+                self.slot0.sqrt_price,
+                tick_math::get_sqrt_ratio_at_tick(tick_lower)?,
+                tick_math::get_sqrt_ratio_at_tick(tick_upper)?,
+                liquidity,
+                false,
+            )?;
+            amount0 += range_amount0;
+            amount1 += range_amount1;
+        }
+        Ok((amount0, amount1))
+    }
+
+    //Returns (amount0, amount1), the reserves the pool would hold after a swap moving the price to
+    //`target_sqrt_price`, e.g. to model impermanent loss. The deltas of the simulated swap are applied to tvl_amounts,
+    //so the swap fees are included as the input token stays in the pool. The target has to be strictly within
+    //(MIN_SQRT_RATIO, MAX_SQRT_RATIO) like a swap price limit
+    pub fn reserves_at_price(
+        &self,
+        target_sqrt_price: U256,
+    ) -> Result<(U256, U256), UniswapV3MathError> {
+        let (amount0, amount1) = self.tvl_amounts()?;
+        if target_sqrt_price == self.slot0.sqrt_price {
+            return Ok((amount0, amount1));
+        }
+
+        let zero_for_one = target_sqrt_price < self.slot0.sqrt_price;
+        let result = self.simulate(zero_for_one, I256::MAX, target_sqrt_price)?;
+        let apply_delta = |amount: U256, delta: I256| {
+            if delta.is_negative() {
+                amount.checked_sub(delta.unsigned_abs())
+            } else {
+                amount.checked_add(delta.into_raw())
+            }
+            .ok_or(UniswapV3MathError::Overflow)
+        };
+        Ok((
+            apply_delta(amount0, result.amount0_delta)?,
+            apply_delta(amount1, result.amount1_delta)?,
+        ))
+    }
+
+    //Returns the price of token0 in token1 in whole units given the token decimals. The price is computed exactly in
+    //X128 from slot0.sqrt_price and only converted to a float at the end
+    pub fn spot_price(&self, decimals0: u8, decimals1: u8) -> Result<f64, UniswapV3MathError> {
+        let price_x_128 = self.price_x_128()?;
+        Ok(x_128_to_f64(price_x_128) * decimals_scale(decimals0, decimals1))
+    }
+
+    //Returns the midpoint of the marginal bid (selling token0 for spot * (1 - fee)) and ask (buying token0 for
+    //spot / (1 - fee)) in whole units. It is slightly above the spot price since the fee spread is asymmetric
+    pub fn mid_price(&self, decimals0: u8, decimals1: u8) -> Result<f64, UniswapV3MathError> {
+        let bid_x_128 = self.marginal_price(true)?;
+        let ask_x_128 = self.marginal_price(false)?;
+        let mid_x_128 = (bid_x_128 >> 1) + (ask_x_128 >> 1) + (bid_x_128 & ask_x_128 & U256_1);
+        Ok(x_128_to_f64(mid_x_128) * decimals_scale(decimals0, decimals1))
+    }
+
+    //Returns the raw price of token0 in token1 an infinitesimally small swap gets at the current price, in X128. Selling
+    //token0 (zero_for_one) pays the fee on the token0 in and gets the bid spot * (1 - fee), buying token0 pays it on
+    //the token1 in and costs the ask spot / (1 - fee). The bid is rounded down and the ask up
+    pub fn marginal_price(&self, zero_for_one: bool) -> Result<U256, UniswapV3MathError> {
+        let price_x_128 = self.price_x_128()?;
+        let fee_complement = U256::from(1_000_000 - self.fee.min(999_999));
+        if zero_for_one {
+            mul_div(price_x_128, fee_complement, U256::from(1_000_000))
+        } else {
+            mul_div_rounding_up(price_x_128, U256::from(1_000_000), fee_complement)
+        }
+    }
+
+    //Returns the raw price of token0 in token1 without the fee, slot0.price(). Unlike a swap quote, which pays the fee
+    //on the input, it is the same for any fee tier and suits comparisons against a fair value
+    pub fn spot_quote_with_fee_excluded(&self) -> f64 {
+        self.slot0.price()
+    }
+
+    // the raw price of token0 in token1, sqrt_price^2 in X128
+    pub(crate) fn price_x_128(&self) -> Result<U256, UniswapV3MathError> {
+        mul_div(
+            self.slot0.sqrt_price,
+            self.slot0.sqrt_price,
+            U256::from(1_u128 << 64),
+        )
+    }
+
+    //Computes the result of a swap against the current state without modifying the pool. `amount_specified` follows
+    //the sign convention of swap and can be given as an I256, an Amount, an i128 or a u128 exact input
+    pub fn simulate(
+        &self,
+        zero_for_one: bool,
+        amount_specified: impl Into<Amount>,
+        sqrt_price_limit: U256,
+    ) -> Result<SwapResult, UniswapV3MathError> {
+        swap(
+            &self.ticks,
+            &self.tick_bitmap,
+            self.tick_spacing,
+            zero_for_one,
+            amount_specified.into().amount_specified(),
+            sqrt_price_limit,
+            &self.slot0,
+            self.fee,
+        )
+    }
+
+    //Same as simulate with the unsigned amounts of swap_exact
+    pub fn simulate_exact(
+        &self,
+        direction: SwapDirection,
+        amount: SwapAmount,
+        sqrt_price_limit: Option<U256>,
+    ) -> Result<UnsignedSwapResult, UniswapV3MathError> {
+        let (zero_for_one, amount_specified, sqrt_price_limit) =
+            exact_swap_params(direction, amount, sqrt_price_limit)?;
+        let result = self.simulate(zero_for_one, amount_specified, sqrt_price_limit)?;
+        Ok(unsigned_swap_result(&result, zero_for_one))
+    }
+
+    //Same as swap with the unsigned amounts of swap_exact
+    pub fn swap_exact(
+        &mut self,
+        direction: SwapDirection,
+        amount: SwapAmount,
+        sqrt_price_limit: Option<U256>,
+    ) -> Result<UnsignedSwapResult, UniswapV3MathError> {
+        let (zero_for_one, amount_specified, sqrt_price_limit) =
+            exact_swap_params(direction, amount, sqrt_price_limit)?;
+        let result = self.swap(zero_for_one, amount_specified, sqrt_price_limit)?;
+        Ok(unsigned_swap_result(&result, zero_for_one))
+    }
+
+    //Swaps against the pool, moves slot0 to the state after the swap, accrues the swap fees
+    //to the fee growth global of the input token and flips the fee growth outside of the crossed ticks
+    pub fn swap(
+        &mut self,
+        zero_for_one: bool,
+        amount_specified: impl Into<Amount>,
+        sqrt_price_limit: U256,
+    ) -> Result<SwapResult, UniswapV3MathError> {
+        let result = self.simulate(zero_for_one, amount_specified, sqrt_price_limit)?;
+        if !result.tick_crossings.is_empty() {
+            let ticks = Arc::make_mut(&mut self.ticks);
+            for crossing in &result.tick_crossings {
+                let (fee_growth_global_0_x_128, fee_growth_global_1_x_128) = if zero_for_one {
+                    (
+                        self.fee_growth_global_0_x_128
+                            .wrapping_add(crossing.fee_growth_x_128),
+                        self.fee_growth_global_1_x_128,
+                    )
+                } else {
+                    (
+                        self.fee_growth_global_0_x_128,
+                        self.fee_growth_global_1_x_128
+                            .wrapping_add(crossing.fee_growth_x_128),
+                    )
+                };
+                // the swap read the tick, so it is present
+                if let Some(info) = ticks.get_mut(&crossing.tick) {
+                    info.fee_growth_outside_0_x_128 =
+                        fee_growth_global_0_x_128.wrapping_sub(info.fee_growth_outside_0_x_128);
+                    info.fee_growth_outside_1_x_128 =
+                        fee_growth_global_1_x_128.wrapping_sub(info.fee_growth_outside_1_x_128);
+                }
+            }
+        }
+        self.slot0 = Slot0 {
+            sqrt_price: result.sqrt_price_after,
+            liquidity: result.liquidity_after,
+            tick: result.tick_after,
+        };
+        // overflow is acceptable, the fee growth inside of a position is computed with wrapping subtraction
+        if zero_for_one {
+            self.fee_growth_global_0_x_128 = self
+                .fee_growth_global_0_x_128
+                .wrapping_add(result.fee_growth_x_128);
+        } else {
+            self.fee_growth_global_1_x_128 = self
+                .fee_growth_global_1_x_128
+                .wrapping_add(result.fee_growth_x_128);
+        }
+        Ok(result)
+    }
+
+    //Returns (zero_for_one, amount_in), the exact input including fees that moves the price from the current price to
+    //exactly the sqrt ratio of the initialized `tick`, found by a swap capped at that price. Swapping amount_in as an
+    //exact input crosses the tick when moving down, like any swap ending on a tick. The tick has to be initialized,
+    //only there a swap step ends exactly on its price and the amount is precise
+    pub fn amount_for_full_range_tick_cross(
+        &self,
+        tick: i32,
+    ) -> Result<(bool, U256), UniswapV3MathError> {
+        if !self.get_tick(tick).is_some_and(|info| info.initialized) {
+            return Err(UniswapV3MathError::TickNotFound(tick));
+        }
+
+        let sqrt_price_target = tick_math::get_sqrt_ratio_at_tick(tick)?;
+        if sqrt_price_target == self.slot0.sqrt_price {
+            return Ok((self.slot0.tick >= tick, U256::ZERO));
+        }
+
+        let zero_for_one = sqrt_price_target < self.slot0.sqrt_price;
+        let result = self.simulate(zero_for_one, I256::MAX, sqrt_price_target)?;
+        let amount_in = if zero_for_one {
+            result.amount0_delta
+        } else {
+            result.amount1_delta
+        };
+        Ok((zero_for_one, amount_in.into_raw()))
+    }
+
+    //Applies the swaps (zero_for_one, amount_specified, sqrt_price_limit) in order, e.g. to backtest against historical
+    //swaps, and returns their results. On the first failing swap the pool is left in the state after the swaps
+    //before it and the error carries the index of the failing swap
+    pub fn replay(
+        &mut self,
+        swaps: &[(bool, I256, U256)],
+    ) -> Result<Vec<SwapResult>, UniswapV3MathError> {
+        let mut results = Vec::with_capacity(swaps.len());
+        for (index, (zero_for_one, amount_specified, sqrt_price_limit)) in swaps.iter().enumerate()
+        {
+            let result = self
+                .swap(*zero_for_one, *amount_specified, *sqrt_price_limit)
+                .map_err(|err| UniswapV3MathError::ReplayFailed {
+                    index,
+                    source: Box::new(err),
+                })?;
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    //Returns (out_zero_for_one, out_one_for_zero), the output amounts of an exact input swap of `amount`
+    //in each direction. Both are quoted against the current state, so their difference is the effective spread
+    pub fn quote_both_directions(
+        &self,
+        amount: impl Into<U256>,
+    ) -> Result<(U256, U256), UniswapV3MathError> {
+        // amounts above I256::MAX would turn into an exact output
+        let amount_specified = I256::from_raw(amount.into());
+        if amount_specified.is_negative() {
+            return Err(UniswapV3MathError::Overflow);
+        }
+
+        let zero_for_one =
+            self.simulate(true, amount_specified, tick_math::MIN_SQRT_RATIO + U256_1)?;
+        let one_for_zero =
+            self.simulate(false, amount_specified, tick_math::MAX_SQRT_RATIO - U256_1)?;
+
+        Ok((
+            (-zero_for_one.amount1_delta).into_raw(),
+            (-one_for_zero.amount0_delta).into_raw(),
+        ))
+    }
+
+    //Returns the output of an exact input swap of `amount_in` without a price limit, see
+    //quoter::quote_exact_input_single for the price and ticks crossed after the swap
+    pub fn quote_exact_input(
+        &self,
+        amount_in: impl Into<U256>,
+        zero_for_one: bool,
+    ) -> Result<U256, UniswapV3MathError> {
+        let (amount_out, _, _, _) =
+            quote_exact_input_single(self, zero_for_one, amount_in, U256::ZERO)?;
+        Ok(amount_out)
+    }
+
+    //Returns the sqrt price after an exact input swap of `amount_in` without a price limit, e.g. for an oracle that
+    //only needs the price impact of a trade and not its output
+    pub fn sqrt_price_after_amount(
+        &self,
+        amount_in: impl Into<U256>,
+        zero_for_one: bool,
+    ) -> Result<U256, UniswapV3MathError> {
+        let sqrt_price_limit = if zero_for_one {
+            tick_math::MIN_SQRT_RATIO + U256_1
+        } else {
+            tick_math::MAX_SQRT_RATIO - U256_1
+        };
+        // amounts above I256::MAX would turn into an exact output
+        let amount_specified = I256::from_raw(amount_in.into());
+        if amount_specified.is_negative() {
+            return Err(UniswapV3MathError::Overflow);
+        }
+        Ok(self
+            .simulate(zero_for_one, amount_specified, sqrt_price_limit)?
+            .sqrt_price_after)
+    }
+}
+
+// the cumulative amounts swapped and the state of the pool after a chunk of a PricePath
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PricePoint {
+    // the input, fee included, and output of all the chunks so far
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub slot0: Slot0,
+}
+
+// the price path of a swap in fixed size chunks, see Pool::simulate_until_price
+#[derive(Debug, Clone)]
+pub struct PricePath {
+    pool: Pool,
+    zero_for_one: bool,
+    chunk: U256,
+    sqrt_price_target: U256,
+    // the input left before max_amount_in is reached
+    remaining: U256,
+    amount_in: U256,
+    amount_out: U256,
+    done: bool,
+}
+
+impl Iterator for PricePath {
+    type Item = Result<PricePoint, UniswapV3MathError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let chunk = self.chunk.min(self.remaining);
+        if self.done || chunk.is_zero() {
+            return None;
+        }
+        let result = match self.pool.swap(
+            self.zero_for_one,
+            I256::from_raw(chunk),
+            self.sqrt_price_target,
+        ) {
+            Ok(result) => result,
+            Err(err) => {
+                self.done = true;
+                return Some(Err(err));
+            }
+        };
+        let amounts = unsigned_swap_result(&result, self.zero_for_one);
+        if amounts.amount_in.is_zero() {
+            self.done = true;
+            return None;
+        }
+        self.remaining -= amounts.amount_in;
+        self.amount_in += amounts.amount_in;
+        self.amount_out += amounts.amount_out;
+        // the swap stopped at the target before using up the chunk
+        self.done = result.sqrt_price_after == self.sqrt_price_target || amounts.amount_in < chunk;
+        Some(Ok(PricePoint {
+            amount_in: self.amount_in,
+            amount_out: self.amount_out,
+            slot0: self.pool.slot0.clone(),
+        }))
+    }
+}
+
+// converts an X128 fixed point number to a float, keeping the 64 most significant bits
+fn x_128_to_f64(x_128: U256) -> f64 {
+    let bits = x_128.bit_len();
+    if bits <= 64 {
+        return x_128.as_limbs()[0] as f64 / 2_f64.powi(128);
+    }
+    let shift = bits - 64;
+    (x_128 >> shift).as_limbs()[0] as f64 * 2_f64.powi(shift as i32 - 128)
+}
+
+// the factor converting a raw token1 per token0 price to whole units
+fn decimals_scale(decimals0: u8, decimals1: u8) -> f64 {
+    10_f64.powi(decimals0 as i32 - decimals1 as i32)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::{x_128_to_f64, Pool, PoolDiff, TickUpdate};
+    use crate::{
+        error::UniswapV3MathError,
+        fee_tier::FeeTier,
+        liquidity_amounts::get_amounts_for_liquidity,
+        position::simulate_lp_earnings,
+        sqrt_price_math::Q96,
+        swap::{Amount, Slot0, SwapAmount, SwapDirection},
+        swap_event::SwapEventData,
+        tick::{get_fee_growth_inside, tick_spacing_to_max_liquidity_per_tick, Tick},
+        tick_bitmap::flip_tick,
+        tick_math,
+    };
+    use alloy::primitives::{I256, U256};
+    use std::{collections::HashMap, str::FromStr, sync::Arc};
+
+    // (tick_lower, tick_upper, liquidity)
+    pub const TEST_POSITIONS: [(i32, i32, u128); 3] = [
+        (-600, 600, 1_000_000_000_000_000_000),
+        (-1200, -120, 500_000_000_000_000_000),
+        (120, 1800, 500_000_000_000_000_000),
+    ];
+
+    // a 0.3% pool at tick 0 with the test positions, only the first one in range
+    pub fn init_test_pool() -> eyre::Result<Pool> {
+        let tick_spacing = 60;
+        let mut ticks: HashMap<i32, Tick> = HashMap::new();
+        for (tick_lower, tick_upper, liquidity) in TEST_POSITIONS {
+            for (tick, liquidity_net) in [
+                (tick_lower, liquidity as i128),
+                (tick_upper, -(liquidity as i128)),
+            ] {
+                let info = ticks.entry(tick).or_default();
+                info.liquidity_gross += liquidity;
+                info.liquidity_net += liquidity_net;
+                info.initialized = true;
+            }
+        }
+        let mut tick_bitmap: HashMap<i16, U256> = HashMap::new();
+        for tick in ticks.keys() {
+            flip_tick(&mut tick_bitmap, *tick, tick_spacing)?;
+        }
+
+        Ok(Pool::new(
+            ticks,
+            tick_bitmap,
+            tick_spacing,
+            3000,
+            Slot0 {
+                sqrt_price: Q96,
+                liquidity: 1_000_000_000_000_000_000,
+                tick: 0,
+            },
+        )?)
+    }
+
+    // an empty 0.3% pool at tick 0 with the (tick_lower, tick_upper, liquidity) positions minted into it
+    pub fn init_minted_test_pool(positions: &[(i32, i32, u128)]) -> eyre::Result<Pool> {
+        let mut pool = Pool::new(
+            HashMap::new(),
+            HashMap::new(),
+            60,
+            3000,
+            Slot0 {
+                sqrt_price: Q96,
+                liquidity: 0,
+                tick: 0,
+            },
+        )?;
+        for &(tick_lower, tick_upper, liquidity) in positions {
+            pool.mint(tick_lower, tick_upper, liquidity)?;
+        }
+        Ok(pool)
+    }
+
+    #[test]
+    fn test_from_ticks() -> eyre::Result<()> {
+        let pool = init_test_pool()?;
+        let from_ticks = Pool::from_fee_tier(
+            pool.ticks.as_ref().clone(),
+            FeeTier::Medium,
+            pool.slot0.clone(),
+        )?;
+        assert_eq!(from_ticks.tick_bitmap, pool.tick_bitmap);
+        assert_eq!(from_ticks.tick_spacing, 60);
+        assert_eq!(from_ticks.fee(), 3000);
+        from_ticks.validate()?;
+
+        let mut ticks = pool.ticks.as_ref().clone();
+        ticks.insert(-90, Tick::new(1, 1)?);
+        ticks.insert(150, Tick::new(1, -1)?);
+        assert!(matches!(
+            Pool::from_ticks(ticks.clone(), 60, 3000, pool.slot0.clone()),
+            Err(UniswapV3MathError::MisalignedTick {
+                tick: -90,
+                tick_spacing: 60
+            })
+        ));
+        // both ticks are multiples of 10
+        Pool::from_fee_tier(ticks, FeeTier::Low, pool.slot0.clone())?;
+
+        // mint, burn and the swaps divide by the tick spacing
+        for tick_spacing in [0, -60] {
+            assert!(matches!(
+                Pool::new(
+                    HashMap::new(),
+                    HashMap::new(),
+                    tick_spacing,
+                    3000,
+                    Slot0::default()
+                ),
+                Err(UniswapV3MathError::TickSpacingError)
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_fee_tier() -> eyre::Result<()> {
+        let pool = init_test_pool()?;
+        pool.validate_fee_tier(&[])?;
+        for tier in FeeTier::ALL {
+            Pool::from_fee_tier(HashMap::new(), tier, Slot0::default())?.validate_fee_tier(&[])?;
+        }
+
+        let mismatched = Pool::new(HashMap::new(), HashMap::new(), 10, 3000, Slot0::default())?;
+        assert!(matches!(
+            mismatched.validate_fee_tier(&[]),
+            Err(UniswapV3MathError::FeeTierMismatch {
+                fee: 3000,
+                tick_spacing: 10
+            })
+        ));
+        assert!(mismatched
+            .validate_fee_tier(&[(3000, 60), (100, 10)])
+            .is_err());
+        mismatched.validate_fee_tier(&[(3000, 10)])?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate() -> eyre::Result<()> {
+        let mut pool = init_test_pool()?;
+        pool.validate()?;
+        // swaps keep the pool consistent, also when stopping on a tick while moving down
+        pool.swap(
+            true,
+            I256::from_str("50000000000000000")?,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+        )?;
+        pool.validate()?;
+        pool.swap(false, I256::MAX, tick_math::get_sqrt_ratio_at_tick(600)?)?;
+        pool.validate()?;
+        pool.swap(true, I256::MAX, tick_math::get_sqrt_ratio_at_tick(-120)?)?;
+        assert_eq!(pool.slot0.tick, -121);
+        pool.validate()?;
+
+        // a bit without a tick
+        let mut corrupted = init_test_pool()?;
+        flip_tick(Arc::make_mut(&mut corrupted.tick_bitmap), 60, 60)?;
+        assert!(matches!(
+            corrupted.validate(),
+            Err(UniswapV3MathError::UninitializedBitmapTick(60))
+        ));
+
+        // a tick without a bit, and one off the tick spacing
+        for tick in [240, 130] {
+            let mut corrupted = init_test_pool()?;
+            Arc::make_mut(&mut corrupted.ticks).insert(tick, Tick::new(1000, 1000)?);
+            assert!(matches!(
+                corrupted.validate(),
+                Err(UniswapV3MathError::TickNotInBitmap(t)) if t == tick
+            ));
+        }
+
+        // an uninitialized tick is not expected in the bitmap
+        let mut corrupted = init_test_pool()?;
+        Arc::make_mut(&mut corrupted.ticks).insert(-120, Tick::default());
+        assert!(matches!(
+            corrupted.validate(),
+            Err(UniswapV3MathError::UninitializedBitmapTick(-120))
+        ));
+
+        let mut corrupted = init_test_pool()?;
+        if let Some(info) = Arc::make_mut(&mut corrupted.ticks).get_mut(&600) {
+            info.liquidity_gross = 1;
+        }
+        assert!(matches!(
+            corrupted.validate(),
+            Err(UniswapV3MathError::InvalidTick {
+                liquidity_gross: 1,
+                ..
+            })
+        ));
+
+        // an initialized flag out of line with the liquidity, either way
+        for (tick, initialized, liquidity_gross) in
+            [(600, false, 1_000_000_000_000_000_000), (-120, true, 0)]
+        {
+            let mut corrupted = init_test_pool()?;
+            let mut info = Tick::new(liquidity_gross, 0)?;
+            info.initialized = initialized;
+            Arc::make_mut(&mut corrupted.ticks).insert(tick, info);
+            assert!(matches!(
+                corrupted.validate(),
+                Err(UniswapV3MathError::TickInitializedMismatch(t)) if t == tick
+            ));
+        }
+
+        let mut corrupted = init_test_pool()?;
+        corrupted.tick_spacing = 0;
+        assert!(matches!(
+            corrupted.validate(),
+            Err(UniswapV3MathError::TickSpacingError)
+        ));
+
+        // the ticks of the last word of the bitmap overflow an i32 with a tick spacing of 300
+        let corrupted = Pool::new(
+            HashMap::new(),
+            HashMap::from([(i16::MAX, U256::from(1))]),
+            300,
+            3000,
+            Slot0::default(),
+        )?;
+        assert!(matches!(
+            corrupted.validate(),
+            Err(UniswapV3MathError::Overflow)
+        ));
+
+        // a liquidity above i128::MAX is not taken for a negative one
+        let mut corrupted = init_test_pool()?;
+        corrupted.slot0.liquidity = u128::MAX;
+        assert!(matches!(
+            corrupted.validate(),
+            Err(UniswapV3MathError::InconsistentLiquidity {
+                liquidity: u128::MAX,
+                expected: 1_000_000_000_000_000_000,
+            })
+        ));
+
+        let mut corrupted = init_test_pool()?;
+        corrupted.slot0.liquidity = 500_000_000_000_000_000;
+        assert!(matches!(
+            corrupted.validate(),
+            Err(UniswapV3MathError::InconsistentLiquidity {
+                liquidity: 500_000_000_000_000_000,
+                expected: 1_000_000_000_000_000_000,
+            })
+        ));
+
+        // the tick is reported before the liquidity it implies
+        let mut corrupted = init_test_pool()?;
+        corrupted.slot0.tick = 200;
+        assert!(matches!(
+            corrupted.validate(),
+            Err(UniswapV3MathError::InconsistentTick {
+                tick: 200,
+                expected: 0
+            })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_swap_limit_at_current_price() -> eyre::Result<()> {
+        let mut pool = init_test_pool()?;
+        let before = pool.snapshot();
+        let result = pool.swap(true, I256::from_str("50000000000000000")?, Q96)?;
+        assert!(result.amount0_delta.is_zero());
+        assert!(result.amount1_delta.is_zero());
+        assert!(before.diff(&pool.snapshot()).is_empty());
+        assert_eq!(pool.fee_growth_global(), (U256::ZERO, U256::ZERO));
+        Ok(())
+    }
+
+    #[test]
+    fn test_swap_exact() -> eyre::Result<()> {
+        let mut pool = init_test_pool()?;
+        let mut raw = init_test_pool()?;
+        let amount = U256::from(50_000_000_000_000_000_u128);
+        let result = pool.swap_exact(
+            SwapDirection::ZeroForOne,
+            SwapAmount::ExactInput { amount },
+            None,
+        )?;
+        let raw_result = raw.swap(
+            true,
+            I256::from_raw(amount),
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+        )?;
+        assert_eq!(result, raw_result.as_unsigned());
+        assert_eq!(result.amount_out, U256::from(48_007_037_308_774_480_u128));
+        assert!(pool.snapshot().diff(&raw.snapshot()).is_empty());
+
+        let limit = tick_math::get_sqrt_ratio_at_tick(0)?;
+        let quote = pool.simulate_exact(
+            SwapDirection::OneForZero,
+            SwapAmount::ExactOutput { amount },
+            Some(limit),
+        )?;
+        let raw_quote = raw.simulate(false, -I256::from_raw(amount), limit)?;
+        assert_eq!(quote, raw_quote.as_unsigned());
+        assert_eq!(
+            raw.simulate(false, Amount::exact_out(50_000_000_000_000_000), limit)?,
+            raw_quote
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_swap_moves_slot0() -> eyre::Result<()> {
+        let mut pool = init_test_pool()?;
+        let result = pool.swap(
+            true,
+            I256::from_str("50000000000000000")?,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+        )?;
+
+        // crosses -120 (liquidity in) and -600 (liquidity out)
+        assert_eq!(result.amount0_delta, I256::from_str("50000000000000000")?);
+        assert_eq!(result.amount1_delta, I256::from_str("-48007037308774480")?);
+        assert_eq!(
+            result.sqrt_price_after,
+            U256::from_str("75830083212837298011496951984")?
+        );
+        assert_eq!(result.liquidity_after, 500_000_000_000_000_000);
+        assert_eq!(result.tick_after, -877);
+
+        assert_eq!(pool.slot0.sqrt_price, result.sqrt_price_after);
+        assert_eq!(pool.slot0.liquidity, result.liquidity_after);
+        assert_eq!(pool.slot0.tick, result.tick_after);
+        Ok(())
+    }
+
+    #[test]
+    fn test_amount_for_full_range_tick_cross() -> eyre::Result<()> {
+        let pool = init_test_pool()?;
+
+        for (tick, zero_for_one, amount_in, tick_after) in [
+            (-600, true, 42_799_012_466_860_637_u128, -601),
+            (-120, true, 6_035_841_794_200_769, -121),
+            (600, false, 42_799_012_466_860_637, 600),
+        ] {
+            assert_eq!(
+                pool.amount_for_full_range_tick_cross(tick)?,
+                (zero_for_one, U256::from(amount_in))
+            );
+
+            // the amount as an exact input without a limit stops exactly at the tick
+            let sqrt_price_limit = if zero_for_one {
+                tick_math::MIN_SQRT_RATIO + U256::from(1)
+            } else {
+                tick_math::MAX_SQRT_RATIO - U256::from(1)
+            };
+            let result = pool.simulate(
+                zero_for_one,
+                I256::from_raw(U256::from(amount_in)),
+                sqrt_price_limit,
+            )?;
+            assert_eq!(
+                result.sqrt_price_after,
+                tick_math::get_sqrt_ratio_at_tick(tick)?
+            );
+            assert_eq!(result.tick_after, tick_after);
+        }
+
+        assert!(matches!(
+            pool.amount_for_full_range_tick_cross(60),
+            Err(UniswapV3MathError::TickNotFound(60))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_replay() -> eyre::Result<()> {
+        let swaps = [
+            (
+                true,
+                I256::from_str("50000000000000000")?,
+                tick_math::MIN_SQRT_RATIO + U256::from(1),
+            ),
+            (
+                false,
+                I256::from_str("-20000000000000000")?,
+                tick_math::MAX_SQRT_RATIO - U256::from(1),
+            ),
+            (
+                true,
+                I256::from_str("1000000000000000")?,
+                tick_math::MIN_SQRT_RATIO + U256::from(1),
+            ),
+        ];
+
+        let mut replayed = init_test_pool()?;
+        let results = replayed.replay(&swaps)?;
+
+        let mut swapped = init_test_pool()?;
+        for (result, (zero_for_one, amount_specified, sqrt_price_limit)) in
+            results.iter().zip(swaps)
+        {
+            assert_eq!(
+                result,
+                &swapped.swap(zero_for_one, amount_specified, sqrt_price_limit)?
+            );
+        }
+        assert_eq!(results.len(), 3);
+        assert_eq!(replayed.slot0, swapped.slot0);
+        assert_eq!(replayed.fee_growth_global(), swapped.fee_growth_global());
+        assert!(replayed.snapshot().diff(&swapped.snapshot()).is_empty());
+
+        // the second swap has its price limit below the price
+        let mut failing = init_test_pool()?;
+        let result = failing.replay(&[
+            swaps[0],
+            (false, swaps[1].1, tick_math::MIN_SQRT_RATIO + U256::from(1)),
+            swaps[2],
+        ]);
+        assert!(matches!(
+            result,
+            Err(UniswapV3MathError::ReplayFailed { index: 1, ref source })
+                if matches!(**source, UniswapV3MathError::SpuC)
+        ));
+        // the pool is left after the first swap
+        assert_eq!(failing.current_tick(), results[0].tick_after);
+        Ok(())
+    }
+
+    #[test]
+    fn test_spot_price() -> eyre::Result<()> {
+        let mut pool = init_test_pool()?;
+        assert_eq!(pool.spot_price(18, 18)?, 1.0);
+        assert!((pool.mid_price(18, 18)? - (0.997 + 1.0 / 0.997) / 2.0).abs() < 1e-12);
+
+        // a 6 decimals / 18 decimals stablecoin pool at parity, 1e12 raw token1 per raw token0
+        pool.slot0.sqrt_price = Q96 * U256::from(1_000_000);
+        assert!((pool.spot_price(6, 18)? - 1.0).abs() < 1e-12);
+        assert!((pool.spot_price(18, 18)? - 1e12).abs() < 1.0);
+
+        // a price below one
+        pool.slot0.sqrt_price = Q96 / U256::from(2);
+        assert_eq!(pool.spot_price(18, 18)?, 0.25);
+        Ok(())
+    }
+
+    #[test]
+    fn test_spot_quote_with_fee_excluded() -> eyre::Result<()> {
+        let tick = -887;
+        for fee in [0, 100, 500, 3000, 10000] {
+            let pool = Pool::new(
+                HashMap::new(),
+                HashMap::new(),
+                60,
+                fee,
+                Slot0 {
+                    sqrt_price: tick_math::get_sqrt_ratio_at_tick(tick)?,
+                    liquidity: 1_000_000_000_000_000_000,
+                    tick,
+                },
+            )?;
+            assert_eq!(pool.spot_quote_with_fee_excluded(), pool.slot0.price());
+            assert!((pool.spot_quote_with_fee_excluded() - 1.0001_f64.powi(tick)).abs() < 1e-12);
+            // the fee spread moves the mid price away from the spot price
+            if fee > 0 {
+                assert!(pool.mid_price(18, 18)? > pool.spot_quote_with_fee_excluded());
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_marginal_price() -> eyre::Result<()> {
+        let q128 = U256::from(1) << 128;
+        let pool = init_test_pool()?;
+        let spot_x_128 = pool.price_x_128()?;
+        assert_eq!(spot_x_128, q128);
+        let bid = pool.marginal_price(true)?;
+        let ask = pool.marginal_price(false)?;
+        //0.3% on either side of a price of 1
+        assert_eq!(bid, q128 * U256::from(997) / U256::from(1000));
+        assert_eq!(
+            ask,
+            (q128 * U256::from(1000) + U256::from(996)) / U256::from(997)
+        );
+        assert!(bid < spot_x_128 && spot_x_128 < ask);
+        let mid = pool.mid_price(18, 18)?;
+        assert!(0.997 < mid && mid < 1.0 / 0.997);
+
+        //a tiny swap pays the marginal price up to its price impact
+        let result = pool.simulate(
+            true,
+            1_000_000_000u128,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+        )?;
+        let rate = result.amount1_delta.unsigned_abs().to::<u128>() as f64
+            / result.amount0_delta.unsigned_abs().to::<u128>() as f64;
+        assert!((rate - x_128_to_f64(bid)).abs() < 1e-6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_both_directions() -> eyre::Result<()> {
+        let pool = init_test_pool()?;
+        let (out_zero_for_one, out_one_for_zero) =
+            pool.quote_both_directions(U256::from(50_000_000_000_000_000_u128))?;
+
+        // the liquidity is symmetric around tick 0, so both directions quote the same
+        assert_eq!(out_zero_for_one, U256::from(48_007_037_308_774_480_u128));
+        assert_eq!(out_one_for_zero, U256::from(48_007_037_308_774_480_u128));
+
+        // quoting does not touch the pool
+        assert_eq!(pool.slot0.sqrt_price, Q96);
+        assert_eq!(pool.slot0.liquidity, 1_000_000_000_000_000_000);
+        assert_eq!(pool.slot0.tick, 0);
+
+        // an amount above I256::MAX is not quoted as an exact output
+        assert!(matches!(
+            pool.quote_both_directions(U256::MAX),
+            Err(UniswapV3MathError::Overflow)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_integer_amounts() -> eyre::Result<()> {
+        let pool = init_test_pool()?;
+        let (out_zero_for_one, out_one_for_zero) =
+            pool.quote_both_directions(U256::from(1_000_000))?;
+        assert_eq!(
+            pool.quote_exact_input(1_000_000u128, true)?,
+            out_zero_for_one
+        );
+        assert_eq!(
+            pool.quote_exact_input(1_000_000u64, false)?,
+            out_one_for_zero
+        );
+        assert_eq!(
+            pool.quote_both_directions(1_000_000u128)?,
+            (out_zero_for_one, out_one_for_zero)
+        );
+
+        let sqrt_price_limit = tick_math::MIN_SQRT_RATIO + U256::from(1);
+        assert_eq!(
+            pool.simulate(true, 1_000_000u128, sqrt_price_limit)?,
+            pool.simulate(true, I256::from_str("1000000")?, sqrt_price_limit)?
+        );
+        //an i128 keeps the sign convention, negative for an exact output
+        assert_eq!(
+            pool.simulate(true, -1_000_000i128, sqrt_price_limit)?,
+            pool.simulate(true, Amount::exact_out(1_000_000), sqrt_price_limit)?
+        );
+        assert_eq!(
+            pool.sqrt_price_after_amount(1_000_000u128, true)?,
+            pool.simulate(true, 1_000_000u128, sqrt_price_limit)?
+                .sqrt_price_after
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sqrt_price_after_amount() -> eyre::Result<()> {
+        let pool = init_test_pool()?;
+        for amount_in in [
+            1_u128,
+            50_000_000_000_000_000,
+            1_000_000_000_000_000_000_000,
+        ] {
+            for zero_for_one in [true, false] {
+                let sqrt_price_limit = if zero_for_one {
+                    tick_math::MIN_SQRT_RATIO + U256::from(1)
+                } else {
+                    tick_math::MAX_SQRT_RATIO - U256::from(1)
+                };
+                let result = pool.simulate(
+                    zero_for_one,
+                    I256::from_raw(U256::from(amount_in)),
+                    sqrt_price_limit,
+                )?;
+                assert_eq!(
+                    pool.sqrt_price_after_amount(U256::from(amount_in), zero_for_one)?,
+                    result.sqrt_price_after
+                );
+            }
+        }
+        assert_eq!(
+            pool.sqrt_price_after_amount(U256::from(50_000_000_000_000_000_u128), true)?,
+            U256::from_str("75830083212837298011496951984")?
+        );
+        assert!(matches!(
+            pool.sqrt_price_after_amount(U256::MAX, true),
+            Err(UniswapV3MathError::Overflow)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_fee_growth_global_wraps() -> eyre::Result<()> {
+        let mut pool = init_test_pool()?;
+
+        // all ticks were initialized when the fee growth global was just below the overflow,
+        // by convention the growth before initialization happened below the tick
+        let fee_growth_global_0 = U256::MAX - U256::from(10).pow(U256::from(30));
+        pool.set_fee_growth_global(fee_growth_global_0, U256::ZERO);
+        for (tick, info) in Arc::make_mut(&mut pool.ticks).iter_mut() {
+            if *tick <= pool.slot0.tick {
+                info.fee_growth_outside_0_x_128 = fee_growth_global_0;
+            }
+        }
+        let (inside_before, _) =
+            get_fee_growth_inside(&pool.ticks, -600, 600, 0, fee_growth_global_0, U256::ZERO);
+        assert_eq!(inside_before, U256::ZERO);
+
+        let result = pool.swap(
+            true,
+            I256::from_str("1000000000000000")?,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+        )?;
+        assert_eq!(
+            result.fee_growth_x_128,
+            U256::from_str("1020847100762815390390123822295304")?
+        );
+        assert_eq!(result.tick_after, -20);
+
+        // the accumulator wrapped past U256::MAX
+        let (fee_growth_global_0_after, fee_growth_global_1_after) = pool.fee_growth_global();
+        assert_eq!(
+            fee_growth_global_0_after,
+            U256::from_str("1019847100762815390390123822295303")?
+        );
+        assert!(fee_growth_global_0_after < fee_growth_global_0);
+        assert_eq!(fee_growth_global_1_after, U256::ZERO);
+
+        // the position still sees exactly the growth accrued by the swap
+        let (inside_after, _) = get_fee_growth_inside(
+            &pool.ticks,
+            -600,
+            600,
+            result.tick_after,
+            fee_growth_global_0_after,
+            fee_growth_global_1_after,
+        );
+        assert_eq!(
+            inside_after.wrapping_sub(inside_before),
+            result.fee_growth_x_128
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_diff() -> eyre::Result<()> {
+        let mut pool = init_test_pool()?;
+        let before = pool.snapshot();
+        assert!(before.diff(&pool.snapshot()).is_empty());
+
+        pool.swap(
+            true,
+            I256::from_str("50000000000000000")?,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+        )?;
+        let after_swap = pool.snapshot();
+
+        // the crossed ticks flipped their fee growth outside, the bitmap is left as is
+        assert_eq!(
+            before.diff(&after_swap),
+            PoolDiff {
+                ticks: vec![-600, -120],
+                tick_bitmap_words: vec![],
+                sqrt_price: Some((Q96, U256::from_str("75830083212837298011496951984")?)),
+                liquidity: Some((1_000_000_000_000_000_000, 500_000_000_000_000_000)),
+                tick: Some((0, -877)),
+            }
+        );
+
+        // remove the (-1200, -120) position
+        Arc::make_mut(&mut pool.ticks).remove(&-1200);
+        Arc::make_mut(&mut pool.ticks).remove(&-120);
+        flip_tick(Arc::make_mut(&mut pool.tick_bitmap), -1200, 60)?;
+        flip_tick(Arc::make_mut(&mut pool.tick_bitmap), -120, 60)?;
+        let diff = after_swap.diff(&pool.snapshot());
+
+        assert_eq!(diff.ticks, vec![-1200, -120]);
+        assert_eq!(diff.tick_bitmap_words, vec![-1]);
+        assert_eq!(diff.sqrt_price, None);
+        assert_eq!(diff.liquidity, None);
+        assert_eq!(diff.tick, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_checkpoint_restore() -> eyre::Result<()> {
+        let mut pool = init_test_pool()?;
+        pool.swap(
+            false,
+            I256::from_str("1000000000000000")?,
+            tick_math::MAX_SQRT_RATIO - U256::from(1),
+        )?;
+        let before = pool.snapshot();
+        let fee_growth_global = pool.fee_growth_global();
+
+        let state = pool.checkpoint();
+        assert!(Arc::ptr_eq(&state.ticks, &pool.ticks));
+        let result = pool.swap(
+            true,
+            I256::from_str("50000000000000000")?,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+        )?;
+        assert!(!result.tick_crossings.is_empty());
+        pool.mint(-1200, 1200, 1_000_000)?;
+        assert!(!before.diff(&pool.snapshot()).is_empty());
+
+        pool.restore(state);
+        assert!(before.diff(&pool.snapshot()).is_empty());
+        assert_eq!(pool.ticks.as_ref(), &before.ticks);
+        assert_eq!(pool.slot0, before.slot0);
+        assert_eq!(pool.fee_growth_global(), fee_growth_global);
+        pool.validate()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_accessors() -> eyre::Result<()> {
+        let mut pool = init_test_pool()?;
+        assert_eq!(pool.current_tick(), 0);
+        assert_eq!(pool.current_liquidity(), 1_000_000_000_000_000_000);
+        assert_eq!(pool.current_sqrt_price(), Q96);
+
+        let tick = pool.get_tick(-600).unwrap();
+        assert_eq!(tick.liquidity_gross, 1_000_000_000_000_000_000);
+        assert_eq!(tick.liquidity_net, 1_000_000_000_000_000_000);
+        assert!(pool.get_tick(0).is_none());
+
+        let mut ticks: Vec<i32> = pool.ticks_iter().map(|(tick, _)| *tick).collect();
+        ticks.sort_unstable();
+        assert_eq!(ticks, vec![-1200, -600, -120, 120, 600, 1800]);
+        let ordered: Vec<i32> = pool.tick_set().into_iter().map(|(tick, _)| tick).collect();
+        assert_eq!(ordered, ticks);
+        let liquidity_net: i128 = pool.ticks_iter().map(|(_, info)| info.liquidity_net).sum();
+        assert_eq!(liquidity_net, 0);
+
+        pool.swap(
+            false,
+            I256::from_str("50000000000000000")?,
+            tick_math::MAX_SQRT_RATIO - U256::from(1),
+        )?;
+        assert_eq!(pool.current_tick(), 876);
+        assert_eq!(pool.current_liquidity(), 500_000_000_000_000_000);
+        assert_eq!(
+            pool.current_sqrt_price(),
+            U256::from_str("82778515721370437433084229150")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_liquidity_net_at() -> eyre::Result<()> {
+        let mut pool = init_test_pool()?;
+        for (tick, liquidity_net) in [
+            (-1200, 500_000_000_000_000_000),
+            (-600, 1_000_000_000_000_000_000),
+            (-120, -500_000_000_000_000_000),
+            (120, 500_000_000_000_000_000),
+            (600, -1_000_000_000_000_000_000),
+            (1800, -500_000_000_000_000_000),
+        ] {
+            assert_eq!(pool.liquidity_net_at(tick), liquidity_net);
+        }
+        assert_eq!(pool.liquidity_net_at(0), 0);
+        assert_eq!(pool.liquidity_net_at(-60), 0);
+
+        // positions sharing a tick add up
+        pool.mint(-120, 60, 1_000_000)?;
+        assert_eq!(
+            pool.liquidity_net_at(-120),
+            -500_000_000_000_000_000 + 1_000_000
+        );
+        assert_eq!(pool.liquidity_net_at(60), -1_000_000);
+        Ok(())
+    }
+
+    #[test]
+    fn test_active_liquidity_range() -> eyre::Result<()> {
+        let mut pool = init_test_pool()?;
+        assert_eq!(pool.active_liquidity_range()?, (-120, 120));
+
+        pool.swap(
+            true,
+            I256::from_str("50000000000000000")?,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+        )?;
+        assert_eq!(pool.current_tick(), -877);
+        assert_eq!(pool.active_liquidity_range()?, (-1200, -600));
+
+        // below the lowest initialized tick the range extends to MIN_TICK
+        pool.swap(
+            true,
+            I256::from_str("50000000000000000")?,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+        )?;
+        assert!(pool.current_tick() < -1200);
+        assert_eq!(pool.active_liquidity_range()?, (tick_math::MIN_TICK, -1200));
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_tick_for_price() -> eyre::Result<()> {
+        let pool = init_test_pool()?;
+        let initialized_ticks = [-1200, -600, -120, 120, 600, 1800];
+
+        for (target_tick, expected) in [
+            (-877, -1200),
+            (-600, -1200),
+            (-300, -600),
+            (-5000, tick_math::MIN_TICK),
+            (0, -120),
+            (150, 120),
+            (600, 600),
+            (2000, 1800),
+        ] {
+            let target_sqrt_price = tick_math::get_sqrt_ratio_at_tick(target_tick)?;
+            let tick = pool.find_tick_for_price(target_sqrt_price)?;
+            assert_eq!(tick, expected, "{target_tick}");
+
+            // the returned tick and the next initialized tick above bracket the target price
+            if tick > tick_math::MIN_TICK {
+                assert!(tick_math::get_sqrt_ratio_at_tick(tick)? <= target_sqrt_price);
+            }
+            if let Some(upper) = initialized_ticks.iter().find(|upper| **upper > tick) {
+                assert!(target_sqrt_price <= tick_math::get_sqrt_ratio_at_tick(*upper)?);
+            }
+
+            // and match the active liquidity range after swapping to the target
+            if target_sqrt_price != pool.current_sqrt_price() {
+                let zero_for_one = target_sqrt_price < pool.current_sqrt_price();
+                let mut swapped = pool.clone_for_simulation();
+                swapped.swap(
+                    zero_for_one,
+                    I256::from_str("1000000000000000000000000")?,
+                    target_sqrt_price,
+                )?;
+                assert_eq!(swapped.current_sqrt_price(), target_sqrt_price);
+                assert_eq!(swapped.active_liquidity_range()?.0, tick);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_clone_for_simulation() -> eyre::Result<()> {
+        let pool = init_test_pool()?;
+        let mut down = pool.clone_for_simulation();
+        let mut up = pool.clone_for_simulation();
+        assert!(Arc::ptr_eq(&pool.ticks, &down.ticks));
+        assert!(Arc::ptr_eq(&pool.tick_bitmap, &up.tick_bitmap));
+
+        down.swap(
+            true,
+            I256::from_str("50000000000000000")?,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+        )?;
+        up.swap(
+            false,
+            I256::from_str("1000000000000000")?,
+            tick_math::MAX_SQRT_RATIO - U256::from(1),
+        )?;
+        assert_eq!(down.current_tick(), -877);
+        assert_eq!(up.current_tick(), 19);
+        assert_eq!(pool.current_tick(), 0);
+        assert_eq!(pool.current_sqrt_price(), Q96);
+        assert_eq!(pool.fee_growth_global(), (U256::ZERO, U256::ZERO));
+        assert_ne!(down.fee_growth_global().0, U256::ZERO);
+        assert_eq!(up.fee_growth_global().0, U256::ZERO);
+
+        // a swap within the current range only moves slot0 and keeps sharing the tick data,
+        // crossing ticks writes their fee growth outside to a copy
+        assert!(Arc::ptr_eq(&pool.ticks, &up.ticks));
+        assert!(!Arc::ptr_eq(&pool.ticks, &down.ticks));
+        assert_ne!(
+            down.get_tick(-120).unwrap().fee_growth_outside_0_x_128,
+            U256::ZERO
+        );
+        assert_eq!(
+            pool.get_tick(-120).unwrap().fee_growth_outside_0_x_128,
+            U256::ZERO
+        );
+
+        // minting copies the tick data of the clone only
+        up.mint(-60, 60, 1_000_000_000_000_000_000)?;
+        assert!(!Arc::ptr_eq(&pool.ticks, &up.ticks));
+        assert!(!Arc::ptr_eq(&pool.tick_bitmap, &up.tick_bitmap));
+        assert!(up.get_tick(60).is_some());
+        assert!(pool.get_tick(60).is_none());
+        assert!(down.get_tick(60).is_none());
+        assert_eq!(pool.ticks_iter().count(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_for_price_movement() -> eyre::Result<()> {
+        let pool_with_liquidity = |liquidity: u128| {
+            Pool::new(
+                HashMap::new(),
+                HashMap::new(),
+                60,
+                3000,
+                Slot0 {
+                    sqrt_price: Q96,
+                    liquidity,
+                    tick: 0,
+                },
+            )
+        };
+        let shallow = pool_with_liquidity(1_000_000_000_000_000_000)?;
+        let deep = pool_with_liquidity(100_000_000_000_000_000_000)?;
+
+        for zero_for_one in [true, false] {
+            let shallow_amount = shallow.quote_for_price_movement(zero_for_one, 100)?;
+            let deep_amount = deep.quote_for_price_movement(zero_for_one, 100)?;
+            assert!(shallow_amount > U256::ZERO);
+            // the input scales with the liquidity, up to rounding
+            assert!(deep_amount > shallow_amount * U256::from(99));
+            assert!(deep_amount < shallow_amount * U256::from(101));
+            // and with the size of the move
+            assert!(shallow.quote_for_price_movement(zero_for_one, 200)? > shallow_amount);
+        }
+        assert_eq!(shallow.quote_for_price_movement(true, 0)?, U256::ZERO);
+        Ok(())
+    }
+
+    #[test]
+    fn test_limit_for_slippage() -> eyre::Result<()> {
+        let mut pool = init_test_pool()?;
+
+        // 1% below and above the price of 1, i.e. sqrt(0.99) and sqrt(1.01) in X96
+        let limit_down = pool.limit_for_slippage(true, 100)?;
+        assert_eq!(limit_down, U256::from_str("78831026366734652303669917532")?);
+        assert!(limit_down < pool.current_sqrt_price());
+        assert!(limit_down * limit_down * U256::from(10000) >= U256::from(9900) * Q96 * Q96);
+        let limit_up = pool.limit_for_slippage(false, 100)?;
+        assert_eq!(limit_up, U256::from_str("79623317895830914510639640423")?);
+        assert!(limit_up > pool.current_sqrt_price());
+        assert!(limit_up * limit_up * U256::from(10000) <= U256::from(10100) * Q96 * Q96);
+
+        // no slippage allowed and slippage past the price bounds
+        assert_eq!(pool.limit_for_slippage(true, 0)?, Q96);
+        assert_eq!(pool.limit_for_slippage(false, 0)?, Q96);
+        assert_eq!(
+            pool.limit_for_slippage(true, 10000)?,
+            tick_math::MIN_SQRT_RATIO + U256::from(1)
+        );
+        let mut near_max_price = pool.clone_for_simulation();
+        near_max_price.slot0.sqrt_price = tick_math::MAX_SQRT_RATIO - U256::from(1000);
+        assert_eq!(
+            near_max_price.limit_for_slippage(false, 100)?,
+            tick_math::MAX_SQRT_RATIO - U256::from(1)
+        );
+
+        // a large swap stops at the limit
+        let result = pool.swap(true, I256::from_str("1000000000000000000")?, limit_down)?;
+        assert_eq!(result.sqrt_price_after, limit_down);
+        assert_eq!(result.amount0_delta, I256::from_str("5052974181757349")?);
+        assert_eq!(result.tick_after, -101);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mint_and_burn_tick_updates() -> eyre::Result<()> {
+        let mut pool = init_test_pool()?;
+        let update = |tick, flipped, removed| TickUpdate {
+            tick,
+            flipped,
+            removed,
+        };
+
+        // a new range initializes both ticks
+        let (amount0, amount1, tick_updates) = pool.mint(-60, 60, 1_000_000_000_000_000_000)?;
+        assert_eq!(amount0, U256::from(2_995_354_955_910_781_u128));
+        assert_eq!(amount1, U256::from(2_995_354_955_910_781_u128));
+        assert_eq!(
+            tick_updates,
+            vec![update(-60, true, false), update(60, true, false)]
+        );
+        assert_eq!(pool.slot0.liquidity, 2_000_000_000_000_000_000);
+        pool.validate()?;
+
+        // adding to initialized ticks only updates them
+        let (_, _, tick_updates) = pool.mint(-60, 60, 1_000_000_000_000_000_000)?;
+        assert_eq!(
+            tick_updates,
+            vec![update(-60, false, false), update(60, false, false)]
+        );
+
+        // removing part of the liquidity keeps the ticks, the amounts round down
+        let (amount0, amount1, tick_updates) = pool.burn(-60, 60, 1_000_000_000_000_000_000)?;
+        assert_eq!(amount0, U256::from(2_995_354_955_910_780_u128));
+        assert_eq!(amount1, U256::from(2_995_354_955_910_780_u128));
+        assert_eq!(
+            tick_updates,
+            vec![update(-60, false, false), update(60, false, false)]
+        );
+
+        // removing the rest clears them
+        let (_, _, tick_updates) = pool.burn(-60, 60, 1_000_000_000_000_000_000)?;
+        assert_eq!(
+            tick_updates,
+            vec![update(-60, true, true), update(60, true, true)]
+        );
+        assert!(pool.get_tick(-60).is_none());
+        assert!(pool.get_tick(60).is_none());
+        assert!(pool
+            .snapshot()
+            .diff(&init_test_pool()?.snapshot())
+            .is_empty());
+
+        // a tick shared with another position stays, a range above the price only takes token0
+        let (amount0, amount1, tick_updates) = pool.mint(120, 180, 1_000_000_000_000_000_000)?;
+        assert_eq!(amount0, U256::from(2_977_437_528_064_880_u128));
+        assert_eq!(amount1, U256::ZERO);
+        assert_eq!(
+            tick_updates,
+            vec![update(120, false, false), update(180, true, false)]
+        );
+        let (_, _, tick_updates) = pool.burn(120, 180, 1_000_000_000_000_000_000)?;
+        assert_eq!(
+            tick_updates,
+            vec![update(120, false, false), update(180, true, true)]
+        );
+        assert!(pool.get_tick(120).is_some());
+        pool.validate()?;
+
+        // burning more than a tick holds or an uninitialized range fails without touching the pool
+        let snapshot = pool.snapshot();
+        assert!(matches!(
+            pool.burn(-1200, -120, 500_000_000_000_000_001),
+            Err(UniswapV3MathError::LiquiditySubUnderflow {
+                current: 500_000_000_000_000_000,
+                delta: 500_000_000_000_000_001
+            })
+        ));
+        // the in range liquidity is checked like the ticks, here against a slot0 holding less than the position
+        let mut short = pool.clone();
+        short.slot0.liquidity = 1;
+        assert!(matches!(
+            short.burn(-600, 600, 2),
+            Err(UniswapV3MathError::LiquiditySubUnderflow {
+                current: 1,
+                delta: 2
+            })
+        ));
+        assert!(matches!(
+            pool.burn(-600, 60, 1),
+            Err(UniswapV3MathError::TickNotFound(60))
+        ));
+        assert!(pool.snapshot().diff(&snapshot).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_current_word() -> eyre::Result<()> {
+        //-1200, -600, -120, 120, 600 and 1800 are the compressed ticks -20, -10, -2, 2, 10 and 30
+        let pool = init_test_pool()?;
+        let bits = |bits: &[usize]| {
+            bits.iter()
+                .fold(U256::ZERO, |word, bit| word | (U256::from(1) << *bit))
+        };
+        assert_eq!(pool.current_word(), (0, bits(&[2, 10, 30])));
+
+        //tick -1 is in the compressed tick -1 of the word below
+        let (ticks, tick_bitmap) = (
+            pool.ticks.as_ref().clone(),
+            pool.tick_bitmap.as_ref().clone(),
+        );
+        let pool = Pool::new(
+            ticks.clone(),
+            tick_bitmap.clone(),
+            60,
+            3000,
+            Slot0 {
+                sqrt_price: tick_math::get_sqrt_ratio_at_tick(-1)?,
+                liquidity: 1_000_000_000_000_000_000,
+                tick: -1,
+            },
+        )?;
+        assert_eq!(pool.current_word(), (-1, bits(&[236, 246, 254])));
+
+        //a word without initialized ticks
+        let pool = Pool::new(
+            ticks,
+            tick_bitmap,
+            60,
+            3000,
+            Slot0 {
+                sqrt_price: tick_math::get_sqrt_ratio_at_tick(20000)?,
+                liquidity: 0,
+                tick: 20000,
+            },
+        )?;
+        assert_eq!(pool.current_word(), (1, U256::ZERO));
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulate_until_price() -> eyre::Result<()> {
+        let pool = init_test_pool()?;
+        let chunk = U256::from(10_000_000_000_000_000_u128);
+        let sqrt_price_target = tick_math::get_sqrt_ratio_at_tick(-1300)?;
+        let path = pool
+            .simulate_until_price(true, chunk, sqrt_price_target, U256::MAX)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let ticks: Vec<i32> = path.iter().map(|point| point.slot0.tick).collect();
+        assert_eq!(ticks, vec![-173, -304, -435, -564, -877, -1300]);
+        for pair in path.windows(2) {
+            assert!(pair[1].slot0.sqrt_price < pair[0].slot0.sqrt_price);
+            assert!(pair[1].amount_in > pair[0].amount_in);
+            assert!(pair[1].amount_out > pair[0].amount_out);
+        }
+        let last = path.last().expect("a point");
+        assert_eq!(last.slot0.sqrt_price, sqrt_price_target);
+        //one wei more than in a single swap, each chunk rounds up its input
+        assert_eq!(last.amount_in, U256::from(58_536_411_101_231_870_u128));
+        //the pool itself is unchanged
+        assert_eq!(pool.current_tick(), 0);
+
+        //stops on the amount, the last chunk is cut
+        let path = pool
+            .simulate_until_price(
+                true,
+                chunk,
+                sqrt_price_target,
+                U256::from(25_000_000_000_000_000_u128),
+            )
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(path.len(), 3);
+        assert_eq!(path[2].amount_in, U256::from(25_000_000_000_000_000_u128));
+
+        //nothing to do at the target
+        assert_eq!(
+            pool.simulate_until_price(true, chunk, Q96, U256::MAX)
+                .count(),
+            0
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_depth_table() -> eyre::Result<()> {
+        let pool = init_test_pool()?;
+        let table = pool.depth_table(50, 4)?;
+        let levels: Vec<i32> = table.iter().map(|(bps, _)| *bps).collect();
+        assert_eq!(levels, vec![-200, -150, -100, -50, 50, 100, 150, 200]);
+
+        let (below, above) = table.split_at(4);
+        assert_eq!(below[3].1, pool.quote_for_price_movement(true, 50)?);
+        assert_eq!(above[0].1, pool.quote_for_price_movement(false, 50)?);
+        //the volume grows away from the current price on both sides
+        for pair in below.windows(2) {
+            assert!(pair[0].1 > pair[1].1);
+        }
+        for pair in above.windows(2) {
+            assert!(pair[0].1 < pair[1].1);
+        }
+        assert!(below[3].1 > U256::ZERO && above[0].1 > U256::ZERO);
+
+        assert_eq!(pool.depth_table(50, 0)?, vec![]);
+        assert!(matches!(
+            pool.depth_table(u32::MAX, 2),
+            Err(UniswapV3MathError::Overflow)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_event() -> eyre::Result<()> {
+        let mut pool = init_test_pool()?;
+        let result = pool.simulate(
+            true,
+            I256::from_str("50000000000000000")?,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+        )?;
+        let event = SwapEventData {
+            amount0: result.amount0_delta,
+            amount1: result.amount1_delta,
+            sqrt_price_x96: result.sqrt_price_after,
+            liquidity: result.liquidity_after,
+            tick: result.tick_after,
+        };
+        pool.apply_event(&event)?;
+        assert_eq!(pool.slot0, result.to_slot0());
+        assert_eq!(pool.current_tick(), -877);
+
+        //a tick not matching the price is rejected
+        let inconsistent = SwapEventData {
+            tick: 0,
+            ..event.clone()
+        };
+        assert!(matches!(
+            pool.apply_event(&inconsistent),
+            Err(UniswapV3MathError::InconsistentTick {
+                tick: 0,
+                expected: -877
+            })
+        ));
+        assert_eq!(pool.slot0, result.to_slot0());
+        Ok(())
+    }
+
+    #[test]
+    fn test_burn_all() -> eyre::Result<()> {
+        let mut pool = init_test_pool()?;
+        let liquidity = 1_000_000_000_000_000_000;
+        let swaps = [
+            (true, I256::from_str("1000000000000000")?),
+            (false, I256::from_str("2000000000000000")?),
+        ];
+        let expected_fees = simulate_lp_earnings(&pool, &[(-60, 60, liquidity)], &swaps)?;
+
+        pool.mint(-60, 60, liquidity)?;
+        let (fee_growth_inside_0_x_128, fee_growth_inside_1_x_128) =
+            pool.fee_growth_inside(-60, 60);
+        for (zero_for_one, amount_specified) in swaps {
+            let sqrt_price_limit = if zero_for_one {
+                tick_math::MIN_SQRT_RATIO + U256::from(1)
+            } else {
+                tick_math::MAX_SQRT_RATIO - U256::from(1)
+            };
+            pool.swap(zero_for_one, amount_specified, sqrt_price_limit)?;
+        }
+        //the position is still in range after the swaps
+        assert!(-60 <= pool.slot0.tick && pool.slot0.tick < 60);
+
+        let (expected_amount0, expected_amount1, _) =
+            pool.clone_for_simulation().burn(-60, 60, liquidity)?;
+        let (amount0, amount1, fees0, fees1) = pool.burn_all(
+            -60,
+            60,
+            liquidity,
+            fee_growth_inside_0_x_128,
+            fee_growth_inside_1_x_128,
+        )?;
+        assert_eq!((amount0, amount1), (expected_amount0, expected_amount1));
+        assert!(amount0 > U256::ZERO && amount1 > U256::ZERO);
+        assert_eq!(vec![(fees0, fees1)], expected_fees);
+        assert!(fees0 > U256::ZERO && fees1 > U256::ZERO);
+        assert!(pool.get_tick(-60).is_none());
+        assert_eq!(pool.slot0.liquidity, 1_000_000_000_000_000_000);
+        pool.validate()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_mint_errors() -> eyre::Result<()> {
+        let mut pool = init_test_pool()?;
+        assert!(matches!(
+            pool.mint(60, 60, 1),
+            Err(UniswapV3MathError::InvalidTickRange)
+        ));
+        assert!(matches!(
+            pool.mint(-887280, 60, 1),
+            Err(UniswapV3MathError::TickOutOfRange(-887280))
+        ));
+        assert!(matches!(
+            pool.mint(-60, 887280, 1),
+            Err(UniswapV3MathError::TickOutOfRange(887280))
+        ));
+        assert!(matches!(
+            pool.mint(-60, 50, 1),
+            Err(UniswapV3MathError::TickSpacingError)
+        ));
+        // a zero mint would initialize the ticks and flip them in the bitmap without any liquidity
+        let before = pool.snapshot();
+        assert!(matches!(
+            pool.mint(-60, 60, 0),
+            Err(UniswapV3MathError::LiquidityIsZero)
+        ));
+        assert!(before.diff(&pool.snapshot()).is_empty());
+        assert!(pool.get_tick(-60).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_mint_max_liquidity_per_tick() -> eyre::Result<()> {
+        let max_liquidity_per_tick = tick_spacing_to_max_liquidity_per_tick(60);
+        assert_eq!(max_liquidity_per_tick, 11505743598341114571880798222544994);
+
+        let mut pool = init_test_pool()?;
+        assert!(matches!(
+            pool.mint(-60, 60, max_liquidity_per_tick + 1),
+            Err(UniswapV3MathError::LO)
+        ));
+        assert!(pool.get_tick(-60).is_none());
+
+        pool.mint(-60, 60, max_liquidity_per_tick)?;
+        assert_eq!(
+            pool.get_tick(60).map(|info| info.liquidity_gross),
+            Some(max_liquidity_per_tick)
+        );
+
+        // one more wei on the full lower tick fails before the upper tick is touched
+        let before = pool.snapshot();
+        assert!(matches!(
+            pool.mint(-60, 120, 1),
+            Err(UniswapV3MathError::LO)
+        ));
+        assert!(before.diff(&pool.snapshot()).is_empty());
+        // the cap is per tick, the existing ticks of the pool still take liquidity up to it
+        pool.mint(-120, 120, max_liquidity_per_tick - 500_000_000_000_000_000)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_reserves_at_price() -> eyre::Result<()> {
+        let pool = init_test_pool()?;
+        let (amount0, amount1) = pool.tvl_amounts()?;
+        assert_eq!(pool.reserves_at_price(Q96)?, (amount0, amount1));
+
+        // moving the price up sells token0 for token1, across the tick at 120
+        let target_sqrt_price = tick_math::get_sqrt_ratio_at_tick(300)?;
+        let (up0, up1) = pool.reserves_at_price(target_sqrt_price)?;
+        assert!(up0 < amount0);
+        assert!(up1 > amount1);
+
+        // the same as the tvl after actually swapping, plus the fees paid in token1
+        let mut swapped = pool.clone_for_simulation();
+        let result = swapped.swap(false, I256::MAX, target_sqrt_price)?;
+        assert_eq!(swapped.current_sqrt_price(), target_sqrt_price);
+        let (swapped0, swapped1) = swapped.tvl_amounts()?;
+        assert!(up0.abs_diff(swapped0) <= U256::from(10));
+        assert!(up1 > swapped1);
+        assert_eq!(up1, amount1 + result.amount1_delta.into_raw());
+
+        let (down0, down1) = pool.reserves_at_price(tick_math::get_sqrt_ratio_at_tick(-300)?)?;
+        assert!(down0 > amount0);
+        assert!(down1 < amount1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tvl_amounts() -> eyre::Result<()> {
+        let pool = init_test_pool()?;
+        let (amount0, amount1) = pool.tvl_amounts()?;
+        assert_eq!(amount0, U256::from_str("69594493150554613")?);
+        assert_eq!(amount1, U256::from_str("55678462652008306")?);
+
+        // by position: (-600, 600) holds both tokens, (-1200, -120) only token1 and (120, 1800) only token0
+        let mut position_amount0 = U256::ZERO;
+        let mut position_amount1 = U256::ZERO;
+        for (tick_lower, tick_upper, liquidity) in TEST_POSITIONS {
+            let (amount0, amount1) = get_amounts_for_liquidity(
+                Q96,
+                tick_math::get_sqrt_ratio_at_tick(tick_lower)?,
+                tick_math::get_sqrt_ratio_at_tick(tick_upper)?,
+                liquidity,
+                false,
+            )?;
+            position_amount0 += amount0;
+            position_amount1 += amount1;
+        }
+        assert_eq!(position_amount0, U256::from_str("69594493150554614")?);
+        assert_eq!(position_amount1, U256::from_str("55678462652008307")?);
+        Ok(())
+    }
+}
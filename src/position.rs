@@ -0,0 +1,171 @@
+use crate::error::UniswapV3MathError;
+use crate::full_math::mul_div;
+use crate::liquidity_math;
+use crate::pool::Pool;
+use crate::sqrt_price_math::Q128;
+use crate::tick_math;
+use crate::U256_1;
+use alloy::primitives::{I256, U256};
+
+// the state of a liquidity position of an owner on [tick_lower, tick_upper)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Position {
+    // the amount of liquidity owned by this position
+    pub liquidity: u128,
+    // fee growth per unit of liquidity as of the last update to liquidity or fees owed
+    pub fee_growth_inside_0_last_x_128: U256,
+    pub fee_growth_inside_1_last_x_128: U256,
+    // the fees owed to the position owner in token0/token1, not truncated to uint128 like in Solidity
+    pub tokens_owed_0: U256,
+    pub tokens_owed_1: U256,
+}
+
+impl Position {
+    //Credits accumulated fees to the position and applies `liquidity_delta`, the fee growth inside is the current
+    //value for the range of the position, e.g. from get_fee_growth_inside
+    pub fn update(
+        &mut self,
+        liquidity_delta: i128,
+        fee_growth_inside_0_x_128: U256,
+        fee_growth_inside_1_x_128: U256,
+    ) -> Result<(), UniswapV3MathError> {
+        let liquidity_next = if liquidity_delta == 0 {
+            // disallow pokes for 0 liquidity positions
+            if self.liquidity == 0 {
+                return Err(UniswapV3MathError::LiquidityIsZero);
+            }
+            self.liquidity
+        } else {
+            liquidity_math::add_delta(self.liquidity, liquidity_delta)?
+        };
+
+        // calculate accumulated fees, the fee growth is unchecked so the differences wrap
+        let tokens_owed_0 = mul_div(
+            fee_growth_inside_0_x_128.wrapping_sub(self.fee_growth_inside_0_last_x_128),
+            U256::from(self.liquidity),
+            Q128,
+        )?;
+        let tokens_owed_1 = mul_div(
+            fee_growth_inside_1_x_128.wrapping_sub(self.fee_growth_inside_1_last_x_128),
+            U256::from(self.liquidity),
+            Q128,
+        )?;
+
+        self.liquidity = liquidity_next;
+        self.fee_growth_inside_0_last_x_128 = fee_growth_inside_0_x_128;
+        self.fee_growth_inside_1_last_x_128 = fee_growth_inside_1_x_128;
+        self.tokens_owed_0 = self.tokens_owed_0.wrapping_add(tokens_owed_0);
+        self.tokens_owed_1 = self.tokens_owed_1.wrapping_add(tokens_owed_1);
+        Ok(())
+    }
+}
+
+//Mints `positions` as (tick_lower, tick_upper, liquidity) into a copy of the pool, runs `swaps` as (zero_for_one,
+//amount_specified) without a price limit and returns the (token0, token1) fees each position earned
+pub fn simulate_lp_earnings(
+    pool: &Pool,
+    positions: &[(i32, i32, u128)],
+    swaps: &[(bool, I256)],
+) -> Result<Vec<(U256, U256)>, UniswapV3MathError> {
+    let mut pool = pool.clone_for_simulation();
+
+    let mut infos = Vec::with_capacity(positions.len());
+    for &(tick_lower, tick_upper, liquidity) in positions {
+        pool.mint(tick_lower, tick_upper, liquidity)?;
+        let (fee_growth_inside_0_x_128, fee_growth_inside_1_x_128) =
+            pool.fee_growth_inside(tick_lower, tick_upper);
+        infos.push(Position {
+            liquidity,
+            fee_growth_inside_0_last_x_128: fee_growth_inside_0_x_128,
+            fee_growth_inside_1_last_x_128: fee_growth_inside_1_x_128,
+            ..Default::default()
+        });
+    }
+
+    for &(zero_for_one, amount_specified) in swaps {
+        let sqrt_price_limit = if zero_for_one {
+            tick_math::MIN_SQRT_RATIO + U256_1
+        } else {
+            tick_math::MAX_SQRT_RATIO - U256_1
+        };
+        pool.swap(zero_for_one, amount_specified, sqrt_price_limit)?;
+    }
+
+    positions
+        .iter()
+        .zip(infos.iter_mut())
+        .map(|(&(tick_lower, tick_upper, _), info)| {
+            let (fee_growth_inside_0_x_128, fee_growth_inside_1_x_128) =
+                pool.fee_growth_inside(tick_lower, tick_upper);
+            info.update(0, fee_growth_inside_0_x_128, fee_growth_inside_1_x_128)?;
+            Ok((info.tokens_owed_0, info.tokens_owed_1))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{simulate_lp_earnings, Position};
+    use crate::{error::UniswapV3MathError, pool::test::init_test_pool, sqrt_price_math::Q128};
+    use alloy::primitives::{I256, U256};
+    use std::str::FromStr;
+
+    #[test]
+    fn test_position_update() -> eyre::Result<()> {
+        let mut position = Position::default();
+        assert!(matches!(
+            position.update(0, U256::ZERO, U256::ZERO),
+            Err(UniswapV3MathError::LiquidityIsZero)
+        ));
+
+        position.update(1000, Q128, U256::ZERO.wrapping_sub(Q128))?;
+        assert_eq!(position.liquidity, 1000);
+        assert_eq!(position.tokens_owed_0, U256::ZERO);
+
+        // 2.5 token0 and 1 token1 per unit of liquidity since the last update, the token1 growth wrapped
+        position.update(-400, Q128 * U256::from(7) / U256::from(2), U256::ZERO)?;
+        assert_eq!(position.liquidity, 600);
+        assert_eq!(position.tokens_owed_0, U256::from(2500));
+        assert_eq!(position.tokens_owed_1, U256::from(1000));
+
+        // fees accrue on the remaining liquidity
+        position.update(0, Q128 * U256::from(9) / U256::from(2), U256::ZERO)?;
+        assert_eq!(position.tokens_owed_0, U256::from(3100));
+        assert_eq!(position.tokens_owed_1, U256::from(1000));
+        Ok(())
+    }
+
+    #[test]
+    fn test_simulate_lp_earnings() -> eyre::Result<()> {
+        let pool = init_test_pool()?;
+
+        // down to tick -762 and back up to tick 16, crossing -60 twice
+        let earnings = simulate_lp_earnings(
+            &pool,
+            &[
+                (-60, 60, 1_000_000_000_000_000_000),
+                (3000, 3600, 1_000_000_000_000_000_000),
+            ],
+            &[
+                (true, I256::from_str("50000000000000000")?),
+                (false, I256::from_str("50000000000000000")?),
+            ],
+        )?;
+
+        assert_eq!(
+            earnings,
+            vec![
+                (
+                    U256::from_str("9040182736435")?,
+                    U256::from_str("11443939843556")?
+                ),
+                (U256::ZERO, U256::ZERO),
+            ]
+        );
+
+        // the pool itself is left untouched
+        assert_eq!(pool.fee_growth_global(), (U256::ZERO, U256::ZERO));
+        assert!(pool.get_tick(-60).is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,283 @@
+use crate::error::UniswapV3MathError;
+use crate::pool::Pool;
+use crate::tick_math;
+use crate::U256_1;
+use alloy::primitives::{I256, U256};
+
+//Mirrors QuoterV2.quoteExactInputSingle: simulates an exact input swap of `amount_in` on the pool and returns
+//(amount_out, sqrt_price_after, tick_after, ticks_crossed). A zero `sqrt_price_limit` means no limit, with a limit
+//the swap may stop early and use up less than `amount_in`
+pub fn quote_exact_input_single(
+    pool: &Pool,
+    zero_for_one: bool,
+    amount_in: impl Into<U256>,
+    sqrt_price_limit: U256,
+) -> Result<(U256, U256, i32, u32), UniswapV3MathError> {
+    let amount_specified = I256::from_raw(amount_in.into());
+    if amount_specified.is_negative() {
+        return Err(UniswapV3MathError::Overflow);
+    }
+
+    let result = pool.simulate(
+        zero_for_one,
+        amount_specified,
+        price_limit_or_bound(zero_for_one, sqrt_price_limit),
+    )?;
+    let amount_out = if zero_for_one {
+        -result.amount1_delta
+    } else {
+        -result.amount0_delta
+    };
+    let ticks_crossed =
+        u32::try_from(result.tick_crossings.len()).map_err(|_| UniswapV3MathError::Overflow)?;
+
+    Ok((
+        amount_out.into_raw(),
+        result.sqrt_price_after,
+        result.tick_after,
+        ticks_crossed,
+    ))
+}
+
+//Mirrors QuoterV2.quoteExactOutputSingle: simulates an exact output swap of `amount_out` on the pool and returns
+//(amount_in, sqrt_price_after, tick_after, ticks_crossed). A zero `sqrt_price_limit` means no limit, in which case
+//the full output must be received, as the Quoter requires. With a limit the swap may stop early and the amount in
+//is for the partial output
+pub fn quote_exact_output_single(
+    pool: &Pool,
+    zero_for_one: bool,
+    amount_out: impl Into<U256>,
+    sqrt_price_limit: U256,
+) -> Result<(U256, U256, i32, u32), UniswapV3MathError> {
+    let amount_specified = I256::from_raw(amount_out.into());
+    if amount_specified.is_negative() {
+        return Err(UniswapV3MathError::Overflow);
+    }
+
+    let no_limit = sqrt_price_limit.is_zero();
+    let result = pool.simulate(
+        zero_for_one,
+        -amount_specified,
+        price_limit_or_bound(zero_for_one, sqrt_price_limit),
+    )?;
+    let (amount_in, amount_received) = if zero_for_one {
+        (result.amount0_delta, -result.amount1_delta)
+    } else {
+        (result.amount1_delta, -result.amount0_delta)
+    };
+
+    //require(amountReceived == amountOut);
+    if no_limit && amount_received != amount_specified {
+        return Err(UniswapV3MathError::InsufficientOutput);
+    }
+
+    let ticks_crossed =
+        u32::try_from(result.tick_crossings.len()).map_err(|_| UniswapV3MathError::Overflow)?;
+
+    Ok((
+        amount_in.into_raw(),
+        result.sqrt_price_after,
+        result.tick_after,
+        ticks_crossed,
+    ))
+}
+
+// the price bound in the direction of the swap for a zero sqrt_price_limit, as in QuoterV2
+fn price_limit_or_bound(zero_for_one: bool, sqrt_price_limit: U256) -> U256 {
+    match (sqrt_price_limit.is_zero(), zero_for_one) {
+        (false, _) => sqrt_price_limit,
+        (true, true) => tick_math::MIN_SQRT_RATIO + U256_1,
+        (true, false) => tick_math::MAX_SQRT_RATIO - U256_1,
+    }
+}
+
+// the quotes of QuoterV2 against a pool, for integrators used to the on-chain Quoter
+#[derive(Debug, Clone, Copy)]
+pub struct Quoter<'a> {
+    pub pool: &'a Pool,
+}
+
+impl<'a> Quoter<'a> {
+    pub fn new(pool: &'a Pool) -> Self {
+        Quoter { pool }
+    }
+
+    //See quote_exact_input_single
+    pub fn quote_exact_input_single(
+        &self,
+        zero_for_one: bool,
+        amount_in: impl Into<U256>,
+        sqrt_price_limit: U256,
+    ) -> Result<(U256, U256, i32, u32), UniswapV3MathError> {
+        quote_exact_input_single(self.pool, zero_for_one, amount_in, sqrt_price_limit)
+    }
+
+    //See quote_exact_output_single
+    pub fn quote_exact_output_single(
+        &self,
+        zero_for_one: bool,
+        amount_out: impl Into<U256>,
+        sqrt_price_limit: U256,
+    ) -> Result<(U256, U256, i32, u32), UniswapV3MathError> {
+        quote_exact_output_single(self.pool, zero_for_one, amount_out, sqrt_price_limit)
+    }
+
+    //Mirrors QuoterV2.quoteExactInput for a path starting with an exact input swap on the pool, `zero_for_one` on
+    //it, followed by the (pool, zero_for_one) hops of `next_hops`. The output of each hop is the input of the next,
+    //without price limits. Returns (amount_out, sqrt_price_after_list, ticks_crossed_list) with one entry per hop
+    pub fn quote_exact_input(
+        &self,
+        zero_for_one: bool,
+        next_hops: &[(&Pool, bool)],
+        amount_in: impl Into<U256>,
+    ) -> Result<(U256, Vec<U256>, Vec<u32>), UniswapV3MathError> {
+        let mut amount = amount_in.into();
+        let mut sqrt_price_after_list = Vec::with_capacity(next_hops.len() + 1);
+        let mut ticks_crossed_list = Vec::with_capacity(next_hops.len() + 1);
+        for (pool, zero_for_one) in std::iter::once((self.pool, zero_for_one)).chain(
+            next_hops
+                .iter()
+                .map(|(pool, zero_for_one)| (*pool, *zero_for_one)),
+        ) {
+            let (amount_out, sqrt_price_after, _, ticks_crossed) =
+                quote_exact_input_single(pool, zero_for_one, amount, U256::ZERO)?;
+            amount = amount_out;
+            sqrt_price_after_list.push(sqrt_price_after);
+            ticks_crossed_list.push(ticks_crossed);
+        }
+        Ok((amount, sqrt_price_after_list, ticks_crossed_list))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{quote_exact_output_single, Quoter};
+    use crate::{error::UniswapV3MathError, pool::test::init_minted_test_pool, tick_math};
+    use alloy::primitives::{I256, U256};
+    use std::str::FromStr;
+
+    // a full range position and a second position over [-60, 60)
+    const QUOTER_TEST_POSITIONS: [(i32, i32, u128); 2] = [
+        (-887220, 887220, 1_000_000_000_000_000_000),
+        (-60, 60, 1_000_000_000_000_000_000),
+    ];
+
+    #[test]
+    fn test_quote_exact_output_single() -> eyre::Result<()> {
+        let pool = init_minted_test_pool(&QUOTER_TEST_POSITIONS)?;
+        let amount_out = U256::from(10_000_000_000_000_000_u128);
+
+        // token1 out, crossing -60
+        let (amount_in, sqrt_price_after, tick_after, ticks_crossed) =
+            quote_exact_output_single(&pool, true, amount_out, U256::ZERO)?;
+        assert_eq!(amount_in, U256::from_str("10088676295547296")?);
+        assert_eq!(
+            sqrt_price_after,
+            U256::from_str("78673197358356500590191811338")?
+        );
+        assert_eq!(tick_after, -141);
+        assert_eq!(ticks_crossed, 1);
+
+        // the same as a swap with a negative amount specified
+        let result = pool.simulate(
+            true,
+            -I256::from_raw(amount_out),
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+        )?;
+        assert_eq!(result.amount0_delta, I256::from_raw(amount_in));
+        assert_eq!(result.amount1_delta, -I256::from_raw(amount_out));
+        assert_eq!(result.sqrt_price_after, sqrt_price_after);
+        assert_eq!(result.tick_after, tick_after);
+        assert_eq!(result.tick_crossings.len(), 1);
+
+        // token0 out, crossing 60
+        let (amount_in, _, tick_after, ticks_crossed) =
+            quote_exact_output_single(&pool, false, amount_out, U256::ZERO)?;
+        assert_eq!(amount_in, U256::from_str("10088676295547296")?);
+        assert_eq!(tick_after, 140);
+        assert_eq!(ticks_crossed, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_quote_exact_output_single_price_limit() -> eyre::Result<()> {
+        let pool = init_minted_test_pool(&QUOTER_TEST_POSITIONS)?;
+        let amount_out = U256::from(10_000_000_000_000_000_u128);
+        let sqrt_price_limit = tick_math::get_sqrt_ratio_at_tick(-100)?;
+
+        // with a limit the quote is for the partial output up to the limit
+        let (amount_in, sqrt_price_after, tick_after, ticks_crossed) =
+            quote_exact_output_single(&pool, true, amount_out, sqrt_price_limit)?;
+        assert_eq!(amount_in, U256::from_str("8040745923563823")?);
+        assert_eq!(sqrt_price_after, sqrt_price_limit);
+        assert_eq!(tick_after, -100);
+        assert_eq!(ticks_crossed, 1);
+
+        // without a limit the whole output must be available
+        let empty_pool = init_minted_test_pool(&[])?;
+        assert!(matches!(
+            quote_exact_output_single(&empty_pool, true, amount_out, U256::ZERO),
+            Err(UniswapV3MathError::InsufficientOutput)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_quoter() -> eyre::Result<()> {
+        let pool = init_minted_test_pool(&QUOTER_TEST_POSITIONS)?;
+        let quoter = Quoter::new(&pool);
+        let amount = U256::from(10_000_000_000_000_000_u128);
+        let min_limit = tick_math::MIN_SQRT_RATIO + U256::from(1);
+
+        let (amount_out, sqrt_price_after, tick_after, ticks_crossed) =
+            quoter.quote_exact_input_single(true, amount, U256::ZERO)?;
+        let result = pool.simulate(true, I256::from_raw(amount), min_limit)?;
+        assert_eq!(result.amount0_delta, I256::from_raw(amount));
+        assert_eq!(result.amount1_delta, -I256::from_raw(amount_out));
+        assert_eq!(result.sqrt_price_after, sqrt_price_after);
+        assert_eq!(result.tick_after, tick_after);
+        assert_eq!(result.tick_crossings.len() as u32, ticks_crossed);
+        assert_eq!(ticks_crossed, 1);
+
+        let (amount_in, sqrt_price_after, tick_after, _) =
+            quoter.quote_exact_output_single(true, amount, U256::ZERO)?;
+        let result = pool.simulate(true, -I256::from_raw(amount), min_limit)?;
+        assert_eq!(result.amount0_delta, I256::from_raw(amount_in));
+        assert_eq!(result.sqrt_price_after, sqrt_price_after);
+        assert_eq!(result.tick_after, tick_after);
+
+        // token0 to token1 on the pool and back to token0 on a copy of it
+        let other = init_minted_test_pool(&QUOTER_TEST_POSITIONS)?;
+        let (amount_out, sqrt_price_after_list, ticks_crossed_list) =
+            quoter.quote_exact_input(true, &[(&other, false)], amount)?;
+        let first = pool.simulate(true, I256::from_raw(amount), min_limit)?;
+        let second = other.simulate(
+            false,
+            -first.amount1_delta,
+            tick_math::MAX_SQRT_RATIO - U256::from(1),
+        )?;
+        assert_eq!(amount_out, (-second.amount0_delta).into_raw());
+        assert!(amount_out < amount);
+        assert_eq!(
+            sqrt_price_after_list,
+            vec![first.sqrt_price_after, second.sqrt_price_after]
+        );
+        assert_eq!(
+            ticks_crossed_list,
+            vec![
+                first.tick_crossings.len() as u32,
+                second.tick_crossings.len() as u32
+            ]
+        );
+
+        // a single hop path is the single quote
+        let (single_amount_out, _, _) = quoter.quote_exact_input(false, &[], amount)?;
+        assert_eq!(
+            single_amount_out,
+            quoter
+                .quote_exact_input_single(false, amount, U256::ZERO)?
+                .0
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+use crate::error::UniswapV3MathError;
+use crate::pool::Pool;
+use crate::sqrt_price_math::{_get_amount_0_delta, _get_amount_1_delta};
+use crate::tick_math::get_sqrt_ratio_at_tick;
+use alloy::primitives::{I256, U256};
+
+//Simulates a range order, i.e. the "limit order" use of a position on a range next to the current price.
+//The position of `liquidity` on [tick_lower, tick_upper) is minted into a copy of the pool, which is then swapped
+//until the range is fully crossed. zero_for_one orders sell token0 and must be placed above the current tick,
+//the others sell token1 below it.
+//Returns (amount_in, amount_out): the amount of the sold token deposited when minting and the amount of the bought
+//token the position holds once filled, not including the swap fees it earned
+pub fn simulate_range_order(
+    pool: &Pool,
+    tick_lower: i32,
+    tick_upper: i32,
+    liquidity: u128,
+    zero_for_one: bool,
+) -> Result<(U256, U256), UniswapV3MathError> {
+    let tick_current = pool.current_tick();
+    if (zero_for_one && tick_current >= tick_lower) || (!zero_for_one && tick_current < tick_upper)
+    {
+        return Err(UniswapV3MathError::RangeOrderInRange);
+    }
+
+    let mut pool = pool.clone();
+    // the position is single sided while out of range, the amounts minting it costs are rounded up
+    let (amount0, amount1, _) = pool.mint(tick_lower, tick_upper, liquidity)?;
+    let amount_in = if zero_for_one { amount0 } else { amount1 };
+
+    let sqrt_ratio_lower_x_96 = get_sqrt_ratio_at_tick(tick_lower)?;
+    let sqrt_ratio_upper_x_96 = get_sqrt_ratio_at_tick(tick_upper)?;
+
+    // an order selling token0 is filled by swaps of token1 for token0 pushing the price up through the range
+    let sqrt_price_limit = if zero_for_one {
+        sqrt_ratio_upper_x_96
+    } else {
+        sqrt_ratio_lower_x_96
+    };
+    pool.swap(!zero_for_one, I256::MAX, sqrt_price_limit)?;
+
+    let sqrt_price = pool.current_sqrt_price();
+    let amount_out = if zero_for_one {
+        _get_amount_1_delta(
+            sqrt_ratio_lower_x_96,
+            sqrt_price.clamp(sqrt_ratio_lower_x_96, sqrt_ratio_upper_x_96),
+            liquidity,
+            false,
+        )?
+    } else {
+        _get_amount_0_delta(
+            sqrt_price.clamp(sqrt_ratio_lower_x_96, sqrt_ratio_upper_x_96),
+            sqrt_ratio_upper_x_96,
+            liquidity,
+            false,
+        )?
+    };
+
+    Ok((amount_in, amount_out))
+}
+
+#[cfg(test)]
+mod test {
+    use super::simulate_range_order;
+    use crate::{
+        error::UniswapV3MathError, pool::test::init_minted_test_pool, sqrt_price_math::Q96,
+    };
+    use alloy::primitives::U256;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_simulate_range_order() -> eyre::Result<()> {
+        // a single full range position
+        let pool = init_minted_test_pool(&[(-887220, 887220, 1_000_000_000_000_000_000)])?;
+
+        // sell token0 over the single spacing [60, 120)
+        let (amount_in, amount_out) =
+            simulate_range_order(&pool, 60, 120, 1_000_000_000_000_000_000, true)?;
+        assert_eq!(amount_in, U256::from_str("2986382804598882")?);
+        assert_eq!(amount_out, U256::from_str("3013380206076239")?);
+
+        // sell token1 over [-120, -60), the mirror image
+        let (amount_in, amount_out) =
+            simulate_range_order(&pool, -120, -60, 1_000_000_000_000_000_000, false)?;
+        assert_eq!(amount_in, U256::from_str("2986382804598882")?);
+        assert_eq!(amount_out, U256::from_str("3013380206076239")?);
+
+        // the pool itself is left untouched
+        assert_eq!(pool.current_sqrt_price(), Q96);
+        assert_eq!(pool.current_liquidity(), 1_000_000_000_000_000_000);
+        assert!(pool.get_tick(60).is_none());
+
+        // a range containing the current tick is a regular position, not a range order
+        assert!(matches!(
+            simulate_range_order(&pool, -60, 60, 1_000_000_000_000_000_000, true),
+            Err(UniswapV3MathError::RangeOrderInRange)
+        ));
+        assert!(matches!(
+            simulate_range_order(&pool, 0, 60, 1_000_000_000_000_000_000, false),
+            Err(UniswapV3MathError::RangeOrderInRange)
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use crate::error::UniswapV3MathError;
+use crate::pool::Pool;
+use crate::tick_math;
+use crate::U256_1;
+use alloy::primitives::{I256, U256};
+
+//Splits an exact input `amount_in` across pools trading the same pair to maximize the total output. The amount is
+//allocated in `splits` equal chunks (the last one takes the rounding remainder), each going to the pool with the
+//largest marginal output for it given what was allocated so far. Every chunk simulates a swap on every pool.
+//Returns (pool index, amount) for the pools that get a non zero allocation, in pool order
+pub fn best_split(
+    pools: &[Pool],
+    zero_for_one: bool,
+    amount_in: U256,
+    splits: u32,
+) -> Result<Vec<(usize, U256)>, UniswapV3MathError> {
+    if I256::from_raw(amount_in).is_negative() {
+        return Err(UniswapV3MathError::Overflow);
+    }
+    let splits = splits.max(1);
+    let chunk = amount_in / U256::from(splits);
+    let last_chunk = amount_in - chunk * U256::from(splits - 1);
+
+    let mut allocations = vec![U256::ZERO; pools.len()];
+    let mut outputs = vec![U256::ZERO; pools.len()];
+    for split in 1..=splits {
+        let chunk = if split == splits { last_chunk } else { chunk };
+        if chunk.is_zero() {
+            continue;
+        }
+
+        // (pool index, output of the pool with the chunk added, marginal output of the chunk)
+        let mut best: Option<(usize, U256, U256)> = None;
+        for (index, pool) in pools.iter().enumerate() {
+            let output = exact_input_output(pool, zero_for_one, allocations[index] + chunk)?;
+            let marginal_output = output.saturating_sub(outputs[index]);
+            match best {
+                Some((_, _, best_marginal_output)) if best_marginal_output >= marginal_output => {}
+                _ => best = Some((index, output, marginal_output)),
+            }
+        }
+
+        if let Some((index, output, _)) = best {
+            allocations[index] += chunk;
+            outputs[index] = output;
+        }
+    }
+
+    Ok(allocations
+        .into_iter()
+        .enumerate()
+        .filter(|(_, allocation)| !allocation.is_zero())
+        .collect())
+}
+
+// the output amount of an exact input swap without a price limit
+fn exact_input_output(
+    pool: &Pool,
+    zero_for_one: bool,
+    amount_in: U256,
+) -> Result<U256, UniswapV3MathError> {
+    let sqrt_price_limit = if zero_for_one {
+        tick_math::MIN_SQRT_RATIO + U256_1
+    } else {
+        tick_math::MAX_SQRT_RATIO - U256_1
+    };
+    let result = pool.simulate(zero_for_one, I256::from_raw(amount_in), sqrt_price_limit)?;
+    let amount_out = if zero_for_one {
+        result.amount1_delta
+    } else {
+        result.amount0_delta
+    };
+    Ok(amount_out.unsigned_abs())
+}
+
+#[cfg(test)]
+mod test {
+    use super::best_split;
+    use crate::{pool::Pool, sqrt_price_math::Q96, swap::Slot0};
+    use alloy::primitives::U256;
+    use std::collections::HashMap;
+
+    // a 0.3% pool at tick 0 with constant liquidity over the whole price range
+    fn constant_liquidity_pool(liquidity: u128) -> eyre::Result<Pool> {
+        Ok(Pool::new(
+            HashMap::new(),
+            HashMap::new(),
+            60,
+            3000,
+            Slot0 {
+                sqrt_price: Q96,
+                liquidity,
+                tick: 0,
+            },
+        )?)
+    }
+
+    #[test]
+    fn test_best_split() -> eyre::Result<()> {
+        let pools = [
+            constant_liquidity_pool(1_000_000_000_000_000_000)?,
+            constant_liquidity_pool(4_000_000_000_000_000_000)?,
+        ];
+        let amount_in = U256::from(10_000_000_000_000_000_u128);
+
+        // at the same price the price impact is inverse to the depth, so the split follows the liquidity
+        let split = best_split(&pools, true, amount_in, 10)?;
+        assert_eq!(
+            split,
+            vec![
+                (0, U256::from(2_000_000_000_000_000_u128)),
+                (1, U256::from(8_000_000_000_000_000_u128)),
+            ]
+        );
+        let allocated = split
+            .iter()
+            .fold(U256::ZERO, |allocated, (_, amount)| allocated + amount);
+        assert_eq!(allocated, amount_in);
+
+        // a single chunk all goes to the deeper pool
+        assert_eq!(
+            best_split(&pools, false, amount_in, 1)?,
+            vec![(1, amount_in)]
+        );
+        assert_eq!(best_split(&[], true, amount_in, 10)?, vec![]);
+        Ok(())
+    }
+}
@@ -9,6 +9,7 @@ use crate::{
 pub const MAX_U160: U256 =
     U256::from_limbs([18446744073709551615, 18446744073709551615, 4294967295, 0]);
 pub const Q96: U256 = U256::from_limbs([0, 4294967296, 0, 0]);
+pub const Q128: U256 = U256::from_limbs([0, 0, 1, 0]);
 pub const FIXED_POINT_96_RESOLUTION: U256 = U256::from_limbs([96, 0, 0, 0]);
 
 // returns (sqrtQX96)
@@ -115,18 +116,19 @@ pub fn get_next_sqrt_price_from_amount_1_rounding_down(
             Ok(next_sqrt_price)
         }
     } else {
+        //an amount whose quotient overflows U256 is always more than the pool can pay out
         let quotient = if amount <= MAX_U160 {
             div_rounding_up(amount << FIXED_POINT_96_RESOLUTION, liquidity)
         } else {
-            mul_div_rounding_up(amount, Q96, liquidity)?
+            mul_div_rounding_up(amount, Q96, liquidity)
+                .map_err(|_| UniswapV3MathError::SqrtPriceIsLteQuotient)?
         };
 
         //require(sqrtPX96 > quotient);
-        if sqrt_price_x_96 <= quotient {
-            return Err(UniswapV3MathError::SqrtPriceIsLteQuotient);
-        }
-
-        Ok(sqrt_price_x_96.overflowing_sub(quotient).0)
+        sqrt_price_x_96
+            .checked_sub(quotient)
+            .filter(|next_sqrt_price| !next_sqrt_price.is_zero())
+            .ok_or(UniswapV3MathError::SqrtPriceIsLteQuotient)
     }
 }
 
@@ -424,6 +426,30 @@ mod test {
             "Sqrt price is less than or equal to quotient"
         );
 
+        //fails if the output amount overflows the quotient instead of reverting on mul_div
+        let result = get_next_sqrt_price_from_output(
+            U256::from_str("20282409603651670423947251286016").unwrap(),
+            1024,
+            U256::MAX,
+            true,
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Sqrt price is less than or equal to quotient"
+        );
+
+        //fails if output amount is above max u160 and greater than virtual reserves of token1
+        let result = get_next_sqrt_price_from_output(
+            U256::from_str("20282409603651670423947251286016").unwrap(),
+            u128::MAX,
+            MAX_U160 + U256_1,
+            true,
+        );
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Sqrt price is less than or equal to quotient"
+        );
+
         //succeeds if output amount is just less than the virtual
         let result = get_next_sqrt_price_from_output(
             U256::from_str("20282409603651670423947251286016").unwrap(),
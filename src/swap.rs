@@ -1,13 +1,19 @@
 use crate::error::UniswapV3MathError;
+use crate::full_math::{mul_div, mul_div_rounding_up};
 use crate::liquidity_math;
+use crate::sqrt_price_math::{_get_amount_0_delta, _get_amount_1_delta, Q128, Q96};
 use crate::swap_math;
 use crate::tick::Tick;
-use crate::tick_bitmap;
 use crate::tick_math;
+use crate::tick_provider::{HashMapTickProvider, ScanBudgetTickProvider, TickProvider, WordCache};
+use crate::U256_1;
 use alloy::primitives::{I256, U256};
 use std::collections::HashMap;
+use std::str::FromStr;
 
 // the current state of the pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Slot0 {
     // the current price
     pub sqrt_price: U256,
@@ -16,35 +22,416 @@ pub struct Slot0 {
     pub tick: i32,
 }
 
-#[derive(Debug)]
+// the pool at tick 0, i.e. a price of 1, without liquidity
+impl Default for Slot0 {
+    fn default() -> Self {
+        Slot0 {
+            sqrt_price: Q96,
+            liquidity: 0,
+            tick: 0,
+        }
+    }
+}
+
+impl Slot0 {
+    //Builds a slot0, failing with InconsistentTick if the tick does not contain the sqrt price, see
+    //tick_consistency_check
+    pub fn new(sqrt_price: U256, liquidity: u128, tick: i32) -> Result<Slot0, UniswapV3MathError> {
+        let slot0 = Slot0 {
+            sqrt_price,
+            liquidity,
+            tick,
+        };
+        slot0.tick_consistency_check()?;
+        Ok(slot0)
+    }
+
+    //Returns the raw price of token0 in token1 at the sqrt price, sqrt_price^2 as a float, the same conversion as
+    //tick_math::tick_to_price
+    pub fn price(&self) -> f64 {
+        tick_math::sqrt_price_x_96_to_price(self.sqrt_price)
+    }
+
+    //Checks that the tick is the one whose range contains the sqrt price. A swap that stops exactly on a tick boundary
+    //while moving down leaves the tick one below get_tick_at_sqrt_ratio, as in UniswapV3Pool, so that is accepted too
+    pub fn tick_consistency_check(&self) -> Result<(), UniswapV3MathError> {
+        let expected = tick_math::get_tick_at_sqrt_ratio(self.sqrt_price)?;
+        if self.tick == expected {
+            return Ok(());
+        }
+
+        if self.tick == expected - 1
+            && tick_math::get_sqrt_ratio_at_tick(expected)? == self.sqrt_price
+        {
+            return Ok(());
+        }
+
+        Err(UniswapV3MathError::InconsistentTick {
+            tick: self.tick,
+            expected,
+        })
+    }
+}
+
+// the direction of a swap, zero_for_one in the swap functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SwapDirection {
+    ZeroForOne,
+    OneForZero,
+}
+
+impl SwapDirection {
+    pub fn zero_for_one(&self) -> bool {
+        matches!(self, SwapDirection::ZeroForOne)
+    }
+}
+
+impl FromStr for SwapDirection {
+    type Err = UniswapV3MathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "0to1" | "zeroForOne" | "zero_for_one" => Ok(SwapDirection::ZeroForOne),
+            "1to0" | "oneForZero" | "one_for_zero" => Ok(SwapDirection::OneForZero),
+            _ => Err(UniswapV3MathError::UnknownSwapDirection(s.to_string())),
+        }
+    }
+}
+
+// the amount of a swap, in place of the sign of amount_specified in swap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapAmount {
+    // swap in this amount of the input token, less if the price limit is reached first
+    ExactInput { amount: U256 },
+    // receive this amount of the output token, less if the price limit is reached first
+    ExactOutput { amount: U256 },
+}
+
+impl SwapAmount {
+    //Returns the amount_specified of swap, failing with Overflow for amounts above I256::MAX
+    pub fn amount_specified(&self) -> Result<I256, UniswapV3MathError> {
+        let (amount, exact_input) = match *self {
+            SwapAmount::ExactInput { amount } => (amount, true),
+            SwapAmount::ExactOutput { amount } => (amount, false),
+        };
+        let amount = i256_from_amount(amount)?;
+        Ok(if exact_input { amount } else { -amount })
+    }
+}
+
+// an amount_specified of swap built from its direction, so the sign convention is spelled out at the call site.
+// Converts into the I256 taken by swap, positive for an exact input and negative for an exact output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Amount(I256);
+
+impl Amount {
+    // swap in this amount of the input token
+    pub fn exact_in(amount: u128) -> Self {
+        Amount(I256::from_raw(U256::from(amount)))
+    }
+
+    // receive this amount of the output token
+    pub fn exact_out(amount: u128) -> Self {
+        Amount(-I256::from_raw(U256::from(amount)))
+    }
+
+    pub fn is_exact_input(&self) -> bool {
+        !self.0.is_negative()
+    }
+
+    pub fn amount_specified(&self) -> I256 {
+        self.0
+    }
+}
+
+impl From<Amount> for I256 {
+    fn from(amount: Amount) -> I256 {
+        amount.0
+    }
+}
+
+// an amount_specified following the sign convention of swap
+impl From<I256> for Amount {
+    fn from(amount_specified: I256) -> Amount {
+        Amount(amount_specified)
+    }
+}
+
+// an exact input, as the unsigned amounts of the quotes
+impl From<u128> for Amount {
+    fn from(amount: u128) -> Amount {
+        Amount::exact_in(amount)
+    }
+}
+
+// an amount_specified following the sign convention of swap
+impl From<i128> for Amount {
+    fn from(amount_specified: i128) -> Amount {
+        let amount = I256::from_raw(U256::from(amount_specified.unsigned_abs()));
+        Amount(if amount_specified < 0 {
+            -amount
+        } else {
+            amount
+        })
+    }
+}
+
+impl From<Amount> for SwapAmount {
+    fn from(amount: Amount) -> SwapAmount {
+        let amount_specified = amount.0.unsigned_abs();
+        if amount.is_exact_input() {
+            SwapAmount::ExactInput {
+                amount: amount_specified,
+            }
+        } else {
+            SwapAmount::ExactOutput {
+                amount: amount_specified,
+            }
+        }
+    }
+}
+
+// turns an exact input into an exact output of the same amount and vice versa
+impl std::ops::Neg for Amount {
+    type Output = Amount;
+
+    fn neg(self) -> Amount {
+        Amount(-self.0)
+    }
+}
+
+// e.g. "+1000 (exact in)" or "-1000 (exact out)"
+impl std::fmt::Display for Amount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_exact_input() {
+            write!(f, "+{} (exact in)", self.0)
+        } else {
+            write!(f, "{} (exact out)", self.0)
+        }
+    }
+}
+
+// the (zero_for_one, amount_specified, sqrt_price_limit) of swap for a swap_exact, no limit is the price bound
+pub(crate) fn exact_swap_params(
+    direction: SwapDirection,
+    amount: SwapAmount,
+    sqrt_price_limit: Option<U256>,
+) -> Result<(bool, I256, U256), UniswapV3MathError> {
+    let zero_for_one = direction.zero_for_one();
+    let sqrt_price_limit = sqrt_price_limit.unwrap_or(if zero_for_one {
+        tick_math::MIN_SQRT_RATIO + U256_1
+    } else {
+        tick_math::MAX_SQRT_RATIO - U256_1
+    });
+    Ok((zero_for_one, amount.amount_specified()?, sqrt_price_limit))
+}
+
+// the unsigned amounts of a swap in the given direction, also when it did not move
+pub(crate) fn unsigned_swap_result(result: &SwapResult, zero_for_one: bool) -> UnsignedSwapResult {
+    let (amount_in, amount_out, token_in, token_out) = if zero_for_one {
+        (result.amount0_delta, result.amount1_delta, 0, 1)
+    } else {
+        (result.amount1_delta, result.amount0_delta, 1, 0)
+    };
+    UnsignedSwapResult {
+        amount_in: amount_in.unsigned_abs(),
+        amount_out: amount_out.unsigned_abs(),
+        token_in,
+        token_out,
+        sqrt_price_after: result.sqrt_price_after,
+        liquidity_after: result.liquidity_after,
+        tick_after: result.tick_after,
+    }
+}
+
+// an initialized tick crossed during a swap
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TickCrossing {
+    pub tick: i32,
+    // the fee growth per unit of liquidity in the input token accrued by the swap up to the crossing,
+    // added to the fee growth global before the swap it is the global used to flip the tick's fee growth outside
+    pub fee_growth_x_128: U256,
+}
+
+// a rough estimate of what a swap costs on chain, for integrators budgeting gas or compute on a metered chain
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SwapCost {
+    // the iterations of the swap loop, each one searches the tick bitmap and computes a swap step
+    pub iterations: u32,
+    pub ticks_crossed: u32,
+    // the storage slots UniswapV3Pool writes: slot0 and the fee growth global of the input token, the liquidity if
+    // any tick was crossed and three slots of each crossed tick. Observations and protocol fees are not counted
+    pub estimated_sstores: u32,
+}
+
+impl SwapCost {
+    fn new(iterations: u32, ticks_crossed: u32) -> Self {
+        SwapCost {
+            iterations,
+            ticks_crossed,
+            estimated_sstores: 2 + (ticks_crossed > 0) as u32 + 3 * ticks_crossed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SwapResult {
+    #[cfg_attr(feature = "serde", serde(with = "crate::swap_json::i256_decimal"))]
     pub amount0_delta: I256,
+    #[cfg_attr(feature = "serde", serde(with = "crate::swap_json::i256_decimal"))]
     pub amount1_delta: I256,
     pub sqrt_price_after: U256,
     pub liquidity_after: u128,
     pub tick_after: i32,
+    // the fee growth per unit of liquidity accrued by the swap in the input token, wrapping added to the
+    // pool's fee growth global of that token like the unchecked accumulation in UniswapV3Pool
+    pub fee_growth_x_128: U256,
+    // the initialized ticks crossed in order
+    pub tick_crossings: Vec<TickCrossing>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub cost: SwapCost,
+}
+
+impl SwapResult {
+    //Reconciles two results, e.g. a local simulation against the on-chain Swap event: the amount deltas must be within
+    //`wei_tolerance` of each other and the prices after within one tick. Liquidity and fee growth are not compared
+    pub fn approx_eq(&self, other: &SwapResult, wei_tolerance: u128) -> bool {
+        let tolerance = U256::from(wei_tolerance);
+        let within_tolerance = |a: I256, b: I256| {
+            a.checked_sub(b)
+                .is_some_and(|diff| diff.unsigned_abs() <= tolerance)
+        };
+
+        within_tolerance(self.amount0_delta, other.amount0_delta)
+            && within_tolerance(self.amount1_delta, other.amount1_delta)
+            && self.tick_after.abs_diff(other.tick_after) <= 1
+    }
+
+    //Converts the deltas to the amount in and out of the pool. The input token is the one with the positive delta,
+    //token0 if neither delta is positive (a swap that did not move)
+    pub fn as_unsigned(&self) -> UnsignedSwapResult {
+        let (amount_in, amount_out, token_in, token_out) = if self.amount1_delta.is_positive() {
+            (self.amount1_delta, self.amount0_delta, 1, 0)
+        } else {
+            (self.amount0_delta, self.amount1_delta, 0, 1)
+        };
+
+        UnsignedSwapResult {
+            amount_in: amount_in.unsigned_abs(),
+            amount_out: amount_out.unsigned_abs(),
+            token_in,
+            token_out,
+            sqrt_price_after: self.sqrt_price_after,
+            liquidity_after: self.liquidity_after,
+            tick_after: self.tick_after,
+        }
+    }
+
+    //Returns the state the swap left the pool in, to advance a stored Slot0 after calling swap
+    pub fn to_slot0(&self) -> Slot0 {
+        Slot0 {
+            sqrt_price: self.sqrt_price_after,
+            liquidity: self.liquidity_after,
+            tick: self.tick_after,
+        }
+    }
+}
+
+// a SwapResult with the signed deltas replaced by unsigned amounts and the index of the token they are in, for
+// bindings to environments without big signed integers
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnsignedSwapResult {
+    // the amount the pool received
+    pub amount_in: U256,
+    // the amount the pool paid out
+    pub amount_out: U256,
+    // 0 for token0, 1 for token1
+    pub token_in: u8,
+    pub token_out: u8,
+    pub sqrt_price_after: U256,
+    pub liquidity_after: u128,
+    pub tick_after: i32,
+}
+
+// the result of a swap of a fee-on-transfer input token
+#[derive(Debug)]
+pub struct TransferFeeSwapResult {
+    // the swap of the amount the pool actually received
+    pub result: SwapResult,
+    // the amount the sender has to transfer for the pool to receive amount_in_net
+    pub amount_in_gross: U256,
+    // the input amount received and used by the pool
+    pub amount_in_net: U256,
+}
+
+// the part of a swap that traded against the liquidity between two consecutive initialized ticks, for attributing
+// volume to the positions in range. A bound is None where the swap started or stopped inside the range, as the
+// initialized tick beyond it is not searched for
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TickRangeContribution {
+    pub tick_lower: Option<i32>,
+    pub tick_upper: Option<i32>,
+    // the liquidity in range between the two ticks
+    pub liquidity: u128,
+    // the input swapped within the range, without the fee
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub fee_amount: U256,
 }
 
 // the top level state of the swap, the results of which are recorded in storage at the end
-struct SwapState {
-    amount_specified_remaining: I256,
-    amount_calculated: I256,
-    sqrt_price_x96: U256,
-    tick: i32,
-    liquidity: u128,
+#[derive(Debug, Clone)]
+pub struct SwapState {
+    // the amount remaining to be swapped in/out of the input/output asset
+    pub amount_specified_remaining: I256,
+    // the amount already swapped out/in of the output/input asset
+    pub amount_calculated: I256,
+    // current sqrt(price)
+    pub sqrt_price_x96: U256,
+    // the tick associated with the current price
+    pub tick: i32,
+    // the current liquidity in range
+    pub liquidity: u128,
+    // the fee growth per unit of liquidity of the input token accrued by the swap so far
+    pub fee_growth_x_128: U256,
+}
+
+// the values computed by one step of the swap loop
+#[derive(Debug, Clone, Default)]
+pub struct StepComputations {
+    // the price at the beginning of the step
+    pub sqrt_price_start_x96: U256,
+    // the next tick to swap to from the current tick in the swap direction
+    pub tick_next: i32,
+    // whether tick_next is initialized or not
+    pub initialized: bool,
+    // sqrt(price) for the next tick (1/0)
+    pub sqrt_price_next_x96: U256,
+    // how much is being swapped in in this step
+    pub amount_in: U256,
+    // how much is being swapped out
+    pub amount_out: U256,
+    // how much fee is being paid in
+    pub fee_amount: U256,
 }
 
-#[derive(Default)]
-struct StepComputations {
-    sqrt_price_start_x96: U256,
-    tick_next: i32,
-    initialized: bool,
-    sqrt_price_next_x96: U256,
-    amount_in: U256,
-    amount_out: U256,
-    fee_amount: U256,
+// converts a step amount to a signed delta, amounts above I256::MAX can not be accounted for
+fn i256_from_amount(amount: U256) -> Result<I256, UniswapV3MathError> {
+    let amount = I256::from_raw(amount);
+    if amount.is_negative() {
+        Err(UniswapV3MathError::Overflow)
+    } else {
+        Ok(amount)
+    }
 }
 
+//Swaps with the signed amount of the Solidity pool, positive for exact input and negative for exact output. Kept
+//public for the callers that already pass an I256, new callers should prefer swap_exact
 pub fn swap(
     ticks: &HashMap<i32, Tick>,
     tick_bitmap: &HashMap<i16, U256>,
@@ -55,12 +442,341 @@ pub fn swap(
     slot0: &Slot0,
     fee: u32,
 ) -> Result<SwapResult, UniswapV3MathError> {
+    swap_with_tick_provider(
+        &HashMapTickProvider::new(ticks, tick_bitmap),
+        tick_spacing,
+        zero_for_one,
+        amount_specified,
+        sqrt_price_limit,
+        slot0,
+        fee,
+    )
+}
+
+//The recommended entry to swap: the amount is unsigned with its kind spelled out, the result is in amounts in and out
+//and no sqrt price limit swaps up to the price bound in the direction of the swap. Same results as swap
+pub fn swap_exact(
+    ticks: &HashMap<i32, Tick>,
+    tick_bitmap: &HashMap<i16, U256>,
+    tick_spacing: i32,
+    direction: SwapDirection,
+    amount: SwapAmount,
+    sqrt_price_limit: Option<U256>,
+    slot0: &Slot0,
+    fee: u32,
+) -> Result<UnsignedSwapResult, UniswapV3MathError> {
+    let (zero_for_one, amount_specified, sqrt_price_limit) =
+        exact_swap_params(direction, amount, sqrt_price_limit)?;
+    let result = swap(
+        ticks,
+        tick_bitmap,
+        tick_spacing,
+        zero_for_one,
+        amount_specified,
+        sqrt_price_limit,
+        slot0,
+        fee,
+    )?;
+    Ok(unsigned_swap_result(&result, zero_for_one))
+}
+
+//A swap that fails with ScanBudgetExceeded once it has scanned `max_words_scanned` tick bitmap words, bounding the
+//work of a swap through sparse liquidity. No budget is the same as swap
+pub fn swap_with_scan_budget(
+    ticks: &HashMap<i32, Tick>,
+    tick_bitmap: &HashMap<i16, U256>,
+    tick_spacing: i32,
+    zero_for_one: bool,
+    amount_specified: I256,
+    sqrt_price_limit: U256,
+    slot0: &Slot0,
+    fee: u32,
+    max_words_scanned: Option<u32>,
+) -> Result<SwapResult, UniswapV3MathError> {
+    let provider = HashMapTickProvider::new(ticks, tick_bitmap);
+    match max_words_scanned {
+        Some(max_words_scanned) => swap_with_tick_provider(
+            &ScanBudgetTickProvider::new(provider, max_words_scanned),
+            tick_spacing,
+            zero_for_one,
+            amount_specified,
+            sqrt_price_limit,
+            slot0,
+            fee,
+        ),
+        None => swap_with_tick_provider(
+            &provider,
+            tick_spacing,
+            zero_for_one,
+            amount_specified,
+            sqrt_price_limit,
+            slot0,
+            fee,
+        ),
+    }
+}
+
+//Same as swap, calling `hook` at the end of every iteration of the swap loop with the step and the swap state after
+//it, including the tick crossed by the step. The values are the ones of the Solidity loop, e.g. to record a custom
+//trace of the swap
+pub fn swap_with_hook(
+    ticks: &HashMap<i32, Tick>,
+    tick_bitmap: &HashMap<i16, U256>,
+    tick_spacing: i32,
+    zero_for_one: bool,
+    amount_specified: I256,
+    sqrt_price_limit: U256,
+    slot0: &Slot0,
+    fee: u32,
+    hook: &mut dyn FnMut(&StepComputations, &SwapState),
+) -> Result<SwapResult, UniswapV3MathError> {
+    let mut tick_crossings = Vec::new();
+    let mut result = swap_with_crossing_sink(
+        &HashMapTickProvider::new(ticks, tick_bitmap),
+        tick_spacing,
+        zero_for_one,
+        amount_specified,
+        sqrt_price_limit,
+        slot0,
+        fee,
+        |crossing| {
+            tick_crossings.push(crossing);
+            Ok(())
+        },
+        hook,
+    )?;
+    result.tick_crossings = tick_crossings;
+    Ok(result)
+}
+
+//Same as swap, also splitting the amounts of the swap into the tick ranges it traded in, in the order they were
+//swapped through. The amount_in plus fee_amount and the amount_out of the contributions add up to the amounts of the
+//result. Ranges nothing was swapped in, e.g. the one above a start price right on an initialized tick when swapping
+//down, are left out
+pub fn swap_with_attribution(
+    ticks: &HashMap<i32, Tick>,
+    tick_bitmap: &HashMap<i16, U256>,
+    tick_spacing: i32,
+    zero_for_one: bool,
+    amount_specified: I256,
+    sqrt_price_limit: U256,
+    slot0: &Slot0,
+    fee: u32,
+) -> Result<(SwapResult, Vec<TickRangeContribution>), UniswapV3MathError> {
+    let mut contributions = Vec::new();
+    // the range being swapped through with the initialized tick the swap entered it at
+    let mut current = TickRangeContribution {
+        liquidity: slot0.liquidity,
+        ..Default::default()
+    };
+    let mut tick_entered = None;
+    let mut close_range = |current: &mut TickRangeContribution, tick_left: Option<i32>| {
+        (current.tick_lower, current.tick_upper) = if zero_for_one {
+            (tick_left, tick_entered)
+        } else {
+            (tick_entered, tick_left)
+        };
+        tick_entered = tick_left;
+        let contribution = std::mem::take(current);
+        if !(contribution.amount_in.is_zero()
+            && contribution.amount_out.is_zero()
+            && contribution.fee_amount.is_zero())
+        {
+            contributions.push(contribution);
+        }
+    };
+    let result = swap_with_hook(
+        ticks,
+        tick_bitmap,
+        tick_spacing,
+        zero_for_one,
+        amount_specified,
+        sqrt_price_limit,
+        slot0,
+        fee,
+        &mut |step, state| {
+            current.amount_in += step.amount_in;
+            current.amount_out += step.amount_out;
+            current.fee_amount += step.fee_amount;
+            if step.initialized && state.sqrt_price_x96 == step.sqrt_price_next_x96 {
+                close_range(&mut current, Some(step.tick_next));
+                current.liquidity = state.liquidity;
+            }
+        },
+    )?;
+    close_range(&mut current, None);
+    Ok((result, contributions))
+}
+
+//Same as swap, also returning the wei lost to rounding summed over the steps: for each step the amount in rounded up
+//less the amount in rounded down plus the amount out rounded up less the amount out between the prices of the step.
+//At most 2 per step, the fee rounding is not included. The amounts are recomputed from the step hook, so the loop of
+//swap does not pay for them
+pub fn swap_with_rounding_residue(
+    ticks: &HashMap<i32, Tick>,
+    tick_bitmap: &HashMap<i16, U256>,
+    tick_spacing: i32,
+    zero_for_one: bool,
+    amount_specified: I256,
+    sqrt_price_limit: U256,
+    slot0: &Slot0,
+    fee: u32,
+) -> Result<(SwapResult, U256), UniswapV3MathError> {
+    let mut rounding_residue = U256::ZERO;
+    // the liquidity the step swapped against, the state passed to the hook is after the tick crossed by the step
+    let mut liquidity = slot0.liquidity;
+    let mut residue_error = None;
+    let result = swap_with_hook(
+        ticks,
+        tick_bitmap,
+        tick_spacing,
+        zero_for_one,
+        amount_specified,
+        sqrt_price_limit,
+        slot0,
+        fee,
+        &mut |step, state| {
+            if state.sqrt_price_x96 != step.sqrt_price_start_x96 && residue_error.is_none() {
+                match step_rounding_residue(zero_for_one, step, state.sqrt_price_x96, liquidity) {
+                    Ok(residue) => rounding_residue += residue,
+                    Err(err) => residue_error = Some(err),
+                }
+            }
+            liquidity = state.liquidity;
+        },
+    )?;
+    if let Some(err) = residue_error {
+        return Err(err);
+    }
+    Ok((result, rounding_residue))
+}
+
+// the rounding residue of a step from step.sqrt_price_start_x96 to sqrt_price_x96 at `liquidity`
+fn step_rounding_residue(
+    zero_for_one: bool,
+    step: &StepComputations,
+    sqrt_price_x96: U256,
+    liquidity: u128,
+) -> Result<U256, UniswapV3MathError> {
+    let (amount_in_rounded_down, amount_out_rounded_up) = if zero_for_one {
+        (
+            _get_amount_0_delta(sqrt_price_x96, step.sqrt_price_start_x96, liquidity, false)?,
+            _get_amount_1_delta(sqrt_price_x96, step.sqrt_price_start_x96, liquidity, true)?,
+        )
+    } else {
+        (
+            _get_amount_1_delta(step.sqrt_price_start_x96, sqrt_price_x96, liquidity, false)?,
+            _get_amount_0_delta(step.sqrt_price_start_x96, sqrt_price_x96, liquidity, true)?,
+        )
+    };
+    Ok(step.amount_in.saturating_sub(amount_in_rounded_down)
+        + amount_out_rounded_up.saturating_sub(step.amount_out))
+}
+
+//Same as swap, reading the ticks crossed during the walk from `tick_provider` as they are needed
+pub fn swap_with_tick_provider<P: TickProvider>(
+    tick_provider: &P,
+    tick_spacing: i32,
+    zero_for_one: bool,
+    amount_specified: I256,
+    sqrt_price_limit: U256,
+    slot0: &Slot0,
+    fee: u32,
+) -> Result<SwapResult, UniswapV3MathError> {
+    let mut tick_crossings = Vec::new();
+    let mut result = swap_with_crossing_sink(
+        tick_provider,
+        tick_spacing,
+        zero_for_one,
+        amount_specified,
+        sqrt_price_limit,
+        slot0,
+        fee,
+        |crossing| {
+            tick_crossings.push(crossing);
+            Ok(())
+        },
+        |_, _| {},
+    )?;
+    result.tick_crossings = tick_crossings;
+    Ok(result)
+}
+
+//Same as swap_with_tick_provider without allocating on the heap, for constrained targets. The ticks crossed are
+//written to the front of `tick_crossings` instead of the result, whose tick_crossings stays empty, and their number
+//is returned with the result. Fails with TickCrossingBufferFull if the swap crosses more ticks than fit in the
+//buffer. Together with SliceTickProvider a swap runs over caller owned memory only, as long as the tracing feature
+//is off
+pub fn swap_no_alloc<P: TickProvider>(
+    tick_provider: &P,
+    tick_spacing: i32,
+    zero_for_one: bool,
+    amount_specified: I256,
+    sqrt_price_limit: U256,
+    slot0: &Slot0,
+    fee: u32,
+    tick_crossings: &mut [TickCrossing],
+) -> Result<(SwapResult, usize), UniswapV3MathError> {
+    let capacity = tick_crossings.len();
+    let mut crossed = 0;
+    let result = swap_with_crossing_sink(
+        tick_provider,
+        tick_spacing,
+        zero_for_one,
+        amount_specified,
+        sqrt_price_limit,
+        slot0,
+        fee,
+        |crossing| {
+            let slot = tick_crossings
+                .get_mut(crossed)
+                .ok_or(UniswapV3MathError::TickCrossingBufferFull(capacity))?;
+            *slot = crossing;
+            crossed += 1;
+            Ok(())
+        },
+        |_, _| {},
+    )?;
+    Ok((result, crossed))
+}
+
+// the swap loop, handing every initialized tick crossed to `on_tick_crossing` and every completed step to
+// `on_step`, and returning a result without tick crossings
+fn swap_with_crossing_sink<P, F, S>(
+    tick_provider: &P,
+    tick_spacing: i32,
+    zero_for_one: bool,
+    amount_specified: I256,
+    sqrt_price_limit: U256,
+    slot0: &Slot0,
+    fee: u32,
+    mut on_tick_crossing: F,
+    mut on_step: S,
+) -> Result<SwapResult, UniswapV3MathError>
+where
+    P: TickProvider,
+    F: FnMut(TickCrossing) -> Result<(), UniswapV3MathError>,
+    S: FnMut(&StepComputations, &SwapState),
+{
     if sqrt_price_limit <= tick_math::MIN_SQRT_RATIO {
         return Err(UniswapV3MathError::SplM);
     }
     if sqrt_price_limit >= tick_math::MAX_SQRT_RATIO {
         return Err(UniswapV3MathError::SpuM);
     }
+    // unlike UniswapV3Pool, which reverts, a limit at the current price means there is nothing to swap
+    if sqrt_price_limit == slot0.sqrt_price {
+        return Ok(SwapResult {
+            amount0_delta: I256::ZERO,
+            amount1_delta: I256::ZERO,
+            sqrt_price_after: slot0.sqrt_price,
+            liquidity_after: slot0.liquidity,
+            tick_after: slot0.tick,
+            fee_growth_x_128: U256::ZERO,
+            tick_crossings: Vec::new(),
+            cost: SwapCost::default(),
+        });
+    }
     if zero_for_one {
         if sqrt_price_limit >= slot0.sqrt_price {
             return Err(UniswapV3MathError::SplC);
@@ -70,6 +786,16 @@ pub fn swap(
             return Err(UniswapV3MathError::SpuC);
         }
     }
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!(
+        "swap",
+        zero_for_one,
+        amount_specified = %amount_specified,
+        sqrt_price_limit = %sqrt_price_limit,
+        tick = slot0.tick
+    )
+    .entered();
+
     let exact_input = amount_specified.is_positive();
     let mut state = SwapState {
         amount_specified_remaining: amount_specified,
@@ -77,20 +803,25 @@ pub fn swap(
         sqrt_price_x96: slot0.sqrt_price,
         tick: slot0.tick,
         liquidity: slot0.liquidity,
+        fee_growth_x_128: U256::ZERO,
     };
+    let mut word_cache = WordCache::default();
+    let mut iterations: u32 = 0;
+    let mut ticks_crossed: u32 = 0;
     while !state.amount_specified_remaining.is_zero() && state.sqrt_price_x96 != sqrt_price_limit {
+        iterations += 1;
         let mut step = StepComputations::default();
         step.sqrt_price_start_x96 = state.sqrt_price_x96;
-        (step.tick_next, step.initialized) = tick_bitmap::next_initialized_tick_within_one_word(
-            tick_bitmap,
-            slot0.tick,
-            tick_spacing,
-            zero_for_one,
-        )?;
+        (step.tick_next, step.initialized) =
+            word_cache.next_initialized(tick_provider, state.tick, tick_spacing, zero_for_one)?;
+        // the initialized flag is for the unclamped tick, the boundary tick itself would have been found by the
+        // search if it was initialized. The price limit checks keep the boundary from being reached anyway
         if step.tick_next < tick_math::MIN_TICK {
             step.tick_next = tick_math::MIN_TICK;
+            step.initialized = false;
         } else if step.tick_next > tick_math::MAX_TICK {
             step.tick_next = tick_math::MAX_TICK;
+            step.initialized = false;
         }
         step.sqrt_price_next_x96 = tick_math::get_sqrt_ratio_at_tick(step.tick_next)?;
         let hit_to_limit = if zero_for_one {
@@ -118,25 +849,79 @@ pub fn swap(
             state.amount_specified_remaining,
             fee,
         )?;
+        // the step moves the price towards the target and never past it
+        debug_assert!(
+            if zero_for_one {
+                target_price <= state.sqrt_price_x96
+                    && state.sqrt_price_x96 <= step.sqrt_price_start_x96
+            } else {
+                step.sqrt_price_start_x96 <= state.sqrt_price_x96
+                    && state.sqrt_price_x96 <= target_price
+            },
+            "swap step moved the price in the wrong direction"
+        );
         if exact_input {
-            state.amount_specified_remaining =
-                state.amount_specified_remaining - I256::from_raw(step.amount_in + step.fee_amount);
-            state.amount_calculated = state.amount_calculated - I256::from_raw(step.amount_out);
+            state.amount_specified_remaining = state
+                .amount_specified_remaining
+                .checked_sub(i256_from_amount(step.amount_in + step.fee_amount)?)
+                .ok_or(UniswapV3MathError::Overflow)?;
+            state.amount_calculated = state
+                .amount_calculated
+                .checked_sub(i256_from_amount(step.amount_out)?)
+                .ok_or(UniswapV3MathError::Overflow)?;
         } else {
-            state.amount_specified_remaining =
-                state.amount_specified_remaining + I256::from_raw(step.amount_out);
-            state.amount_calculated =
-                state.amount_calculated + I256::from_raw(step.amount_in + step.fee_amount);
+            state.amount_specified_remaining = state
+                .amount_specified_remaining
+                .checked_add(i256_from_amount(step.amount_out)?)
+                .ok_or(UniswapV3MathError::Overflow)?;
+            state.amount_calculated = state
+                .amount_calculated
+                .checked_add(i256_from_amount(step.amount_in + step.fee_amount)?)
+                .ok_or(UniswapV3MathError::Overflow)?;
         }
+        // update global fee tracker
+        if state.liquidity > 0 {
+            state.fee_growth_x_128 = state.fee_growth_x_128.wrapping_add(mul_div(
+                step.fee_amount,
+                Q128,
+                U256::from(state.liquidity),
+            )?);
+        }
+        #[cfg(feature = "tracing")]
+        tracing::trace!(
+            tick_next = step.tick_next,
+            initialized = step.initialized,
+            sqrt_price_start_x96 = %step.sqrt_price_start_x96,
+            sqrt_price_next_x96 = %state.sqrt_price_x96,
+            amount_in = %step.amount_in,
+            amount_out = %step.amount_out,
+            fee_amount = %step.fee_amount,
+            "swap step"
+        );
         // Do not calculate protocol fee
         if state.sqrt_price_x96 == step.sqrt_price_next_x96 {
             if step.initialized {
                 // The initialized tick must exist in ticks
-                let mut l_net = ticks.get(&step.tick_next).unwrap().liquidity_net;
+                let mut l_net = tick_provider
+                    .tick(step.tick_next)?
+                    .ok_or(UniswapV3MathError::TickNotFound(step.tick_next))?
+                    .liquidity_net;
                 if zero_for_one {
                     l_net = -1 * l_net;
                 }
                 state.liquidity = liquidity_math::add_delta(state.liquidity, l_net)?;
+                #[cfg(feature = "tracing")]
+                tracing::trace!(
+                    tick = step.tick_next,
+                    liquidity_net = l_net,
+                    liquidity = state.liquidity,
+                    "tick crossed"
+                );
+                on_tick_crossing(TickCrossing {
+                    tick: step.tick_next,
+                    fee_growth_x_128: state.fee_growth_x_128,
+                })?;
+                ticks_crossed += 1;
             }
             if zero_for_one {
                 state.tick = step.tick_next - 1
@@ -146,15 +931,19 @@ pub fn swap(
         } else if state.sqrt_price_x96 != step.sqrt_price_start_x96 {
             state.tick = tick_math::get_tick_at_sqrt_ratio(state.sqrt_price_x96)?;
         }
+        on_step(&step, &state);
     }
+    let amount_specified_used = amount_specified
+        .checked_sub(state.amount_specified_remaining)
+        .ok_or(UniswapV3MathError::Overflow)?;
     let amount0_delta;
     let amount1_delta;
     if zero_for_one == exact_input {
-        amount0_delta = amount_specified - state.amount_specified_remaining;
+        amount0_delta = amount_specified_used;
         amount1_delta = state.amount_calculated;
     } else {
         amount0_delta = state.amount_calculated;
-        amount1_delta = amount_specified - state.amount_specified_remaining;
+        amount1_delta = amount_specified_used;
     }
     return Ok(SwapResult {
         amount0_delta,
@@ -162,19 +951,119 @@ pub fn swap(
         sqrt_price_after: state.sqrt_price_x96,
         liquidity_after: state.liquidity,
         tick_after: state.tick,
+        fee_growth_x_128: state.fee_growth_x_128,
+        tick_crossings: Vec::new(),
+        cost: SwapCost::new(iterations, ticks_crossed),
     });
 }
 
-#[cfg(test)]
-mod test {
-    use super::{swap, Tick};
-    use crate::{
-        swap::Slot0,
-        tick_bitmap::{flip_tick, next_initialized_tick_within_one_word},
-        tick_math,
+const BPS: U256 = U256::from_limbs([10000, 0, 0, 0]);
+
+//Swaps an input token that takes `input_fee_bps` of every transfer, so the pool receives less than the sender pays.
+//An exact input `amount_specified` is the gross amount sent and is reduced by the transfer fee before the swap math,
+//it is the amount_in_gross unless the swap stops at the price limit with part of the input left. For exact output
+//swaps and partial exact input swaps the input the pool used is grossed up by the transfer fee
+pub fn swap_with_transfer_fee(
+    ticks: &HashMap<i32, Tick>,
+    tick_bitmap: &HashMap<i16, U256>,
+    tick_spacing: i32,
+    zero_for_one: bool,
+    amount_specified: I256,
+    sqrt_price_limit: U256,
+    slot0: &Slot0,
+    fee: u32,
+    input_fee_bps: u32,
+) -> Result<TransferFeeSwapResult, UniswapV3MathError> {
+    if input_fee_bps >= 10000 {
+        return Err(UniswapV3MathError::InvalidTransferFee);
+    }
+    let remaining_bps = BPS - U256::from(input_fee_bps);
+
+    // the gross amount sent for exact input swaps
+    let (amount_specified, amount_sent) = if amount_specified.is_positive() {
+        (
+            I256::from_raw(mul_div(amount_specified.into_raw(), remaining_bps, BPS)?),
+            Some(amount_specified.into_raw()),
+        )
+    } else {
+        (amount_specified, None)
     };
-    use alloy::primitives::{I256, U256};
-    use std::{collections::HashMap, vec};
+
+    let result = swap(
+        ticks,
+        tick_bitmap,
+        tick_spacing,
+        zero_for_one,
+        amount_specified,
+        sqrt_price_limit,
+        slot0,
+        fee,
+    )?;
+
+    let amount_in_net = if zero_for_one {
+        result.amount0_delta.into_raw()
+    } else {
+        result.amount1_delta.into_raw()
+    };
+    // the transfer fee rounds down the amount received, so grossing up the net would not give back an amount sent
+    // that swapped entirely
+    let amount_in_gross = match amount_sent {
+        Some(amount_sent) if amount_in_net == amount_specified.into_raw() => amount_sent,
+        _ => mul_div_rounding_up(amount_in_net, BPS, remaining_bps)?,
+    };
+
+    Ok(TransferFeeSwapResult {
+        result,
+        amount_in_gross,
+        amount_in_net,
+    })
+}
+
+//Swaps with the amount given in token1 terms regardless of direction, for UIs quoting in token1 as the quote token.
+//`amount1` is the token1 received for zero_for_one swaps, which makes them exact output (amount_specified = -amount1),
+//and the token1 spent for one_for_zero swaps, which makes them exact input (amount_specified = amount1)
+pub fn swap_token1_amount(
+    ticks: &HashMap<i32, Tick>,
+    tick_bitmap: &HashMap<i16, U256>,
+    tick_spacing: i32,
+    zero_for_one: bool,
+    amount1: U256,
+    sqrt_price_limit: U256,
+    slot0: &Slot0,
+    fee: u32,
+) -> Result<SwapResult, UniswapV3MathError> {
+    let amount1 = i256_from_amount(amount1)?;
+    let amount_specified = if zero_for_one { -amount1 } else { amount1 };
+
+    swap(
+        ticks,
+        tick_bitmap,
+        tick_spacing,
+        zero_for_one,
+        amount_specified,
+        sqrt_price_limit,
+        slot0,
+        fee,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        i256_from_amount, swap, swap_exact, swap_token1_amount, swap_with_attribution,
+        swap_with_hook, swap_with_rounding_residue, swap_with_scan_budget, swap_with_transfer_fee,
+        Amount, SwapAmount, SwapCost, SwapDirection, SwapResult, Tick, TickRangeContribution,
+        UnsignedSwapResult,
+    };
+    use crate::{
+        error::UniswapV3MathError,
+        sqrt_price_math::{_get_amount_0_delta, _get_amount_1_delta, Q96},
+        swap::Slot0,
+        tick_bitmap::{flip_tick, next_initialized_tick_within_one_word},
+        tick_math,
+    };
+    use alloy::primitives::{I256, U256};
+    use std::{collections::HashMap, str::FromStr, vec};
 
     pub fn init_test_ticks() -> eyre::Result<HashMap<i16, U256>> {
         let test_ticks = vec![-200, -55, -4, 70, 78, 84, 139, 240, 535];
@@ -185,6 +1074,30 @@ mod test {
         Ok(tick_bitmap)
     }
 
+    // the ticks, tick bitmap and slot0 at tick 0 of (-600, 600, 1e18), (-1200, -120, 5e17) and (120, 1800, 5e17)
+    // with a tick spacing of 60
+    pub fn init_test_positions() -> eyre::Result<(HashMap<i32, Tick>, HashMap<i16, U256>, Slot0)> {
+        let mut ticks: HashMap<i32, Tick> = HashMap::new();
+        let mut tick_bitmap: HashMap<i16, U256> = HashMap::new();
+        for (tick, liquidity_gross, liquidity_net) in [
+            (-1200, 500_000_000_000_000_000, 500_000_000_000_000_000),
+            (-600, 1_000_000_000_000_000_000, 1_000_000_000_000_000_000),
+            (-120, 500_000_000_000_000_000, -500_000_000_000_000_000),
+            (120, 500_000_000_000_000_000, 500_000_000_000_000_000),
+            (600, 1_000_000_000_000_000_000, -1_000_000_000_000_000_000),
+            (1800, 500_000_000_000_000_000, -500_000_000_000_000_000),
+        ] {
+            ticks.insert(tick, Tick::new(liquidity_gross, liquidity_net)?);
+            flip_tick(&mut tick_bitmap, tick, 60)?;
+        }
+        let slot0 = Slot0 {
+            sqrt_price: Q96,
+            liquidity: 1_000_000_000_000_000_000,
+            tick: 0,
+        };
+        Ok((ticks, tick_bitmap, slot0))
+    }
+
     #[test]
     pub fn test_swap() -> eyre::Result<()> {
         let tick_bitmap = init_test_ticks()?;
@@ -233,4 +1146,1664 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    pub fn test_swap_crosses_consecutive_ticks() -> eyre::Result<()> {
+        // (-600, 600, 1e18), (-1200, -120, 5e17) and (120, 1800, 5e17) in a 0.3% pool at tick 0
+        let mut ticks: HashMap<i32, Tick> = HashMap::new();
+        let mut tick_bitmap: HashMap<i16, U256> = HashMap::new();
+        for (tick, liquidity_gross, liquidity_net) in [
+            (-1200, 500_000_000_000_000_000, 500_000_000_000_000_000),
+            (-600, 1_000_000_000_000_000_000, 1_000_000_000_000_000_000),
+            (-120, 500_000_000_000_000_000, -500_000_000_000_000_000),
+            (120, 500_000_000_000_000_000, 500_000_000_000_000_000),
+            (600, 1_000_000_000_000_000_000, -1_000_000_000_000_000_000),
+            (1800, 500_000_000_000_000_000, -500_000_000_000_000_000),
+        ] {
+            ticks.insert(
+                tick,
+                Tick {
+                    liquidity_gross,
+                    liquidity_net,
+                    fee_growth_outside_0_x_128: U256::ZERO,
+                    fee_growth_outside_1_x_128: U256::ZERO,
+                    tick_cumulative_outside: U256::ZERO,
+                    seconds_per_liquidity_outside_x_128: U256::ZERO,
+                    seconds_outside: 0,
+                    initialized: true,
+                },
+            );
+            flip_tick(&mut tick_bitmap, tick, 60)?;
+        }
+        let slot0 = Slot0 {
+            sqrt_price: U256::from(1) << 96,
+            liquidity: 1_000_000_000_000_000_000,
+            tick: 0,
+        };
+
+        // crosses -120 and then -600, each searched for from the tick the previous step ended at
+        let swap_result = swap(
+            &ticks,
+            &tick_bitmap,
+            60,
+            true,
+            I256::from_raw(U256::from(50_000_000_000_000_000_u128)),
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+            &slot0,
+            3000,
+        )?;
+        assert_eq!(swap_result.tick_after, -877);
+        assert_eq!(swap_result.liquidity_after, 500_000_000_000_000_000);
+        assert_eq!(
+            swap_result.amount1_delta,
+            -I256::from_raw(U256::from(48_007_037_308_774_480_u128))
+        );
+        assert_eq!(
+            swap_result.sqrt_price_after,
+            U256::from(75_830_083_212_837_298_011_496_951_984_u128)
+        );
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_with_transfer_fee() -> eyre::Result<()> {
+        // a single constant liquidity range, no initialized ticks
+        let ticks: HashMap<i32, Tick> = HashMap::new();
+        let tick_bitmap: HashMap<i16, U256> = HashMap::new();
+        let slot0 = Slot0 {
+            sqrt_price: U256::from_str("79228162514264337593543950336")?,
+            liquidity: 1_000_000_000_000_000_000,
+            tick: 0,
+        };
+        let sqrt_price_limit = tick_math::MIN_SQRT_RATIO + U256::from(1);
+        let amount = I256::from_str("1000000000000000")?;
+
+        let baseline = swap(
+            &ticks,
+            &tick_bitmap,
+            60,
+            true,
+            amount,
+            sqrt_price_limit,
+            &slot0,
+            3000,
+        )?;
+        assert_eq!(baseline.amount0_delta, amount);
+        assert_eq!(baseline.amount1_delta, I256::from_str("-996006981039903")?);
+
+        assert_eq!(
+            swap_with_transfer_fee(
+                &ticks,
+                &tick_bitmap,
+                60,
+                true,
+                amount,
+                sqrt_price_limit,
+                &slot0,
+                3000,
+                0,
+            )?
+            .result
+            .amount1_delta,
+            baseline.amount1_delta
+        );
+
+        // 1% of the transfer is taken by the token, the pool only swaps 0.99e15
+        let with_fee = swap_with_transfer_fee(
+            &ticks,
+            &tick_bitmap,
+            60,
+            true,
+            amount,
+            sqrt_price_limit,
+            &slot0,
+            3000,
+            100,
+        )?;
+        assert_eq!(
+            with_fee.amount_in_gross,
+            U256::from(1_000_000_000_000_000_u128)
+        );
+        assert_eq!(with_fee.amount_in_net, U256::from(990_000_000_000_000_u128));
+        assert_eq!(
+            with_fee.result.amount0_delta,
+            I256::from_str("990000000000000")?
+        );
+        assert_eq!(
+            with_fee.result.amount1_delta,
+            I256::from_str("-986056732423396")?
+        );
+        assert!(with_fee.result.amount1_delta > baseline.amount1_delta);
+
+        // 1% of 1e15 + 1 is not a whole amount, the token takes 1e13 + 1 and the pool receives 0.99e15. Grossing up the
+        // net would give 1e15, one less than the sender sent
+        let uneven = swap_with_transfer_fee(
+            &ticks,
+            &tick_bitmap,
+            60,
+            true,
+            I256::from_str("1000000000000001")?,
+            sqrt_price_limit,
+            &slot0,
+            3000,
+            100,
+        )?;
+        assert_eq!(
+            uneven.amount_in_gross,
+            U256::from(1_000_000_000_000_001_u128)
+        );
+        assert_eq!(uneven.amount_in_net, U256::from(990_000_000_000_000_u128));
+        assert_eq!(uneven.result, with_fee.result);
+
+        // exact output of 1e15 token1: the pool swaps as without the fee and the sender has to transfer the 1.004e15
+        // token0 the pool needs grossed up by the 1%, rounded up
+        let amount_out = I256::from_str("-1000000000000000")?;
+        let baseline = swap(
+            &ticks,
+            &tick_bitmap,
+            60,
+            true,
+            amount_out,
+            sqrt_price_limit,
+            &slot0,
+            3000,
+        )?;
+        let with_fee = swap_with_transfer_fee(
+            &ticks,
+            &tick_bitmap,
+            60,
+            true,
+            amount_out,
+            sqrt_price_limit,
+            &slot0,
+            3000,
+            100,
+        )?;
+        assert_eq!(with_fee.result, baseline);
+        assert_eq!(with_fee.result.amount1_delta, amount_out);
+        assert_eq!(
+            with_fee.amount_in_net,
+            U256::from(1_004_013_040_121_367_u128)
+        );
+        assert_eq!(
+            with_fee.amount_in_gross,
+            U256::from(1_014_154_585_981_179_u128)
+        );
+
+        let result = swap_with_transfer_fee(
+            &ticks,
+            &tick_bitmap,
+            60,
+            true,
+            amount,
+            sqrt_price_limit,
+            &slot0,
+            3000,
+            10000,
+        );
+        assert!(matches!(
+            result,
+            Err(UniswapV3MathError::InvalidTransferFee)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_delta_signs() -> eyre::Result<()> {
+        let ticks: HashMap<i32, Tick> = HashMap::new();
+        let tick_bitmap: HashMap<i16, U256> = HashMap::new();
+        let slot0 = Slot0 {
+            sqrt_price: U256::from_str("79228162514264337593543950336")?,
+            liquidity: 1_000_000_000_000_000_000,
+            tick: 0,
+        };
+        let min_limit = tick_math::MIN_SQRT_RATIO + U256::from(1);
+        let max_limit = tick_math::MAX_SQRT_RATIO - U256::from(1);
+        let lower_limit = tick_math::get_sqrt_ratio_at_tick(-10)?;
+        let upper_limit = tick_math::get_sqrt_ratio_at_tick(10)?;
+
+        // (zero_for_one, amount_specified, sqrt_price_limit, amount0_delta, amount1_delta)
+        let cases = [
+            // fully filled, the specified token delta is amount_specified
+            (
+                true,
+                "1000000000000000",
+                min_limit,
+                "1000000000000000",
+                "-996006981039903",
+            ),
+            (
+                true,
+                "-1000000000000000",
+                min_limit,
+                "1004013040121367",
+                "-1000000000000000",
+            ),
+            (
+                false,
+                "1000000000000000",
+                max_limit,
+                "-996006981039903",
+                "1000000000000000",
+            ),
+            (
+                false,
+                "-1000000000000000",
+                max_limit,
+                "-1000000000000000",
+                "1004013040121367",
+            ),
+            // stopped by the price limit, the specified token delta is amount_specified - remaining
+            (
+                true,
+                "100000000000000000",
+                lower_limit,
+                "501604824473923",
+                "-499850034993001",
+            ),
+            (
+                true,
+                "-100000000000000000",
+                lower_limit,
+                "501604824473923",
+                "-499850034993001",
+            ),
+            (
+                false,
+                "100000000000000000",
+                upper_limit,
+                "-499850034993001",
+                "501604824473923",
+            ),
+            (
+                false,
+                "-100000000000000000",
+                upper_limit,
+                "-499850034993001",
+                "501604824473923",
+            ),
+        ];
+
+        for (zero_for_one, amount_specified, sqrt_price_limit, amount0_delta, amount1_delta) in
+            cases
+        {
+            let amount_specified = I256::from_str(amount_specified)?;
+            let result = swap(
+                &ticks,
+                &tick_bitmap,
+                60,
+                zero_for_one,
+                amount_specified,
+                sqrt_price_limit,
+                &slot0,
+                3000,
+            )?;
+            assert_eq!(
+                result.amount0_delta,
+                I256::from_str(amount0_delta)?,
+                "amount0_delta for zero_for_one {zero_for_one}, amount_specified {amount_specified}"
+            );
+            assert_eq!(
+                result.amount1_delta,
+                I256::from_str(amount1_delta)?,
+                "amount1_delta for zero_for_one {zero_for_one}, amount_specified {amount_specified}"
+            );
+
+            // the specified token moves in the direction of amount_specified, the other one opposite
+            let exact_input = amount_specified.is_positive();
+            let (specified, calculated) = if zero_for_one == exact_input {
+                (result.amount0_delta, result.amount1_delta)
+            } else {
+                (result.amount1_delta, result.amount0_delta)
+            };
+            assert_eq!(specified.is_positive(), exact_input);
+            assert_eq!(calculated.is_negative(), exact_input);
+            assert!(specified.unsigned_abs() <= amount_specified.unsigned_abs());
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_extreme_amounts() -> eyre::Result<()> {
+        assert!(matches!(
+            i256_from_amount(U256::MAX),
+            Err(UniswapV3MathError::Overflow)
+        ));
+        assert_eq!(i256_from_amount(I256::MAX.into_raw())?, I256::MAX);
+
+        let ticks: HashMap<i32, Tick> = HashMap::new();
+        let tick_bitmap: HashMap<i16, U256> = HashMap::new();
+        let slot0 = Slot0 {
+            sqrt_price: U256::from_str("79228162514264337593543950336")?,
+            liquidity: u128::MAX,
+            tick: 0,
+        };
+
+        // exact input of I256::MAX drains the pool down to the price limit
+        let result = swap(
+            &ticks,
+            &tick_bitmap,
+            60,
+            true,
+            I256::MAX,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+            &slot0,
+            3000,
+        )?;
+        assert_eq!(
+            result.amount0_delta,
+            I256::from_str("6295753052743416194186008905616998367954189762567572346683")?
+        );
+        assert_eq!(
+            result.amount1_delta,
+            I256::from_str("-340282366920938463444927169961358524358")?
+        );
+        assert_eq!(result.tick_after, tick_math::MIN_TICK);
+
+        // exact output of I256::MIN, which can not be negated as an I256
+        let result = swap(
+            &ticks,
+            &tick_bitmap,
+            60,
+            false,
+            I256::MIN,
+            tick_math::MAX_SQRT_RATIO - U256::from(1),
+            &slot0,
+            3000,
+        )?;
+        assert_eq!(
+            result.amount0_delta,
+            I256::from_str("-340282366920938463444927169969384229582")?
+        );
+        assert_eq!(
+            result.amount1_delta,
+            I256::from_str("6295753055482433914711471901020355315330342748929792430298")?
+        );
+        assert_eq!(result.tick_after, tick_math::MAX_TICK - 1);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_max_liquidity() -> eyre::Result<()> {
+        let mut ticks: HashMap<i32, Tick> = HashMap::new();
+        let mut tick_bitmap: HashMap<i16, U256> = HashMap::new();
+        let slot0 = Slot0 {
+            sqrt_price: U256::from_str("79228162514264337593543950336")?,
+            liquidity: u128::MAX,
+            tick: 0,
+        };
+
+        // the intermediate products of liquidity << 96 and the sqrt prices go through the 512 bit mul_div,
+        // so a modest swap at u128::MAX liquidity barely moves the price
+        let result = swap(
+            &ticks,
+            &tick_bitmap,
+            60,
+            true,
+            I256::from_str("1000000000000000000")?,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+            &slot0,
+            3000,
+        )?;
+        assert_eq!(result.amount0_delta, I256::from_str("1000000000000000000")?);
+        assert_eq!(result.amount1_delta, I256::from_str("-996999996895133695")?);
+        assert_eq!(
+            result.sqrt_price_after,
+            U256::from_str("79228162514264337593311818185")?
+        );
+        assert_eq!(result.liquidity_after, u128::MAX);
+        assert_eq!(result.tick_after, -1);
+        assert_eq!(result.fee_growth_x_128, U256::from(3000003104866303_u128));
+
+        let result = swap(
+            &ticks,
+            &tick_bitmap,
+            60,
+            true,
+            I256::from_str("-1000000000000000000")?,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+            &slot0,
+            3000,
+        )?;
+        assert_eq!(result.amount0_delta, I256::from_str("1003009028572335632")?);
+        assert_eq!(
+            result.amount1_delta,
+            I256::from_str("-1000000000000000000")?
+        );
+        assert_eq!(result.tick_after, -1);
+
+        // crossing a tick that adds liquidity on top of u128::MAX fails instead of wrapping
+        ticks.insert(
+            -60,
+            Tick {
+                liquidity_gross: 1,
+                liquidity_net: -1,
+                initialized: true,
+                ..Default::default()
+            },
+        );
+        flip_tick(&mut tick_bitmap, -60, 60)?;
+        let result = swap(
+            &ticks,
+            &tick_bitmap,
+            60,
+            true,
+            I256::from_str("10000000000000000000000000000000000000")?,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+            &slot0,
+            3000,
+        );
+        assert!(matches!(result, Err(UniswapV3MathError::LiquidityAdd)));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_with_scan_budget() -> eyre::Result<()> {
+        // a single full range position, so a swap towards the minimum price scans every word on the way
+        let mut ticks: HashMap<i32, Tick> = HashMap::new();
+        let mut tick_bitmap: HashMap<i16, U256> = HashMap::new();
+        for (tick, liquidity_net) in [
+            (-887220, 1_000_000_000_000_000_000),
+            (887220, -1_000_000_000_000_000_000),
+        ] {
+            ticks.insert(
+                tick,
+                Tick {
+                    liquidity_gross: 1_000_000_000_000_000_000,
+                    liquidity_net,
+                    initialized: true,
+                    ..Default::default()
+                },
+            );
+            flip_tick(&mut tick_bitmap, tick, 60)?;
+        }
+        let slot0 = Slot0 {
+            sqrt_price: U256::from_str("79228162514264337593543950336")?,
+            liquidity: 1_000_000_000_000_000_000,
+            tick: 0,
+        };
+        let swap_with_budget = |amount_specified: I256, max_words_scanned: Option<u32>| {
+            swap_with_scan_budget(
+                &ticks,
+                &tick_bitmap,
+                60,
+                true,
+                amount_specified,
+                tick_math::MIN_SQRT_RATIO + U256::from(1),
+                &slot0,
+                3000,
+                max_words_scanned,
+            )
+        };
+
+        // a small swap ends in the second word, after the word boundary at tick 0
+        let amount_specified = I256::from_str("1000000000000000")?;
+        assert!(matches!(
+            swap_with_budget(amount_specified, Some(1)),
+            Err(UniswapV3MathError::ScanBudgetExceeded(1))
+        ));
+        let result = swap_with_budget(amount_specified, Some(2))?;
+        assert_eq!(result, swap_with_budget(amount_specified, None)?);
+        assert_eq!(result.tick_after, -20);
+
+        // a large swap moves the price by hundreds of thousands of ticks, 28 words
+        let amount_specified = I256::from_str("1000000000000000000000000000")?;
+        assert!(matches!(
+            swap_with_budget(amount_specified, Some(27)),
+            Err(UniswapV3MathError::ScanBudgetExceeded(27))
+        ));
+        let result = swap_with_budget(amount_specified, Some(28))?;
+        assert_eq!(result, swap_with_budget(amount_specified, None)?);
+        assert_eq!(result.tick_after, -414426);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_result_eq() -> eyre::Result<()> {
+        let ticks: HashMap<i32, Tick> = HashMap::new();
+        let tick_bitmap: HashMap<i16, U256> = HashMap::new();
+        let slot0 = Slot0 {
+            sqrt_price: U256::from_str("79228162514264337593543950336")?,
+            liquidity: 1_000_000_000_000_000_000,
+            tick: 0,
+        };
+        assert_eq!(slot0, slot0.clone());
+
+        let result = swap(
+            &ticks,
+            &tick_bitmap,
+            60,
+            true,
+            I256::from_str("1000000000000000")?,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+            &slot0,
+            3000,
+        )?;
+        let expected = SwapResult {
+            amount0_delta: I256::from_str("1000000000000000")?,
+            amount1_delta: I256::from_str("-996006981039903")?,
+            sqrt_price_after: U256::from_str("79149250711305166342700278159")?,
+            liquidity_after: 1_000_000_000_000_000_000,
+            tick_after: -20,
+            fee_growth_x_128: U256::from_str("1020847100762815390390123822295304")?,
+            tick_crossings: vec![],
+            // a step to the uninitialized tick 0 at the current price, then one to the amount
+            cost: SwapCost {
+                iterations: 2,
+                ticks_crossed: 0,
+                estimated_sstores: 2,
+            },
+        };
+        assert_eq!(result, expected);
+        assert_ne!(
+            result,
+            SwapResult {
+                tick_after: expected.tick_after - 1,
+                ..expected.clone()
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_token1_amount() -> eyre::Result<()> {
+        let ticks: HashMap<i32, Tick> = HashMap::new();
+        let tick_bitmap: HashMap<i16, U256> = HashMap::new();
+        let slot0 = Slot0 {
+            sqrt_price: U256::from_str("79228162514264337593543950336")?,
+            liquidity: 1_000_000_000_000_000_000,
+            tick: 0,
+        };
+        let amount1 = U256::from(1_000_000_000_000_000_u128);
+
+        // (zero_for_one, sqrt_price_limit, amount0_delta, amount1_delta)
+        let cases = [
+            // receive exactly 1e15 token1
+            (
+                true,
+                tick_math::MIN_SQRT_RATIO + U256::from(1),
+                "1004013040121367",
+                "-1000000000000000",
+            ),
+            // spend exactly 1e15 token1
+            (
+                false,
+                tick_math::MAX_SQRT_RATIO - U256::from(1),
+                "-996006981039903",
+                "1000000000000000",
+            ),
+            // the price limit stops the swap before 1e15 token1 is received
+            (
+                true,
+                tick_math::get_sqrt_ratio_at_tick(-5)?,
+                "250771063503007",
+                "-249956256561597",
+            ),
+            // the price limit stops the swap before 1e15 token1 is spent
+            (
+                false,
+                tick_math::get_sqrt_ratio_at_tick(5)?,
+                "-249956256561597",
+                "250771063503007",
+            ),
+        ];
+        for (zero_for_one, sqrt_price_limit, amount0_delta, amount1_delta) in cases {
+            let result = swap_token1_amount(
+                &ticks,
+                &tick_bitmap,
+                60,
+                zero_for_one,
+                amount1,
+                sqrt_price_limit,
+                &slot0,
+                3000,
+            )?;
+            assert_eq!(result.amount0_delta, I256::from_str(amount0_delta)?);
+            assert_eq!(result.amount1_delta, I256::from_str(amount1_delta)?);
+        }
+
+        assert!(matches!(
+            swap_token1_amount(
+                &ticks,
+                &tick_bitmap,
+                60,
+                true,
+                U256::MAX,
+                tick_math::MIN_SQRT_RATIO + U256::from(1),
+                &slot0,
+                3000,
+            ),
+            Err(UniswapV3MathError::Overflow)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_clamped_tick_next() -> eyre::Result<()> {
+        // a bit set out of the tick range, next to the min tick, as a corrupt provider could return
+        let out_of_range_tick = tick_math::MIN_TICK - 1;
+        let mut ticks: HashMap<i32, Tick> = HashMap::new();
+        ticks.insert(
+            out_of_range_tick,
+            Tick {
+                liquidity_gross: 1_000_000_000_000_000_000,
+                liquidity_net: -1_000_000_000_000_000_000,
+                initialized: true,
+                ..Default::default()
+            },
+        );
+        let mut tick_bitmap: HashMap<i16, U256> = HashMap::new();
+        flip_tick(&mut tick_bitmap, out_of_range_tick, 1)?;
+        let (tick_next, initialized) =
+            next_initialized_tick_within_one_word(&tick_bitmap, tick_math::MIN_TICK, 1, true)?;
+        assert_eq!((tick_next, initialized), (out_of_range_tick, true));
+
+        let slot0 = Slot0 {
+            sqrt_price: tick_math::get_sqrt_ratio_at_tick(-887000)?,
+            liquidity: 1_000_000_000_000_000_000,
+            tick: -887000,
+        };
+        let sqrt_price_limit = tick_math::MIN_SQRT_RATIO + U256::from(1);
+        let result = swap(
+            &ticks,
+            &tick_bitmap,
+            1,
+            true,
+            I256::MAX,
+            sqrt_price_limit,
+            &slot0,
+            3000,
+        )?;
+
+        // the walk ends at the price limit without applying any liquidity_net at the boundary
+        assert_eq!(result.sqrt_price_after, sqrt_price_limit);
+        assert_eq!(result.tick_after, tick_math::MIN_TICK);
+        assert_eq!(result.liquidity_after, slot0.liquidity);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_result_approx_eq() -> eyre::Result<()> {
+        let result = SwapResult {
+            amount0_delta: I256::from_str("1000000000000000")?,
+            amount1_delta: I256::from_str("-996006981039903")?,
+            sqrt_price_after: U256::from_str("79149250711305166342700278159")?,
+            liquidity_after: 1_000_000_000_000_000_000,
+            tick_after: -20,
+            fee_growth_x_128: U256::from_str("1020847100762815390390123822295304")?,
+            tick_crossings: vec![],
+            cost: SwapCost::default(),
+        };
+        let off_by_one_wei = SwapResult {
+            amount1_delta: I256::from_str("-996006981039904")?,
+            ..result.clone()
+        };
+        assert!(result.approx_eq(&off_by_one_wei, 1));
+        assert!(off_by_one_wei.approx_eq(&result, 1));
+        assert!(!result.approx_eq(&off_by_one_wei, 0));
+        assert!(result.approx_eq(&result, 0));
+
+        // the price may land on the neighbouring tick but not further
+        let next_tick = SwapResult {
+            sqrt_price_after: tick_math::get_sqrt_ratio_at_tick(-21)?,
+            tick_after: -21,
+            ..result.clone()
+        };
+        assert!(result.approx_eq(&next_tick, 0));
+        let two_ticks_away = SwapResult {
+            tick_after: -22,
+            ..result.clone()
+        };
+        assert!(!result.approx_eq(&two_ticks_away, 0));
+
+        // deltas too far apart to subtract never match
+        let extreme = SwapResult {
+            amount0_delta: I256::MIN,
+            ..result.clone()
+        };
+        let other_extreme = SwapResult {
+            amount0_delta: I256::MAX,
+            ..result.clone()
+        };
+        assert!(!extreme.approx_eq(&other_extreme, u128::MAX));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_missing_initialized_tick() -> eyre::Result<()> {
+        // -60 is flipped in the bitmap but missing from the ticks
+        let ticks: HashMap<i32, Tick> = HashMap::new();
+        let mut tick_bitmap: HashMap<i16, U256> = HashMap::new();
+        flip_tick(&mut tick_bitmap, -60, 60)?;
+        let slot0 = Slot0 {
+            sqrt_price: U256::from_str("79228162514264337593543950336")?,
+            liquidity: 1_000_000_000_000_000_000,
+            tick: 0,
+        };
+
+        let result = swap(
+            &ticks,
+            &tick_bitmap,
+            60,
+            true,
+            I256::from_str("50000000000000000")?,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+            &slot0,
+            3000,
+        );
+        assert!(matches!(result, Err(UniswapV3MathError::TickNotFound(-60))));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_direction_from_str() -> eyre::Result<()> {
+        for s in ["0to1", "zeroForOne", "zero_for_one"] {
+            assert_eq!(s.parse::<SwapDirection>()?, SwapDirection::ZeroForOne);
+        }
+        for s in ["1to0", "oneForZero", "one_for_zero"] {
+            assert_eq!(s.parse::<SwapDirection>()?, SwapDirection::OneForZero);
+        }
+        assert!("0to1".parse::<SwapDirection>()?.zero_for_one());
+        assert!(!"1to0".parse::<SwapDirection>()?.zero_for_one());
+
+        assert!(matches!(
+            "sideways".parse::<SwapDirection>(),
+            Err(UniswapV3MathError::UnknownSwapDirection(s)) if s == "sideways"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_exact() -> eyre::Result<()> {
+        let (ticks, tick_bitmap, slot0) = init_test_positions()?;
+
+        for direction in [SwapDirection::ZeroForOne, SwapDirection::OneForZero] {
+            let zero_for_one = direction.zero_for_one();
+            let bound = if zero_for_one {
+                tick_math::MIN_SQRT_RATIO + U256::from(1)
+            } else {
+                tick_math::MAX_SQRT_RATIO - U256::from(1)
+            };
+            let limit = tick_math::get_sqrt_ratio_at_tick(if zero_for_one { -300 } else { 300 })?;
+            for amount in [
+                0_u128,
+                1_000,
+                50_000_000_000_000_000,
+                10_000_000_000_000_000_000,
+            ] {
+                let amount = U256::from(amount);
+                for (swap_amount, amount_specified) in [
+                    (SwapAmount::ExactInput { amount }, I256::from_raw(amount)),
+                    (SwapAmount::ExactOutput { amount }, -I256::from_raw(amount)),
+                ] {
+                    for (sqrt_price_limit, raw_limit) in [(None, bound), (Some(limit), limit)] {
+                        let result = swap_exact(
+                            &ticks,
+                            &tick_bitmap,
+                            60,
+                            direction,
+                            swap_amount,
+                            sqrt_price_limit,
+                            &slot0,
+                            3000,
+                        );
+                        let raw = swap(
+                            &ticks,
+                            &tick_bitmap,
+                            60,
+                            zero_for_one,
+                            amount_specified,
+                            raw_limit,
+                            &slot0,
+                            3000,
+                        );
+                        match (result, raw) {
+                            (Ok(result), Ok(raw)) => {
+                                let (amount_in, amount_out) = if zero_for_one {
+                                    (raw.amount0_delta, raw.amount1_delta)
+                                } else {
+                                    (raw.amount1_delta, raw.amount0_delta)
+                                };
+                                assert_eq!(result.amount_in, amount_in.into_raw());
+                                assert_eq!(result.amount_out, (-amount_out).into_raw());
+                                assert_eq!(result.token_in, if zero_for_one { 0 } else { 1 });
+                                assert_eq!(result.token_out, 1 - result.token_in);
+                                assert_eq!(result.sqrt_price_after, raw.sqrt_price_after);
+                                assert_eq!(result.liquidity_after, raw.liquidity_after);
+                                assert_eq!(result.tick_after, raw.tick_after);
+                            }
+                            (result, raw) => assert_eq!(
+                                result.map_err(|err| err.to_string()),
+                                raw.map(|raw| raw.as_unsigned())
+                                    .map_err(|err| err.to_string())
+                            ),
+                        }
+                    }
+                }
+            }
+        }
+
+        // a zero swap one for zero still reports token1 as the input
+        let result = swap_exact(
+            &ticks,
+            &tick_bitmap,
+            60,
+            SwapDirection::OneForZero,
+            SwapAmount::ExactInput { amount: U256::ZERO },
+            None,
+            &slot0,
+            3000,
+        )?;
+        assert_eq!((result.amount_in, result.token_in), (U256::ZERO, 1));
+
+        assert!(matches!(
+            SwapAmount::ExactOutput { amount: U256::MAX }.amount_specified(),
+            Err(UniswapV3MathError::Overflow)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_amount() -> eyre::Result<()> {
+        let exact_in = Amount::exact_in(1_000_000);
+        assert!(exact_in.is_exact_input());
+        assert_eq!(I256::from(exact_in), I256::from_str("1000000")?);
+        assert_eq!(exact_in.to_string(), "+1000000 (exact in)");
+        assert_eq!(
+            SwapAmount::from(exact_in),
+            SwapAmount::ExactInput {
+                amount: U256::from(1_000_000)
+            }
+        );
+
+        let exact_out = Amount::exact_out(u128::MAX);
+        assert!(!exact_out.is_exact_input());
+        assert_eq!(
+            exact_out.amount_specified(),
+            -I256::from_raw(U256::from(u128::MAX))
+        );
+        assert_eq!(
+            exact_out.to_string(),
+            "-340282366920938463463374607431768211455 (exact out)"
+        );
+        assert_eq!(
+            SwapAmount::from(exact_out).amount_specified()?,
+            exact_out.amount_specified()
+        );
+        assert_eq!(Amount::exact_in(0).to_string(), "+0 (exact in)");
+        assert_eq!(-exact_in, Amount::exact_out(1_000_000));
+        assert_eq!(-(-exact_out), exact_out);
+        assert_eq!(Amount::from(1_000_000_u128), exact_in);
+        assert_eq!(Amount::from(-1_000_000_i128), Amount::exact_out(1_000_000));
+        assert_eq!(
+            Amount::from(i128::MIN).amount_specified(),
+            -I256::from_raw(U256::from(1_u128 << 127))
+        );
+        assert_eq!(Amount::from(I256::from_str("-5")?), Amount::exact_out(5));
+
+        // the same swap as with the raw amount_specified
+        let (ticks, tick_bitmap, slot0) = init_test_positions()?;
+        let swap_amount = |amount_specified: I256| {
+            swap(
+                &ticks,
+                &tick_bitmap,
+                60,
+                true,
+                amount_specified,
+                tick_math::MIN_SQRT_RATIO + U256::from(1),
+                &slot0,
+                3000,
+            )
+        };
+        assert_eq!(
+            swap_amount(Amount::exact_out(1_000_000_000_000_000).into())?,
+            swap_amount(I256::from_str("-1000000000000000")?)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_limit_at_current_price() -> eyre::Result<()> {
+        let (ticks, tick_bitmap, slot0) = init_test_positions()?;
+        for (zero_for_one, amount_specified) in [
+            (true, "1000000000000000"),
+            (false, "1000000000000000"),
+            (true, "-1000000000000000"),
+            (false, "-1000000000000000"),
+        ] {
+            let result = swap(
+                &ticks,
+                &tick_bitmap,
+                60,
+                zero_for_one,
+                I256::from_str(amount_specified)?,
+                slot0.sqrt_price,
+                &slot0,
+                3000,
+            )?;
+            assert_eq!(
+                result,
+                SwapResult {
+                    amount0_delta: I256::ZERO,
+                    amount1_delta: I256::ZERO,
+                    sqrt_price_after: slot0.sqrt_price,
+                    liquidity_after: slot0.liquidity,
+                    tick_after: slot0.tick,
+                    fee_growth_x_128: U256::ZERO,
+                    tick_crossings: vec![],
+                    cost: SwapCost::default(),
+                }
+            );
+        }
+
+        // a limit on the wrong side of the price still fails
+        assert!(matches!(
+            swap(
+                &ticks,
+                &tick_bitmap,
+                60,
+                true,
+                I256::from_str("1000000000000000")?,
+                slot0.sqrt_price + U256::from(1),
+                &slot0,
+                3000,
+            ),
+            Err(UniswapV3MathError::SplC)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_stops_at_price_limit_between_ticks() -> eyre::Result<()> {
+        let (ticks, tick_bitmap, slot0) = init_test_positions()?;
+        let amount_specified = I256::from_str("100000000000000000000")?;
+        let swap_to = |zero_for_one, sqrt_price_limit| {
+            swap(
+                &ticks,
+                &tick_bitmap,
+                60,
+                zero_for_one,
+                amount_specified,
+                sqrt_price_limit,
+                &slot0,
+                3000,
+            )
+        };
+
+        // (zero_for_one, limit, tick after, token0 in or out)
+        for (zero_for_one, sqrt_price_limit, tick_after, amount0) in [
+            //between -120 and -600, and one wei above -600 which must not be crossed
+            (
+                true,
+                tick_math::get_sqrt_ratio_at_tick(-300)?,
+                -300,
+                "19718744095815106",
+            ),
+            (
+                true,
+                tick_math::get_sqrt_ratio_at_tick(-600)? + U256::from(1),
+                -600,
+                "42799012466860637",
+            ),
+            //between 120 and 600, and one wei below 600
+            (
+                false,
+                tick_math::get_sqrt_ratio_at_tick(300)?,
+                300,
+                "-19340113537681438",
+            ),
+            (
+                false,
+                tick_math::get_sqrt_ratio_at_tick(600)? - U256::from(1),
+                599,
+                "-41338647438450922",
+            ),
+        ] {
+            let result = swap_to(zero_for_one, sqrt_price_limit)?;
+            assert_eq!(result.sqrt_price_after, sqrt_price_limit);
+            assert_eq!(result.tick_after, tick_after);
+            assert_eq!(result.amount0_delta, I256::from_str(amount0)?);
+            //only the tick between the price and the limit is crossed
+            let crossed: Vec<i32> = result.tick_crossings.iter().map(|c| c.tick).collect();
+            assert_eq!(crossed, vec![if zero_for_one { -120 } else { 120 }]);
+            assert_eq!(result.liquidity_after, 1_500_000_000_000_000_000);
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_cost() -> eyre::Result<()> {
+        let (ticks, tick_bitmap, slot0) = init_test_positions()?;
+        let mut costs = Vec::new();
+        for amount in [
+            "1000000000000000",
+            "50000000000000000",
+            "1000000000000000000",
+        ] {
+            let result = swap(
+                &ticks,
+                &tick_bitmap,
+                60,
+                true,
+                I256::from_str(amount)?,
+                tick_math::MIN_SQRT_RATIO + U256::from(1),
+                &slot0,
+                3000,
+            )?;
+            assert_eq!(
+                result.cost.ticks_crossed as usize,
+                result.tick_crossings.len()
+            );
+            costs.push(result.cost);
+        }
+        // the last swap runs out of ticks and walks the empty words down to the minimum price
+        assert_eq!(
+            costs,
+            vec![
+                SwapCost::new(2, 0),
+                SwapCost::new(4, 2),
+                SwapCost::new(62, 3)
+            ]
+        );
+        assert_eq!(
+            costs
+                .iter()
+                .map(|cost| cost.estimated_sstores)
+                .collect::<Vec<_>>(),
+            vec![2, 9, 12]
+        );
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_rounding_residue() -> eyre::Result<()> {
+        // a tick every tick from -100 to -1 without liquidity of its own, so the swap takes a step per tick
+        let mut ticks: HashMap<i32, Tick> = HashMap::new();
+        let mut tick_bitmap: HashMap<i16, U256> = HashMap::new();
+        for tick in -100..0 {
+            ticks.insert(tick, Tick::new(1, 0)?);
+            flip_tick(&mut tick_bitmap, tick, 1)?;
+        }
+        let slot0 = Slot0 {
+            sqrt_price: Q96,
+            liquidity: 1_000_000_000_000_000_000,
+            tick: 0,
+        };
+        let sqrt_price_limit = tick_math::get_sqrt_ratio_at_tick(-100)?;
+        let amount_specified = I256::from_str("1000000000000000000")?;
+        let swap_over = |ticks: &HashMap<i32, Tick>, tick_bitmap: &HashMap<i16, U256>| {
+            swap_with_rounding_residue(
+                ticks,
+                tick_bitmap,
+                1,
+                true,
+                amount_specified,
+                sqrt_price_limit,
+                &slot0,
+                3000,
+            )
+        };
+        let (stepped, stepped_residue) = swap_over(&ticks, &tick_bitmap)?;
+        assert_eq!(stepped.cost.iterations, 101);
+        assert_eq!(
+            stepped,
+            swap(
+                &ticks,
+                &tick_bitmap,
+                1,
+                true,
+                amount_specified,
+                sqrt_price_limit,
+                &slot0,
+                3000,
+            )?
+        );
+        //the first step is to tick 0 at the current price, every other one rounds both amounts
+        assert_eq!(stepped_residue, U256::from(200));
+        assert!(stepped_residue <= U256::from(2 * stepped.cost.iterations));
+
+        //the same price move in a single step loses less to rounding
+        let (single, single_residue) = swap_over(&HashMap::new(), &HashMap::new())?;
+        assert_eq!(single_residue, U256::from(2));
+        assert_eq!(single.sqrt_price_after, stepped.sqrt_price_after);
+        let dust = stepped.amount1_delta - single.amount1_delta;
+        assert_eq!(dust, I256::from_str("45")?);
+        assert!(dust.into_raw() <= stepped_residue);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_exact_output_dust() -> eyre::Result<()> {
+        let (ticks, tick_bitmap, slot0) = init_test_positions()?;
+        // (zero_for_one, amount in, tick after)
+        for (zero_for_one, amount_in, tick_after) in [(true, 3_u64, -1), (false, 3, 0)] {
+            let sqrt_price_limit = if zero_for_one {
+                tick_math::MIN_SQRT_RATIO + U256::from(1)
+            } else {
+                tick_math::MAX_SQRT_RATIO - U256::from(1)
+            };
+            let swap_amount = |amount_specified: I256| {
+                swap(
+                    &ticks,
+                    &tick_bitmap,
+                    60,
+                    zero_for_one,
+                    amount_specified,
+                    sqrt_price_limit,
+                    &slot0,
+                    3000,
+                )
+                .map(|result| result.as_unsigned())
+            };
+
+            // 1 wei out costs 2 wei in plus 1 wei of fee rounded up and stops right after it, like the pool
+            let result = swap_amount(I256::MINUS_ONE)?;
+            assert_eq!(result.amount_out, U256::from(1));
+            assert_eq!(result.amount_in, U256::from(amount_in));
+            assert_eq!(result.tick_after, tick_after);
+            assert_eq!(result.liquidity_after, slot0.liquidity);
+
+            // and it is the least input with a non zero output
+            assert_eq!(
+                swap_amount(I256::from_raw(U256::from(amount_in)))?.amount_out,
+                U256::from(1)
+            );
+            assert_eq!(
+                swap_amount(I256::from_raw(U256::from(amount_in - 1)))?.amount_out,
+                U256::ZERO
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_with_hook() -> eyre::Result<()> {
+        let (ticks, tick_bitmap, slot0) = init_test_positions()?;
+
+        // (zero_for_one, iterations): a step that does not move to the boundary of the word at tick 0, then to -120, to
+        // -600 and on to -877, or to 120, to 600 and on to 876
+        for (zero_for_one, iterations) in [(true, 4), (false, 3)] {
+            let sqrt_price_limit = if zero_for_one {
+                tick_math::MIN_SQRT_RATIO + U256::from(1)
+            } else {
+                tick_math::MAX_SQRT_RATIO - U256::from(1)
+            };
+            let mut steps = Vec::new();
+            let result = swap_with_hook(
+                &ticks,
+                &tick_bitmap,
+                60,
+                zero_for_one,
+                I256::from_str("50000000000000000")?,
+                sqrt_price_limit,
+                &slot0,
+                3000,
+                &mut |step, state| steps.push((step.clone(), state.clone())),
+            )?;
+            assert_eq!(
+                result,
+                swap(
+                    &ticks,
+                    &tick_bitmap,
+                    60,
+                    zero_for_one,
+                    I256::from_str("50000000000000000")?,
+                    sqrt_price_limit,
+                    &slot0,
+                    3000,
+                )?
+            );
+            assert_eq!(steps.len(), iterations);
+
+            // every step starts where the previous one ended and never moves the price against the swap direction
+            let mut sqrt_price = slot0.sqrt_price;
+            for (step, state) in &steps {
+                assert_eq!(step.sqrt_price_start_x96, sqrt_price);
+                if zero_for_one {
+                    assert!(state.sqrt_price_x96 <= sqrt_price);
+                } else {
+                    assert!(state.sqrt_price_x96 >= sqrt_price);
+                }
+                sqrt_price = state.sqrt_price_x96;
+            }
+            let (_, last) = steps.last().expect("a step");
+            assert_eq!(last.sqrt_price_x96, result.sqrt_price_after);
+            assert_eq!(last.liquidity, result.liquidity_after);
+            assert_eq!(last.tick, result.tick_after);
+            assert!(last.amount_specified_remaining.is_zero());
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_price_direction() -> eyre::Result<()> {
+        // runs the debug assertion on the price of every step of the swap loop, for both directions and amount kinds,
+        // amounts that stop inside the first range, cross ticks or run to the limit, and limits between and past ticks
+        let (ticks, tick_bitmap, slot0) = init_test_positions()?;
+        for zero_for_one in [true, false] {
+            let sqrt_price_limits = if zero_for_one {
+                [
+                    tick_math::get_sqrt_ratio_at_tick(-900)?,
+                    tick_math::MIN_SQRT_RATIO + U256::from(1),
+                ]
+            } else {
+                [
+                    tick_math::get_sqrt_ratio_at_tick(900)?,
+                    tick_math::MAX_SQRT_RATIO - U256::from(1),
+                ]
+            };
+            for sqrt_price_limit in sqrt_price_limits {
+                for amount in [
+                    "1",
+                    "1000000000000000",
+                    "50000000000000000",
+                    "1000000000000000000000000",
+                ] {
+                    for amount_specified in [I256::from_str(amount)?, -I256::from_str(amount)?] {
+                        let result = swap(
+                            &ticks,
+                            &tick_bitmap,
+                            60,
+                            zero_for_one,
+                            amount_specified,
+                            sqrt_price_limit,
+                            &slot0,
+                            3000,
+                        )?;
+                        if zero_for_one {
+                            assert!(sqrt_price_limit <= result.sqrt_price_after);
+                            assert!(result.sqrt_price_after <= slot0.sqrt_price);
+                        } else {
+                            assert!(slot0.sqrt_price <= result.sqrt_price_after);
+                            assert!(result.sqrt_price_after <= sqrt_price_limit);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_with_attribution() -> eyre::Result<()> {
+        let (ticks, tick_bitmap, slot0) = init_test_positions()?;
+
+        // (zero_for_one, the ranges swapped through with their liquidity): down to -877 through (-600, 600, 1e18)
+        // and (-1200, -120, 5e17), up to 876 through (-600, 600, 1e18) and (120, 1800, 5e17)
+        for (zero_for_one, ranges) in [
+            (
+                true,
+                [
+                    (Some(-120), None, 1_000_000_000_000_000_000),
+                    (Some(-600), Some(-120), 1_500_000_000_000_000_000),
+                    (None, Some(-600), 500_000_000_000_000_000),
+                ],
+            ),
+            (
+                false,
+                [
+                    (None, Some(120), 1_000_000_000_000_000_000),
+                    (Some(120), Some(600), 1_500_000_000_000_000_000),
+                    (Some(600), None, 500_000_000_000_000_000),
+                ],
+            ),
+        ] {
+            let sqrt_price_limit = if zero_for_one {
+                tick_math::MIN_SQRT_RATIO + U256::from(1)
+            } else {
+                tick_math::MAX_SQRT_RATIO - U256::from(1)
+            };
+            let amount_specified = I256::from_str("50000000000000000")?;
+            let (result, contributions) = swap_with_attribution(
+                &ticks,
+                &tick_bitmap,
+                60,
+                zero_for_one,
+                amount_specified,
+                sqrt_price_limit,
+                &slot0,
+                3000,
+            )?;
+            assert_eq!(
+                result,
+                swap(
+                    &ticks,
+                    &tick_bitmap,
+                    60,
+                    zero_for_one,
+                    amount_specified,
+                    sqrt_price_limit,
+                    &slot0,
+                    3000,
+                )?
+            );
+            assert_eq!(
+                contributions
+                    .iter()
+                    .map(|contribution| (
+                        contribution.tick_lower,
+                        contribution.tick_upper,
+                        contribution.liquidity
+                    ))
+                    .collect::<Vec<_>>(),
+                ranges
+            );
+
+            // the contributions add up to the amounts of the swap
+            let unsigned = result.as_unsigned();
+            let (amount_in, amount_out) = contributions.iter().fold(
+                (U256::ZERO, U256::ZERO),
+                |(amount_in, amount_out), contribution| {
+                    (
+                        amount_in + contribution.amount_in + contribution.fee_amount,
+                        amount_out + contribution.amount_out,
+                    )
+                },
+            );
+            assert_eq!(amount_in, unsigned.amount_in);
+            assert_eq!(amount_out, unsigned.amount_out);
+
+            // the first range is swapped through whole, as a single step from the price at tick 0
+            let boundary =
+                tick_math::get_sqrt_ratio_at_tick(if zero_for_one { -120 } else { 120 })?;
+            let TickRangeContribution {
+                amount_in,
+                amount_out,
+                ..
+            } = contributions[0];
+            if zero_for_one {
+                assert_eq!(
+                    amount_in,
+                    _get_amount_0_delta(boundary, slot0.sqrt_price, slot0.liquidity, true)?
+                );
+                assert_eq!(
+                    amount_out,
+                    _get_amount_1_delta(boundary, slot0.sqrt_price, slot0.liquidity, false)?
+                );
+            } else {
+                assert_eq!(
+                    amount_in,
+                    _get_amount_1_delta(slot0.sqrt_price, boundary, slot0.liquidity, true)?
+                );
+                assert_eq!(
+                    amount_out,
+                    _get_amount_0_delta(slot0.sqrt_price, boundary, slot0.liquidity, false)?
+                );
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_result_as_unsigned() -> eyre::Result<()> {
+        let result = SwapResult {
+            amount0_delta: I256::from_str("50000000000000000")?,
+            amount1_delta: I256::from_str("-48007037308774480")?,
+            sqrt_price_after: U256::from_str("75830083212837298011496951984")?,
+            liquidity_after: 500_000_000_000_000_000,
+            tick_after: -877,
+            fee_growth_x_128: U256::ZERO,
+            tick_crossings: vec![],
+            cost: SwapCost::default(),
+        };
+        assert_eq!(
+            result.as_unsigned(),
+            UnsignedSwapResult {
+                amount_in: U256::from(50_000_000_000_000_000_u128),
+                amount_out: U256::from(48_007_037_308_774_480_u128),
+                token_in: 0,
+                token_out: 1,
+                sqrt_price_after: result.sqrt_price_after,
+                liquidity_after: 500_000_000_000_000_000,
+                tick_after: -877,
+            }
+        );
+
+        let result = SwapResult {
+            amount0_delta: I256::from_str("-48007037308774480")?,
+            amount1_delta: I256::from_str("50000000000000000")?,
+            tick_after: 876,
+            ..result
+        };
+        let unsigned = result.as_unsigned();
+        assert_eq!(unsigned.amount_in, U256::from(50_000_000_000_000_000_u128));
+        assert_eq!(unsigned.amount_out, U256::from(48_007_037_308_774_480_u128));
+        assert_eq!((unsigned.token_in, unsigned.token_out), (1, 0));
+        assert_eq!(unsigned.tick_after, 876);
+
+        // I256::MIN has no positive counterpart but is representable as unsigned
+        let result = SwapResult {
+            amount0_delta: I256::MIN,
+            amount1_delta: I256::MAX,
+            ..result
+        };
+        let unsigned = result.as_unsigned();
+        assert_eq!(unsigned.amount_in, I256::MAX.into_raw());
+        assert_eq!(unsigned.amount_out, U256::from(1) << 255);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_swap_result_to_slot0() -> eyre::Result<()> {
+        let (ticks, tick_bitmap, slot0) = init_test_positions()?;
+        let result = swap(
+            &ticks,
+            &tick_bitmap,
+            60,
+            true,
+            I256::from_str("50000000000000000")?,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+            &slot0,
+            3000,
+        )?;
+        let after = result.to_slot0();
+        assert_eq!(after.sqrt_price, result.sqrt_price_after);
+        assert_eq!(after.liquidity, result.liquidity_after);
+        assert_eq!(after.tick, result.tick_after);
+        //past -120 and -600, the advanced state is consistent
+        assert_eq!(after.tick, -877);
+        assert_eq!(after.liquidity, 500_000_000_000_000_000);
+        Slot0::new(after.sqrt_price, after.liquidity, after.tick)?;
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_slot0_default() -> eyre::Result<()> {
+        let slot0 = Slot0::default();
+        assert_eq!(slot0.tick, 0);
+        assert_eq!(slot0.sqrt_price, U256::from(1) << 96);
+        assert_eq!(slot0.sqrt_price, tick_math::get_sqrt_ratio_at_tick(0)?);
+        assert_eq!(slot0.liquidity, 0);
+        slot0.tick_consistency_check()?;
+        assert_eq!(slot0.price(), 1.0);
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_slot0_new() -> eyre::Result<()> {
+        let sqrt_price = U256::from_str("79149250711305166342700278159")?;
+        assert_eq!(
+            Slot0::new(sqrt_price, 1_000_000_000_000_000_000, -20)?,
+            Slot0 {
+                sqrt_price,
+                liquidity: 1_000_000_000_000_000_000,
+                tick: -20,
+            }
+        );
+        Slot0::new(tick_math::get_sqrt_ratio_at_tick(60)?, 0, 59)?;
+
+        // the sample swap test starts at tick 1 with a price just above the minimum
+        assert!(matches!(
+            Slot0::new(tick_math::MIN_SQRT_RATIO + U256::from(2), 2_000_000, 1),
+            Err(UniswapV3MathError::InconsistentTick {
+                tick: 1,
+                expected: tick_math::MIN_TICK
+            })
+        ));
+        assert!(matches!(
+            Slot0::new(sqrt_price, 0, -19),
+            Err(UniswapV3MathError::InconsistentTick {
+                tick: -19,
+                expected: -20
+            })
+        ));
+        assert!(Slot0::new(tick_math::MAX_SQRT_RATIO, 0, tick_math::MAX_TICK).is_err());
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_slot0_price() -> eyre::Result<()> {
+        let mut slot0 = Slot0::default();
+        slot0.sqrt_price = Q96 / U256::from(2);
+        assert_eq!(slot0.price(), 0.25);
+        slot0.sqrt_price = Q96 * U256::from(3);
+        assert_eq!(slot0.price(), 9.0);
+
+        // (tick, sqrt_price^2 / 2^192 of the sqrt ratio of the tick rounded to the nearest float)
+        for (tick, price) in [
+            (-887272, 2.9389568087743114e-39),
+            (-60, 0.9940182622394903),
+            (60, 1.0060177342688181),
+            (887272, 3.402567868363881e38),
+        ] {
+            slot0.sqrt_price = tick_math::get_sqrt_ratio_at_tick(tick)?;
+            assert!(
+                ((slot0.price() - price) / price).abs() < 1e-15,
+                "price at tick {tick}"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_slot0_tick_consistency_check() -> eyre::Result<()> {
+        let slot0 = Slot0 {
+            sqrt_price: U256::from_str("79149250711305166342700278159")?,
+            liquidity: 1_000_000_000_000_000_000,
+            tick: -20,
+        };
+        slot0.tick_consistency_check()?;
+
+        // a swap down to exactly the price of tick 0 leaves the pool at tick -1
+        let on_boundary = Slot0 {
+            sqrt_price: tick_math::get_sqrt_ratio_at_tick(0)?,
+            tick: -1,
+            ..slot0.clone()
+        };
+        on_boundary.tick_consistency_check()?;
+
+        let inconsistent = Slot0 {
+            tick: 5,
+            ..on_boundary.clone()
+        };
+        assert!(matches!(
+            inconsistent.tick_consistency_check(),
+            Err(UniswapV3MathError::InconsistentTick {
+                tick: 5,
+                expected: 0
+            })
+        ));
+
+        let below_boundary = Slot0 {
+            tick: -2,
+            ..on_boundary
+        };
+        assert!(matches!(
+            below_boundary.tick_consistency_check(),
+            Err(UniswapV3MathError::InconsistentTick {
+                tick: -2,
+                expected: 0
+            })
+        ));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    pub fn test_swap_tracing_events() -> eyre::Result<()> {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        // counts the swap step and tick crossed events by their fields
+        #[derive(Default)]
+        struct CountingSubscriber {
+            steps: Arc<AtomicUsize>,
+            crossings: Arc<AtomicUsize>,
+        }
+
+        impl tracing::Subscriber for CountingSubscriber {
+            fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, _: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+                tracing::span::Id::from_u64(1)
+            }
+            fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+            fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+            fn event(&self, event: &tracing::Event<'_>) {
+                let fields = event.metadata().fields();
+                if fields.field("sqrt_price_next_x96").is_some() {
+                    self.steps.fetch_add(1, Ordering::SeqCst);
+                }
+                if fields.field("liquidity_net").is_some() {
+                    self.crossings.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+            fn enter(&self, _: &tracing::span::Id) {}
+            fn exit(&self, _: &tracing::span::Id) {}
+        }
+
+        // a single position on (-60, 60)
+        let mut ticks: HashMap<i32, Tick> = HashMap::new();
+        let mut tick_bitmap: HashMap<i16, U256> = HashMap::new();
+        for (tick, liquidity_net) in [
+            (-60, 1_000_000_000_000_000_000),
+            (60, -1_000_000_000_000_000_000),
+        ] {
+            ticks.insert(
+                tick,
+                Tick {
+                    liquidity_gross: 1_000_000_000_000_000_000,
+                    liquidity_net,
+                    initialized: true,
+                    ..Default::default()
+                },
+            );
+            flip_tick(&mut tick_bitmap, tick, 60)?;
+        }
+        let slot0 = Slot0 {
+            sqrt_price: U256::from_str("79228162514264337593543950336")?,
+            liquidity: 1_000_000_000_000_000_000,
+            tick: 0,
+        };
+
+        let amount_specified = I256::from_str("50000000000000000")?;
+        let sqrt_price_limit = tick_math::get_sqrt_ratio_at_tick(-120)?;
+
+        let subscriber = CountingSubscriber::default();
+        let steps = subscriber.steps.clone();
+        let crossings = subscriber.crossings.clone();
+        let result = tracing::subscriber::with_default(subscriber, || {
+            swap(
+                &ticks,
+                &tick_bitmap,
+                60,
+                true,
+                amount_specified,
+                sqrt_price_limit,
+                &slot0,
+                3000,
+            )
+        })?;
+
+        // steps to tick 0, to -60 where the position goes out of range and on to the price limit
+        assert_eq!(result.tick_after, -120);
+        assert_eq!(result.liquidity_after, 0);
+        assert_eq!(steps.load(Ordering::SeqCst), 3);
+        assert_eq!(crossings.load(Ordering::SeqCst), 1);
+        Ok(())
+    }
 }
@@ -0,0 +1,148 @@
+use crate::error::UniswapV3MathError;
+use alloy::primitives::{Log, I256, U256};
+use alloy::sol;
+use alloy::sol_types::SolEvent;
+
+sol! {
+    event Swap(
+        address indexed sender,
+        address indexed recipient,
+        int256 amount0,
+        int256 amount1,
+        uint160 sqrtPriceX96,
+        uint128 liquidity,
+        int24 tick
+    );
+}
+
+// the values emitted by a pool Swap event, laid out like SwapResult so on-chain swaps can be
+// reconciled against a local simulation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SwapEventData {
+    // the delta of the balance of token0 of the pool
+    pub amount0: I256,
+    // the delta of the balance of token1 of the pool
+    pub amount1: I256,
+    // the sqrt(price) of the pool after the swap
+    pub sqrt_price_x96: U256,
+    // the liquidity of the pool after the swap
+    pub liquidity: u128,
+    // the log base 1.0001 of price of the pool after the swap
+    pub tick: i32,
+}
+
+impl SwapEventData {
+    //Decodes a Uniswap V3 pool Swap log. The first topic must be the Swap event signature
+    pub fn from_alloy_log(log: &Log) -> Result<Self, UniswapV3MathError> {
+        let event = Swap::decode_raw_log(log.data.topics().iter().copied(), &log.data.data, true)
+            .map_err(|err| UniswapV3MathError::SwapLogDecodeError(err.to_string()))?;
+
+        Ok(SwapEventData {
+            amount0: event.amount0,
+            amount1: event.amount1,
+            sqrt_price_x96: U256::from(event.sqrtPriceX96),
+            liquidity: event.liquidity,
+            tick: event.tick,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Swap, SwapEventData};
+    use crate::{
+        error::UniswapV3MathError,
+        swap::{swap, Slot0},
+        tick_math,
+    };
+    use alloy::primitives::{address, b256, hex, Bytes, Log, LogData, I256, U256};
+    use alloy::sol_types::SolEvent;
+    use std::{collections::HashMap, str::FromStr};
+
+    // the USDC/WETH 0.05% pool state before usdc_weth_swap_log, a single range with no initialized tick crossed
+    fn usdc_weth_slot0() -> eyre::Result<Slot0> {
+        Ok(Slot0 {
+            sqrt_price: U256::from_str("1834200000000000000000000000000000")?,
+            liquidity: 19986106711189348510,
+            tick: 201005,
+        })
+    }
+
+    // a Swap log laid out as the USDC/WETH 0.05% pool emits it, for 2500 USDC out of the pool at usdc_weth_slot0
+    // (~1865.7 USDC/WETH). Not captured from a transaction: the values are those of swap from usdc_weth_slot0
+    fn usdc_weth_swap_log() -> eyre::Result<Log> {
+        let data = hex::decode(concat!(
+            "ffffffffffffffffffffffffffffffffffffffffffffffffffffffff6afd0700",
+            "000000000000000000000000000000000000000000000000129ab23f189c8f0f",
+            "0000000000000000000000000000000000005a6eed013662e6ce6375fa2b89db",
+            "000000000000000000000000000000000000000000000001155cea29b37de09e",
+            "000000000000000000000000000000000000000000000000000000000003112d",
+        ))?;
+
+        Ok(Log::new_unchecked(
+            address!("88e6A0c2dDD26FEEb64F039a2c41296FcB3f5640"),
+            vec![
+                b256!("c42079f94a6350d7e6235f29174924f928cc2ac818eb64fed8004e115fbcca67"),
+                b256!("0000000000000000000000003fc91a3afd70395cd496c647d5a6cc9d4b2b7fad"),
+                b256!("0000000000000000000000003fc91a3afd70395cd496c647d5a6cc9d4b2b7fad"),
+            ],
+            Bytes::from(data),
+        ))
+    }
+
+    #[test]
+    fn test_from_alloy_log() -> eyre::Result<()> {
+        let log = usdc_weth_swap_log()?;
+        assert_eq!(log.data.topics()[0], Swap::SIGNATURE_HASH);
+
+        let event = SwapEventData::from_alloy_log(&log)?;
+
+        assert_eq!(event.amount0, I256::from_str("-2500000000")?);
+        assert_eq!(event.amount1, I256::from_str("1340579823161741071")?);
+        assert_eq!(
+            event.sqrt_price_x96,
+            U256::from_str("1834205311618305096020476060994011")?
+        );
+        assert_eq!(event.liquidity, 19986106711189348510);
+        assert_eq!(event.tick, 201005);
+
+        // the log is the one of the 2500 USDC exact output swap from usdc_weth_slot0
+        let result = swap(
+            &HashMap::new(),
+            &HashMap::new(),
+            10,
+            false,
+            I256::from_str("-2500000000")?,
+            tick_math::MAX_SQRT_RATIO - U256::from(1),
+            &usdc_weth_slot0()?,
+            500,
+        )?;
+        assert_eq!(result.amount0_delta, event.amount0);
+        assert_eq!(result.amount1_delta, event.amount1);
+        assert_eq!(result.sqrt_price_after, event.sqrt_price_x96);
+        assert_eq!(result.liquidity_after, event.liquidity);
+        assert_eq!(result.tick_after, event.tick);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_alloy_log_wrong_event() -> eyre::Result<()> {
+        let mut log = usdc_weth_swap_log()?;
+        // Transfer(address,address,uint256)
+        log.data = LogData::new_unchecked(
+            vec![b256!(
+                "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+            )],
+            log.data.data.clone(),
+        );
+
+        let result = SwapEventData::from_alloy_log(&log);
+        assert!(matches!(
+            result,
+            Err(UniswapV3MathError::SwapLogDecodeError(_))
+        ));
+
+        Ok(())
+    }
+}
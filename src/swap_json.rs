@@ -0,0 +1,186 @@
+use crate::error::UniswapV3MathError;
+use crate::swap::{swap, Slot0, SwapResult};
+use crate::tick::Tick;
+use crate::tick_bitmap::flip_tick;
+use alloy::primitives::{I256, U256};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// serde for I256 as a signed decimal string, e.g. "-1000000", for use with #[serde(with = "...")]
+pub mod i256_decimal {
+    use alloy::primitives::I256;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &I256, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<I256, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        I256::from_dec_str(&value).map_err(D::Error::custom)
+    }
+}
+
+// the pool state and swap parameters accepted by swap_json
+#[derive(Debug, Deserialize)]
+pub struct SwapJsonInput {
+    // the initialized ticks keyed by tick index, the tick bitmap is derived from them.
+    // Tick fields that are left out default to zero
+    pub ticks: HashMap<i32, Tick>,
+    pub tick_spacing: i32,
+    // the pool fee in hundredths of a bip, i.e. 1e-6
+    pub fee: u32,
+    pub slot0: Slot0,
+    pub zero_for_one: bool,
+    // positive for exact input, negative for exact output
+    #[serde(with = "i256_decimal")]
+    pub amount_specified: I256,
+    pub sqrt_price_limit: U256,
+}
+
+impl SwapJsonInput {
+    pub fn from_json(input: &str) -> Result<Self, UniswapV3MathError> {
+        serde_json::from_str(input)
+            .map_err(|err| UniswapV3MathError::SwapJsonError(err.to_string()))
+    }
+
+    pub fn swap(&self) -> Result<SwapResult, UniswapV3MathError> {
+        let mut tick_bitmap: HashMap<i16, U256> = HashMap::new();
+        for tick in self.ticks.keys() {
+            flip_tick(&mut tick_bitmap, *tick, self.tick_spacing)?;
+        }
+
+        swap(
+            &self.ticks,
+            &tick_bitmap,
+            self.tick_spacing,
+            self.zero_for_one,
+            self.amount_specified,
+            self.sqrt_price_limit,
+            &self.slot0,
+            self.fee,
+        )
+    }
+}
+
+//Runs a swap described by a JSON SwapJsonInput and returns the SwapResult as JSON, so tools in other languages
+//can drive the simulator through a thin binary
+pub fn swap_json(input: &str) -> Result<String, UniswapV3MathError> {
+    let result = SwapJsonInput::from_json(input)?.swap()?;
+
+    serde_json::to_string(&result).map_err(|err| UniswapV3MathError::SwapJsonError(err.to_string()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::swap_json;
+    use crate::{
+        error::UniswapV3MathError,
+        swap::{SwapCost, SwapResult},
+    };
+    use alloy::primitives::{I256, U256};
+    use std::str::FromStr;
+
+    // the 0.3% test pool at tick 0 with positions (-600, 600, 1e18), (-1200, -120, 5e17) and (120, 1800, 5e17)
+    const SWAP_JSON_FIXTURE: &str = r#"{
+        "ticks": {
+            "-1200": { "liquidity_gross": 500000000000000000, "liquidity_net": 500000000000000000, "initialized": true },
+            "-600": { "liquidity_gross": 1000000000000000000, "liquidity_net": 1000000000000000000, "initialized": true },
+            "-120": { "liquidity_gross": 500000000000000000, "liquidity_net": -500000000000000000, "initialized": true },
+            "120": { "liquidity_gross": 500000000000000000, "liquidity_net": 500000000000000000, "initialized": true },
+            "600": { "liquidity_gross": 1000000000000000000, "liquidity_net": -1000000000000000000, "initialized": true },
+            "1800": { "liquidity_gross": 500000000000000000, "liquidity_net": -500000000000000000, "initialized": true }
+        },
+        "tick_spacing": 60,
+        "fee": 3000,
+        "slot0": {
+            "sqrt_price": "79228162514264337593543950336",
+            "liquidity": 1000000000000000000,
+            "tick": 0
+        },
+        "zero_for_one": true,
+        "amount_specified": "50000000000000000",
+        "sqrt_price_limit": "4295128740"
+    }"#;
+
+    #[test]
+    fn test_swap_json() -> eyre::Result<()> {
+        let output = swap_json(SWAP_JSON_FIXTURE)?;
+
+        let value: serde_json::Value = serde_json::from_str(&output)?;
+        assert_eq!(value["tick_after"], -877);
+        assert_eq!(value["liquidity_after"], 500_000_000_000_000_000_u64);
+        for field in [
+            "amount0_delta",
+            "amount1_delta",
+            "sqrt_price_after",
+            "fee_growth_x_128",
+        ] {
+            assert!(value[field].is_string(), "{field}");
+        }
+
+        let result: SwapResult = serde_json::from_str(&output)?;
+        assert_eq!(result.amount0_delta, I256::from_str("50000000000000000")?);
+        assert_eq!(result.amount1_delta, I256::from_str("-48007037308774480")?);
+        assert_eq!(
+            result.sqrt_price_after,
+            U256::from_str("75830083212837298011496951984")?
+        );
+        assert_eq!(result.cost.ticks_crossed, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_amount_deltas_round_trip() -> eyre::Result<()> {
+        let result = SwapResult {
+            amount0_delta: I256::MAX,
+            amount1_delta: I256::MIN,
+            sqrt_price_after: U256::from_str("75830083212837298011496951984")?,
+            liquidity_after: 500_000_000_000_000_000,
+            tick_after: -877,
+            fee_growth_x_128: U256::ZERO,
+            tick_crossings: vec![],
+            cost: SwapCost {
+                iterations: 4,
+                ticks_crossed: 2,
+                estimated_sstores: 9,
+            },
+        };
+
+        let json = serde_json::to_string(&result)?;
+        let value: serde_json::Value = serde_json::from_str(&json)?;
+        assert_eq!(
+            value["amount0_delta"],
+            "57896044618658097711785492504343953926634992332820282019728792003956564819967"
+        );
+        assert_eq!(
+            value["amount1_delta"],
+            "-57896044618658097711785492504343953926634992332820282019728792003956564819968"
+        );
+        assert_eq!(serde_json::from_str::<SwapResult>(&json)?, result);
+
+        let result = SwapResult {
+            amount0_delta: I256::from_str("-1000000")?,
+            amount1_delta: I256::ZERO,
+            ..result
+        };
+        let json = serde_json::to_string(&result)?;
+        let value: serde_json::Value = serde_json::from_str(&json)?;
+        assert_eq!(value["amount0_delta"], "-1000000");
+        assert_eq!(value["amount1_delta"], "0");
+        assert_eq!(serde_json::from_str::<SwapResult>(&json)?, result);
+
+        // hex is not a signed decimal
+        let json = json.replace("\"-1000000\"", "\"-0xf4240\"");
+        assert!(serde_json::from_str::<SwapResult>(&json).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_swap_json_invalid_input() {
+        assert!(matches!(
+            swap_json(r#"{ "ticks": {} }"#),
+            Err(UniswapV3MathError::SwapJsonError(_))
+        ));
+    }
+}
@@ -7,6 +7,7 @@ use crate::{
         _get_amount_0_delta, _get_amount_1_delta, get_next_sqrt_price_from_input,
         get_next_sqrt_price_from_output,
     },
+    U256_1,
 };
 
 // //returns (
@@ -15,6 +16,8 @@ use crate::{
 //         uint256 amountOut,
 //         uint256 feeAmount
 //     )
+// with zero liquidity the cost of moving to the target is zero, so the price jumps to the target with zero amounts
+// and fee without reaching the price from amount functions that divide by the liquidity
 pub fn compute_swap_step(
     sqrt_ratio_current_x_96: U256,
     sqrt_ratio_target_x_96: U256,
@@ -24,16 +27,19 @@ pub fn compute_swap_step(
 ) -> Result<(U256, U256, U256, U256), UniswapV3MathError> {
     let zero_for_one = sqrt_ratio_current_x_96 >= sqrt_ratio_target_x_96;
     let exact_in = amount_remaining >= I256::ZERO;
+    // -amount_remaining as uint256, also for I256::MIN which can not be negated
+    let amount_remaining_abs = amount_remaining.unsigned_abs();
 
     let sqrt_ratio_next_x_96: U256;
     let mut amount_in = U256::ZERO;
     let mut amount_out = U256::ZERO;
 
     if exact_in {
-        let amount_remaining_less_fee = mul_div(
+        let amount_remaining_less_fee = mul_div_pips(
             amount_remaining.into_raw(),
-            U256::from(1e6 as u32 - fee_pips),    //1e6 - fee_pips
-            U256::from_limbs([1000000, 0, 0, 0]), //1e6
+            1e6 as u32 - fee_pips, //1e6 - fee_pips
+            1e6 as u32,
+            false,
         )?;
 
         amount_in = if zero_for_one {
@@ -79,13 +85,13 @@ pub fn compute_swap_step(
             )?
         };
 
-        sqrt_ratio_next_x_96 = if (-amount_remaining).into_raw() >= amount_out {
+        sqrt_ratio_next_x_96 = if amount_remaining_abs >= amount_out {
             sqrt_ratio_target_x_96
         } else {
             get_next_sqrt_price_from_output(
                 sqrt_ratio_current_x_96,
                 liquidity,
-                (-amount_remaining).into_raw(),
+                amount_remaining_abs,
                 zero_for_one,
             )?
         };
@@ -131,29 +137,60 @@ pub fn compute_swap_step(
         }
     }
 
-    if !exact_in && amount_out > (-amount_remaining).into_raw() {
-        amount_out = (-amount_remaining).into_raw();
+    if !exact_in && amount_out > amount_remaining_abs {
+        amount_out = amount_remaining_abs;
     }
 
     if exact_in && sqrt_ratio_next_x_96 != sqrt_ratio_target_x_96 {
         let fee_amount = amount_remaining.into_raw() - amount_in;
         Ok((sqrt_ratio_next_x_96, amount_in, amount_out, fee_amount))
     } else {
-        let fee_amount = mul_div_rounding_up(
-            amount_in,
-            U256::from(fee_pips),
-            U256::from(1e6 as u32 - fee_pips),
-        )?;
+        let fee_amount = mul_div_pips(amount_in, fee_pips, 1e6 as u32 - fee_pips, true)?;
 
         Ok((sqrt_ratio_next_x_96, amount_in, amount_out, fee_amount))
     }
 }
 
+//mul_div or mul_div_rounding_up of a by the fee ratio b / denominator with the same result, but a single 256 bit
+//multiplication and division instead of the 512 bit mul_div whenever a * b fits in 256 bits, which for amounts
+//below 2^236 is always the case
+fn mul_div_pips(
+    a: U256,
+    b: u32,
+    denominator: u32,
+    round_up: bool,
+) -> Result<U256, UniswapV3MathError> {
+    if denominator != 0 {
+        if let Some(product) = a.checked_mul(U256::from(b)) {
+            let denominator = U256::from(denominator);
+            let result = product / denominator;
+            // result < U256::MAX since denominator > 1 whenever the product has a remainder
+            if round_up && !(product % denominator).is_zero() {
+                return Ok(result + U256_1);
+            }
+            return Ok(result);
+        }
+    }
+
+    if round_up {
+        mul_div_rounding_up(a, U256::from(b), U256::from(denominator))
+    } else {
+        mul_div(a, U256::from(b), U256::from(denominator))
+    }
+}
+
 #[cfg(test)]
 mod test {
 
-    use crate::sqrt_price_math::{get_next_sqrt_price_from_input, get_next_sqrt_price_from_output};
-    use crate::swap_math::compute_swap_step;
+    use crate::error::UniswapV3MathError;
+    use crate::full_math::mul_div;
+    use crate::full_math::mul_div_rounding_up;
+    use crate::sqrt_price_math::{
+        _get_amount_0_delta, _get_amount_1_delta, get_next_sqrt_price_from_input,
+        get_next_sqrt_price_from_output,
+    };
+    use crate::swap_math::{compute_swap_step, mul_div_pips};
+    use crate::tick_math::get_sqrt_ratio_at_tick;
     use crate::U256_1;
     use alloy::primitives::{I256, U256};
     use std::str::FromStr;
@@ -392,4 +429,593 @@ mod test {
         assert_eq!(amount_in, U256_1);
         assert_eq!(fee_amount, U256_1);
     }
+
+    #[test]
+    fn test_compute_swap_step_zero_liquidity() -> eyre::Result<()> {
+        let price = U256::from_str("79228162514264337593543950336")?;
+        for price_target in [get_sqrt_ratio_at_tick(-600)?, get_sqrt_ratio_at_tick(600)?] {
+            for amount_remaining in [
+                I256::from_str("1000000000000000000")?,
+                I256::from_str("-1000000000000000000")?,
+                I256::ONE,
+                I256::MINUS_ONE,
+                I256::MAX,
+                I256::MIN,
+            ] {
+                for fee in [0, 3000, 999_999] {
+                    let (sqrt_p, amount_in, amount_out, fee_amount) =
+                        compute_swap_step(price, price_target, 0, amount_remaining, fee)?;
+                    assert_eq!(sqrt_p, price_target);
+                    assert_eq!(amount_in, U256::ZERO);
+                    assert_eq!(amount_out, U256::ZERO);
+                    assert_eq!(fee_amount, U256::ZERO);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_compute_swap_step_zero_fee() {
+        let price = U256::from_str("79228162514264337593543950336").unwrap();
+        let liquidity = 2e18 as u128;
+        let fee = 0;
+
+        //------------------------------------------------------------
+
+        //exact amount in that gets capped at price target, the input is exactly the cost of the price move
+        let price_target = U256::from_str("79623317895830914510639640423").unwrap();
+        let amount = I256::from_str("1000000000000000000").unwrap();
+
+        let (sqrt_p, amount_in, amount_out, fee_amount) =
+            compute_swap_step(price, price_target, liquidity, amount, fee).unwrap();
+
+        assert_eq!(sqrt_p, price_target);
+        assert_eq!(fee_amount, U256::ZERO);
+        assert_eq!(
+            amount_in,
+            _get_amount_1_delta(price, price_target, liquidity, true).unwrap()
+        );
+        assert_eq!(amount_in, U256::from_str("9975124224178055").unwrap());
+        assert_eq!(amount_out, U256::from_str("9925619580021728").unwrap());
+
+        //------------------------------------------------------------
+
+        //exact amount in that is fully spent before the price target, nothing is kept as fee
+        let price_target = get_sqrt_ratio_at_tick(-600).unwrap();
+        let amount = I256::from_str("1000000000000000").unwrap();
+
+        let (sqrt_p, amount_in, amount_out, fee_amount) =
+            compute_swap_step(price, price_target, liquidity, amount, fee).unwrap();
+
+        assert_eq!(
+            sqrt_p,
+            get_next_sqrt_price_from_input(price, liquidity, amount.into_raw(), true).unwrap()
+        );
+        assert_eq!(fee_amount, U256::ZERO);
+        assert_eq!(amount_in, amount.into_raw());
+        assert_eq!(
+            amount_in,
+            _get_amount_0_delta(sqrt_p, price, liquidity, true).unwrap()
+        );
+        assert_eq!(amount_out, U256::from_str("999500249875062").unwrap());
+
+        //------------------------------------------------------------
+
+        //exact amount out that gets capped at price target
+        let price_target = U256::from_str("79623317895830914510639640423").unwrap();
+        let amount = I256::from_str("-1000000000000000000").unwrap();
+
+        let (sqrt_p, amount_in, amount_out, fee_amount) =
+            compute_swap_step(price, price_target, liquidity, amount, fee).unwrap();
+
+        assert_eq!(sqrt_p, price_target);
+        assert_eq!(fee_amount, U256::ZERO);
+        assert_eq!(
+            amount_in,
+            _get_amount_1_delta(price, price_target, liquidity, true).unwrap()
+        );
+        assert_eq!(
+            amount_out,
+            _get_amount_0_delta(price, price_target, liquidity, false).unwrap()
+        );
+
+        //------------------------------------------------------------
+
+        //exact amount out that is fully received before the price target
+        let price_target = get_sqrt_ratio_at_tick(-600).unwrap();
+        let amount = I256::from_str("-1000000000000000").unwrap();
+
+        let (sqrt_p, amount_in, amount_out, fee_amount) =
+            compute_swap_step(price, price_target, liquidity, amount, fee).unwrap();
+
+        assert_eq!(
+            sqrt_p,
+            get_next_sqrt_price_from_output(price, liquidity, amount.unsigned_abs(), true).unwrap()
+        );
+        assert_eq!(fee_amount, U256::ZERO);
+        assert_eq!(amount_out, amount.unsigned_abs());
+        assert_eq!(
+            amount_in,
+            _get_amount_0_delta(sqrt_p, price, liquidity, true).unwrap()
+        );
+        assert_eq!(amount_in, U256::from_str("1000500250125063").unwrap());
+    }
+
+    #[test]
+    fn test_compute_swap_step_exact_out_fee_rounds_up() {
+        //exact amount out that is fully received before the price target, the fee is taken on top of amount_in
+        //as amount_in * fee / (1e6 - fee) rounded up, like FullMath.mulDivRoundingUp in SwapMath
+        let price = U256::from_str("79228162514264337593543950336").unwrap();
+        let price_target = get_sqrt_ratio_at_tick(-600).unwrap();
+        let liquidity = 2e18 as u128;
+        let amount = I256::from_str("-1000000000000000").unwrap();
+        let fee = 3000;
+
+        let (sqrt_p, amount_in, amount_out, fee_amount) =
+            compute_swap_step(price, price_target, liquidity, amount, fee).unwrap();
+
+        assert_eq!(
+            sqrt_p,
+            U256::from_str("79188548433007205424747178360").unwrap()
+        );
+        assert_eq!(amount_out, U256::from_str("1000000000000000").unwrap());
+        assert_eq!(amount_in, U256::from_str("1000500250125063").unwrap());
+        assert_eq!(fee_amount, U256::from_str("3010532347418").unwrap());
+
+        //rounding down would under-charge the fee by 1 wei
+        let fee_rounded_down =
+            mul_div(amount_in, U256::from(fee), U256::from(1e6 as u32 - fee)).unwrap();
+        assert_eq!(fee_rounded_down, U256::from_str("3010532347417").unwrap());
+        assert_eq!(fee_amount, fee_rounded_down + U256_1);
+
+        //a single wei of input is still charged a fee
+        let price = U256::from_str("20282409603651670423947251286016").unwrap();
+        let price_target = price * U256::from(9) / U256::from(10);
+        let amount_remaining = -I256::from_limbs(*U256::from(263000).as_limbs());
+
+        let (_, amount_in, _, fee_amount) =
+            compute_swap_step(price, price_target, 1024, amount_remaining, fee).unwrap();
+
+        assert_eq!(amount_in, U256_1);
+        assert_eq!(fee_amount, U256_1);
+    }
+
+    #[test]
+    fn test_compute_swap_step_exact_out_token0_and_token1() -> eyre::Result<()> {
+        //away from a price of 1 the token0 and token1 outputs round differently: token1 out moves the price down to
+        //a price rounded down, token0 out moves it up to a price rounded up. amount_in always rounds up
+        let price = get_sqrt_ratio_at_tick(1000)?;
+        let liquidity = 2_000_000_000_000_000_000;
+        //(target tick, amount remaining, sqrt price next, amount in, amount out, fee amount)
+        let vectors = [
+            //token1 out, received before the target
+            (
+                400,
+                "-1000000000000000",
+                "83250454977419090834385571294",
+                "905272503930566",
+                "1000000000000000",
+                "2723989480233",
+            ),
+            //token0 out, received before the target
+            (
+                1600,
+                "-1000000000000000",
+                "83333872194769273158342023384",
+                "1105746610876248",
+                "1000000000000000",
+                "3327221497121",
+            ),
+            //token1 out, capped by the target
+            (
+                990,
+                "-1000000000000000000",
+                "83248436514742674201615946657",
+                "951422067804793",
+                "1050953161410331",
+                "2862854767718",
+            ),
+            //token0 out, capped by the target
+            (
+                1010,
+                "-1000000000000000000",
+                "83331722423045409318760427019",
+                "1051478743096863",
+                "950946499450906",
+                "3163928013331",
+            ),
+        ];
+
+        for (target_tick, amount_remaining, sqrt_p, amount_in, amount_out, fee_amount) in vectors {
+            let price_target = get_sqrt_ratio_at_tick(target_tick)?;
+            let step = compute_swap_step(
+                price,
+                price_target,
+                liquidity,
+                I256::from_str(amount_remaining)?,
+                3000,
+            )?;
+            assert_eq!(
+                step,
+                (
+                    U256::from_str(sqrt_p)?,
+                    U256::from_str(amount_in)?,
+                    U256::from_str(amount_out)?,
+                    U256::from_str(fee_amount)?
+                )
+            );
+
+            //amount_in is the delta of the input token rounded up, one wei above rounding it down
+            let (sqrt_p, amount_in, _, _) = step;
+            let amount_in_rounded_down = if target_tick < 1000 {
+                _get_amount_0_delta(sqrt_p, price, liquidity, false)?
+            } else {
+                _get_amount_1_delta(price, sqrt_p, liquidity, false)?
+            };
+            assert_eq!(amount_in, amount_in_rounded_down + U256_1);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_compute_swap_step_price_direction() {
+        //the next price lies between the current price and the target, for every direction and amount kind
+        let price = U256::from_str("79228162514264337593543950336").unwrap();
+        let price_down = get_sqrt_ratio_at_tick(-600).unwrap();
+        let price_up = get_sqrt_ratio_at_tick(600).unwrap();
+
+        for liquidity in [1_000_000u128, 2e18 as u128, u64::MAX as u128] {
+            for amount in [
+                "1",
+                "1000000000000000",
+                "1000000000000000000000000",
+                "-1",
+                "-1000000000000000",
+                "-1000000000000000000000000",
+            ] {
+                let amount = I256::from_str(amount).unwrap();
+                for fee in [0, 500, 3000, 10000] {
+                    let (sqrt_p, _, _, _) =
+                        compute_swap_step(price, price_down, liquidity, amount, fee).unwrap();
+                    assert!(price_down <= sqrt_p && sqrt_p <= price);
+
+                    let (sqrt_p, _, _, _) =
+                        compute_swap_step(price, price_up, liquidity, amount, fee).unwrap();
+                    assert!(price <= sqrt_p && sqrt_p <= price_up);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_mul_div_pips() -> eyre::Result<()> {
+        let amounts = [
+            U256::ZERO,
+            U256_1,
+            U256::from(999_999),
+            U256::from(1_000_000_000_000_000_000_u128),
+            (U256_1 << 236) - U256_1,
+            U256_1 << 236,
+            U256::MAX / U256::from(3000),
+            U256::MAX - U256_1,
+            U256::MAX,
+        ];
+        for a in amounts {
+            for (b, denominator) in [
+                (997_000, 1_000_000),
+                (3000, 997_000),
+                (0, 1_000_000),
+                (1, 1),
+            ] {
+                assert_eq!(
+                    mul_div_pips(a, b, denominator, false)?,
+                    mul_div(a, U256::from(b), U256::from(denominator))?
+                );
+                assert_eq!(
+                    mul_div_pips(a, b, denominator, true).map_err(|err| err.to_string()),
+                    mul_div_rounding_up(a, U256::from(b), U256::from(denominator))
+                        .map_err(|err| err.to_string())
+                );
+            }
+        }
+        assert!(matches!(
+            mul_div_pips(U256_1, 1_000_000, 0, true),
+            Err(UniswapV3MathError::DenominatorIsZero)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_compute_swap_step_vectors() -> eyre::Result<()> {
+        // (current tick, target tick, liquidity, amount remaining, fee, sqrt price next, amount in, amount out, fee
+        // amount), computed with a big integer port of the Solidity SwapMath: exact input and output steps capped at
+        // the target or not in both directions, the fee tiers and the extremes of the fee, amounts of 2^236 and up
+        // which overflow the 256 bit fee multiplication, and the bounds of the amount, the price and the liquidity
+        let vectors: [(i32, i32, u128, &str, u32, &str, &str, &str, &str); 23] = [
+            (
+                0,
+                -50000,
+                2000000000000000000,
+                "1000000000000000000",
+                3000,
+                "52871646656165724119815782674",
+                "997000000000000000",
+                "665331998665331998",
+                "3000000000000000",
+            ),
+            (
+                0,
+                -50000,
+                2000000000000000000,
+                "1000000000000000",
+                3000,
+                "79188686953817859390637717434",
+                "997000000000000",
+                "996503243133298",
+                "3000000000000",
+            ),
+            (
+                0,
+                50000,
+                2000000000000000000,
+                "1000000000000000000",
+                500,
+                "118822436730767940305917539516",
+                "999500000000000000",
+                "666444407401233538",
+                "500000000000000",
+            ),
+            (
+                0,
+                50000,
+                2000000000000000000,
+                "1000000000000000",
+                500,
+                "79267756788480841196256323925",
+                "999500000000000",
+                "999000749375499",
+                "500000000000",
+            ),
+            (
+                0,
+                -50000,
+                2000000000000000000,
+                "-1000000000000000000",
+                3000,
+                "39614081257132168796771975168",
+                "2000000000000000000",
+                "1000000000000000000",
+                "6018054162487463",
+            ),
+            (
+                0,
+                -50000,
+                2000000000000000000,
+                "-1000000000000000",
+                3000,
+                "79188548433007205424747178360",
+                "1000500250125063",
+                "1000000000000000",
+                "3010532347418",
+            ),
+            (
+                0,
+                50000,
+                2000000000000000000,
+                "-1000000000000000000",
+                10000,
+                "158456325028528675187087900672",
+                "2000000000000000000",
+                "1000000000000000000",
+                "20202020202020203",
+            ),
+            (
+                0,
+                50000,
+                2000000000000000000,
+                "-1000000000000000",
+                10000,
+                "79267796412470572879983942308",
+                "1000500250125063",
+                "1000000000000000",
+                "10106063132577",
+            ),
+            (
+                -1,
+                1,
+                2000000000000000000,
+                "1000000000000000000",
+                0,
+                "79232123823359799118286999568",
+                "199990000749938",
+                "199990000749937",
+                "0",
+            ),
+            (
+                1,
+                -1,
+                2000000000000000000,
+                "-1000000000000000000",
+                0,
+                "79224201403219477170569942574",
+                "199990000749938",
+                "199990000749937",
+                "0",
+            ),
+            (
+                0,
+                -1,
+                1,
+                "1",
+                3000,
+                "79228162514264337593543950336",
+                "0",
+                "0",
+                "1",
+            ),
+            (
+                0,
+                1,
+                1,
+                "-1",
+                3000,
+                "79232123823359799118286999568",
+                "1",
+                "0",
+                "1",
+            ),
+            (
+                0,
+                -50000,
+                2000000000000000000,
+                "1000000000000000000",
+                999999,
+                "79228122900202887492100204286",
+                "1000000000000",
+                "999999500000",
+                "999999000000000000",
+            ),
+            (
+                0,
+                50000,
+                2000000000000000000,
+                "-1000000000000000",
+                999999,
+                "79267796412470572879983942308",
+                "1000500250125063",
+                "1000000000000000",
+                "1000499249624812874937",
+            ),
+            (
+                0,
+                -887272,
+                u128::MAX,
+                "1766847064778384329583297500742918515827483896875618958121606201292619776",
+                3000,
+                "4295128739",
+                "6276865795046577716376444686000031194474954365209139664138",
+                "340282366920938463444927169965653491711",
+                "18887259162627615997120696146439411818881507618482867596",
+            ),
+            (
+                0,
+                887272,
+                u128::MAX,
+                "1766847064778384329583297500742918515827483896875618958121606201292619776",
+                3000,
+                "1461446703485210103287273052203988822378723970342",
+                "6276865796315986612967337485317294249384351720687298020247",
+                "340282366920938463444927169969384229630",
+                "18887259166447301744134415703061065945991028246802300964",
+            ),
+            (
+                0,
+                -887272,
+                u128::MAX,
+                "-1766847064778384329583297500742918515827483896875618958121606201292619776",
+                3000,
+                "4295128739",
+                "6276865795046577716376444686000031194474954365209139664138",
+                "340282366920938463444927169965653491711",
+                "18887259162627615997120696146439411818881507618482867596",
+            ),
+            (
+                -50000,
+                50000,
+                u128::MAX,
+                "1000000000000000000",
+                500,
+                "6504256538020985012144935735",
+                "999499998573887488",
+                "148301874680012121852",
+                "500001426112512",
+            ),
+            (
+                50000,
+                -50000,
+                u128::MAX,
+                "-1000000000000000000",
+                500,
+                "965075977353221155028390252272",
+                "6739631594114122",
+                "1000000000000000000",
+                "3371501547831",
+            ),
+            (
+                887272,
+                0,
+                2000000000000000000,
+                "57896044618658097711785492504343953926634992332820282019728792003956564819967",
+                3000,
+                "79228162514264337593543950336",
+                "2000000000000000000",
+                "36892101422195407058628068004439378416",
+                "6018054162487463",
+            ),
+            (
+                -887272,
+                0,
+                2000000000000000000,
+                "-57896044618658097711785492504343953926634992332820282019728792003956564819968",
+                10000,
+                "79228162514264337593543950336",
+                "2000000000000000000",
+                "36892101414734492124497328878318401435",
+                "20202020202020203",
+            ),
+            (
+                0,
+                -50000,
+                0,
+                "1000000000000000000",
+                3000,
+                "6504256538020985011912221507",
+                "0",
+                "0",
+                "0",
+            ),
+            (
+                0,
+                50000,
+                u128::MAX,
+                "110427941548649020598956093796432407239217743554726184882600387580788737",
+                10000,
+                "965075977353221155028623082916",
+                "3804687393966383037639907477603148103669",
+                "312346797805344152275505445637567050235",
+                "38431185797640232703433408864678263674",
+            ),
+        ];
+        for (
+            current_tick,
+            target_tick,
+            liquidity,
+            amount_remaining,
+            fee,
+            sqrt_price_next,
+            amount_in,
+            amount_out,
+            fee_amount,
+        ) in vectors
+        {
+            assert_eq!(
+                compute_swap_step(
+                    get_sqrt_ratio_at_tick(current_tick)?,
+                    get_sqrt_ratio_at_tick(target_tick)?,
+                    liquidity,
+                    I256::from_str(amount_remaining)?,
+                    fee,
+                )?,
+                (
+                    U256::from_str(sqrt_price_next)?,
+                    U256::from_str(amount_in)?,
+                    U256::from_str(amount_out)?,
+                    U256::from_str(fee_amount)?,
+                ),
+                "step from tick {current_tick} to {target_tick} with {amount_remaining} at fee {fee}"
+            );
+        }
+        Ok(())
+    }
 }
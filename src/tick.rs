@@ -1,5 +1,14 @@
+use crate::error::UniswapV3MathError;
+use crate::tick_math;
 use alloy::primitives::U256;
+use std::collections::HashMap;
 
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default)
+)]
 pub struct Tick {
     // the total position liquidity that references this tick
     pub liquidity_gross: u128,
@@ -21,3 +30,334 @@ pub struct Tick {
     // these 8 bits are set to prevent fresh sstores when crossing newly initialized ticks
     pub initialized: bool,
 }
+
+impl Tick {
+    //Builds a tick with zero outside growth, initialized iff liquidity_gross != 0. Fails if |liquidity_net| exceeds
+    //liquidity_gross, which no sequence of mints and burns can produce since every position adds its liquidity to
+    //the gross of both of its ticks
+    pub fn new(liquidity_gross: u128, liquidity_net: i128) -> Result<Tick, UniswapV3MathError> {
+        if liquidity_net.unsigned_abs() > liquidity_gross {
+            return Err(UniswapV3MathError::InvalidTick {
+                liquidity_gross,
+                liquidity_net,
+            });
+        }
+
+        Ok(Tick {
+            liquidity_gross,
+            liquidity_net,
+            initialized: liquidity_gross != 0,
+            ..Default::default()
+        })
+    }
+
+    //Flips the seconds spent on the other side of the tick when it is crossed at `time`, as in Tick.cross. Like the
+    //uint32 in Solidity the subtraction wraps, a difference of two seconds outside stays correct across the overflow
+    pub fn cross_seconds_outside(&mut self, time: u32) {
+        self.seconds_outside = time.wrapping_sub(self.seconds_outside);
+    }
+}
+
+//Derives the max liquidity per tick from the tick spacing, Tick.tickSpacingToMaxLiquidityPerTick. Bounds the
+//liquidity_gross of a tick so the liquidity of a pool with every usable tick initialized fits in a u128
+pub fn tick_spacing_to_max_liquidity_per_tick(tick_spacing: i32) -> u128 {
+    let min_tick = (tick_math::MIN_TICK / tick_spacing) * tick_spacing;
+    let max_tick = (tick_math::MAX_TICK / tick_spacing) * tick_spacing;
+    let num_ticks = ((max_tick - min_tick) / tick_spacing) as u128 + 1;
+    u128::MAX / num_ticks
+}
+
+// returns (uint256 feeGrowthInside0X128, uint256 feeGrowthInside1X128)
+// fee growth is unchecked in solidity, all subtractions wrap so the inside growth stays correct
+// after the global accumulators overflow
+pub fn get_fee_growth_inside(
+    ticks: &HashMap<i32, Tick>,
+    tick_lower: i32,
+    tick_upper: i32,
+    tick_current: i32,
+    fee_growth_global_0_x_128: U256,
+    fee_growth_global_1_x_128: U256,
+) -> (U256, U256) {
+    let empty = Tick::default();
+    let lower = ticks.get(&tick_lower).unwrap_or(&empty);
+    let upper = ticks.get(&tick_upper).unwrap_or(&empty);
+
+    // calculate fee growth below
+    let (fee_growth_below_0_x_128, fee_growth_below_1_x_128) = if tick_current >= tick_lower {
+        (
+            lower.fee_growth_outside_0_x_128,
+            lower.fee_growth_outside_1_x_128,
+        )
+    } else {
+        (
+            fee_growth_global_0_x_128.wrapping_sub(lower.fee_growth_outside_0_x_128),
+            fee_growth_global_1_x_128.wrapping_sub(lower.fee_growth_outside_1_x_128),
+        )
+    };
+
+    // calculate fee growth above
+    let (fee_growth_above_0_x_128, fee_growth_above_1_x_128) = if tick_current < tick_upper {
+        (
+            upper.fee_growth_outside_0_x_128,
+            upper.fee_growth_outside_1_x_128,
+        )
+    } else {
+        (
+            fee_growth_global_0_x_128.wrapping_sub(upper.fee_growth_outside_0_x_128),
+            fee_growth_global_1_x_128.wrapping_sub(upper.fee_growth_outside_1_x_128),
+        )
+    };
+
+    (
+        fee_growth_global_0_x_128
+            .wrapping_sub(fee_growth_below_0_x_128)
+            .wrapping_sub(fee_growth_above_0_x_128),
+        fee_growth_global_1_x_128
+            .wrapping_sub(fee_growth_below_1_x_128)
+            .wrapping_sub(fee_growth_above_1_x_128),
+    )
+}
+
+//Returns the seconds spent with the price in [tick_lower, tick_upper) as of `time`, the secondsInside of
+//UniswapV3Pool.snapshotCumulativesInside. The uint32 arithmetic is unchecked in Solidity and wraps here too, only the
+//difference of two snapshots taken less than 2**32 seconds apart is meaningful
+pub fn get_seconds_inside(
+    ticks: &HashMap<i32, Tick>,
+    tick_lower: i32,
+    tick_upper: i32,
+    tick_current: i32,
+    time: u32,
+) -> u32 {
+    let seconds_outside = |tick| ticks.get(&tick).map_or(0, |info| info.seconds_outside);
+    let lower = seconds_outside(tick_lower);
+    let upper = seconds_outside(tick_upper);
+    if tick_current < tick_lower {
+        lower.wrapping_sub(upper)
+    } else if tick_current < tick_upper {
+        time.wrapping_sub(lower).wrapping_sub(upper)
+    } else {
+        upper.wrapping_sub(lower)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        get_fee_growth_inside, get_seconds_inside, tick_spacing_to_max_liquidity_per_tick, Tick,
+    };
+    use crate::error::UniswapV3MathError;
+    use alloy::primitives::U256;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_cross_seconds_outside() {
+        //initialized below the current tick at u32::MAX - 4, so all the time until then counts as spent below it
+        let mut info = Tick {
+            seconds_outside: u32::MAX - 4,
+            ..Default::default()
+        };
+        //crossed after the timestamp wrapped
+        info.cross_seconds_outside(5);
+        assert_eq!(info.seconds_outside, 10);
+        //crossing back flips it again
+        info.cross_seconds_outside(25);
+        assert_eq!(info.seconds_outside, 15);
+    }
+
+    #[test]
+    fn test_get_seconds_inside_across_timestamp_overflow() {
+        let tick_with_seconds_outside = |seconds_outside| Tick {
+            liquidity_gross: 1,
+            liquidity_net: 0,
+            seconds_outside,
+            initialized: true,
+            ..Default::default()
+        };
+        //-60 initialized below the current tick at u32::MAX - 9, 60 above it
+        let mut ticks = HashMap::new();
+        ticks.insert(-60, tick_with_seconds_outside(u32::MAX - 9));
+        ticks.insert(60, tick_with_seconds_outside(0));
+
+        //in range since u32::MAX - 9, 20 seconds later after the wrap
+        assert_eq!(get_seconds_inside(&ticks, -60, 60, 0, 10), 20);
+        //at the boundary
+        assert_eq!(get_seconds_inside(&ticks, -60, 60, 0, 0), 10);
+        assert_eq!(get_seconds_inside(&ticks, -60, 60, 0, u32::MAX - 9), 0);
+
+        //the price leaves the range upwards at 15, the time spent inside stops there
+        if let Some(upper) = ticks.get_mut(&60) {
+            upper.cross_seconds_outside(15);
+        }
+        assert_eq!(get_seconds_inside(&ticks, -60, 60, 60, 15), 25);
+        assert_eq!(get_seconds_inside(&ticks, -60, 60, 60, 1000), 25);
+
+        //and moves below it at 40, across the range without spending time in it
+        for tick in [60, -60] {
+            if let Some(info) = ticks.get_mut(&tick) {
+                info.cross_seconds_outside(40);
+            }
+        }
+        assert_eq!(get_seconds_inside(&ticks, -60, 60, -61, 1000), 25);
+    }
+
+    #[test]
+    fn test_get_fee_growth_inside() {
+        //returns all for two uninitialized ticks if tick is inside
+        let ticks: HashMap<i32, Tick> = HashMap::new();
+        let (fee_growth_inside_0, fee_growth_inside_1) =
+            get_fee_growth_inside(&ticks, -2, 2, 0, U256::from(15), U256::from(15));
+        assert_eq!(fee_growth_inside_0, U256::from(15));
+        assert_eq!(fee_growth_inside_1, U256::from(15));
+
+        //returns 0 for two uninitialized ticks if tick is above
+        let (fee_growth_inside_0, fee_growth_inside_1) =
+            get_fee_growth_inside(&ticks, -2, 2, 4, U256::from(15), U256::from(15));
+        assert_eq!(fee_growth_inside_0, U256::ZERO);
+        assert_eq!(fee_growth_inside_1, U256::ZERO);
+
+        //returns 0 for two uninitialized ticks if tick is below
+        let (fee_growth_inside_0, fee_growth_inside_1) =
+            get_fee_growth_inside(&ticks, -2, 2, -4, U256::from(15), U256::from(15));
+        assert_eq!(fee_growth_inside_0, U256::ZERO);
+        assert_eq!(fee_growth_inside_1, U256::ZERO);
+
+        //subtracts upper tick if below
+        let mut ticks: HashMap<i32, Tick> = HashMap::new();
+        ticks.insert(
+            2,
+            tick_with_fee_growth_outside(U256::from(2), U256::from(3)),
+        );
+        let (fee_growth_inside_0, fee_growth_inside_1) =
+            get_fee_growth_inside(&ticks, -2, 2, 0, U256::from(15), U256::from(15));
+        assert_eq!(fee_growth_inside_0, U256::from(13));
+        assert_eq!(fee_growth_inside_1, U256::from(12));
+
+        //subtracts lower tick if above
+        let mut ticks: HashMap<i32, Tick> = HashMap::new();
+        ticks.insert(
+            -2,
+            tick_with_fee_growth_outside(U256::from(2), U256::from(3)),
+        );
+        let (fee_growth_inside_0, fee_growth_inside_1) =
+            get_fee_growth_inside(&ticks, -2, 2, 0, U256::from(15), U256::from(15));
+        assert_eq!(fee_growth_inside_0, U256::from(13));
+        assert_eq!(fee_growth_inside_1, U256::from(12));
+
+        //subtracts upper and lower tick if inside
+        ticks.insert(
+            2,
+            tick_with_fee_growth_outside(U256::from(4), U256::from(1)),
+        );
+        let (fee_growth_inside_0, fee_growth_inside_1) =
+            get_fee_growth_inside(&ticks, -2, 2, 0, U256::from(15), U256::from(15));
+        assert_eq!(fee_growth_inside_0, U256::from(9));
+        assert_eq!(fee_growth_inside_1, U256::from(11));
+    }
+
+    // a tick initialized with the given fee growth outside
+    fn tick_with_fee_growth_outside(
+        fee_growth_outside_0_x_128: U256,
+        fee_growth_outside_1_x_128: U256,
+    ) -> Tick {
+        Tick {
+            fee_growth_outside_0_x_128,
+            fee_growth_outside_1_x_128,
+            liquidity_gross: 1,
+            liquidity_net: 1,
+            initialized: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_get_fee_growth_inside_overflow() {
+        //works correctly with overflow on inside tick, the outside values of the lower tick are above the global
+        let mut ticks: HashMap<i32, Tick> = HashMap::new();
+        ticks.insert(
+            -2,
+            tick_with_fee_growth_outside(U256::MAX - U256::from(3), U256::MAX - U256::from(2)),
+        );
+        ticks.insert(
+            2,
+            tick_with_fee_growth_outside(U256::from(3), U256::from(5)),
+        );
+        let (fee_growth_inside_0, fee_growth_inside_1) =
+            get_fee_growth_inside(&ticks, -2, 2, 0, U256::from(15), U256::from(15));
+        assert_eq!(fee_growth_inside_0, U256::from(16));
+        assert_eq!(fee_growth_inside_1, U256::from(13));
+
+        //the global wrapped past U256::MAX after both ticks were crossed. The lower tick was crossed 10 before the
+        //wrap, the upper one 3 before it, so 7 accrued inside regardless of the global now being 5
+        let mut ticks: HashMap<i32, Tick> = HashMap::new();
+        ticks.insert(
+            -2,
+            tick_with_fee_growth_outside(U256::MAX - U256::from(9), U256::MAX - U256::from(9)),
+        );
+        ticks.insert(
+            2,
+            tick_with_fee_growth_outside(U256::MAX - U256::from(2), U256::MAX - U256::from(2)),
+        );
+        let (fee_growth_inside_0, fee_growth_inside_1) =
+            get_fee_growth_inside(&ticks, -2, 2, 4, U256::from(5), U256::from(5));
+        assert_eq!(fee_growth_inside_0, U256::from(7));
+        assert_eq!(fee_growth_inside_1, U256::from(7));
+    }
+
+    #[test]
+    fn test_tick_spacing_to_max_liquidity_per_tick() {
+        //the values of the Uniswap Tick tests for the fee tier spacings
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(10),
+            1917569901783203986719870431555990
+        );
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(60),
+            11505743598341114571880798222544994
+        );
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(200),
+            38350317471085141830651933667504588
+        );
+        //the full tick range, the ticks at MIN_TICK and MAX_TICK are usable
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(1),
+            u128::MAX / 1774545
+        );
+        assert_eq!(
+            tick_spacing_to_max_liquidity_per_tick(887272),
+            u128::MAX / 3
+        );
+    }
+
+    #[test]
+    fn test_tick_new() -> eyre::Result<()> {
+        let tick = Tick::new(1_000, -400)?;
+        assert_eq!(tick.liquidity_gross, 1_000);
+        assert_eq!(tick.liquidity_net, -400);
+        assert!(tick.initialized);
+        assert_eq!(tick.fee_growth_outside_0_x_128, U256::ZERO);
+        assert_eq!(tick.fee_growth_outside_1_x_128, U256::ZERO);
+
+        // a tick referenced by a single position as its lower or upper tick
+        assert!(Tick::new(1_000, 1_000)?.initialized);
+        assert!(Tick::new(u128::MAX, i128::MIN + 1)?.initialized);
+        assert_eq!(Tick::new(0, 0)?, Tick::default());
+
+        assert!(matches!(
+            Tick::new(1_000, 1_001),
+            Err(UniswapV3MathError::InvalidTick {
+                liquidity_gross: 1_000,
+                liquidity_net: 1_001
+            })
+        ));
+        assert!(matches!(
+            Tick::new(0, -1),
+            Err(UniswapV3MathError::InvalidTick { .. })
+        ));
+        assert!(matches!(
+            Tick::new(1_000, i128::MIN),
+            Err(UniswapV3MathError::InvalidTick { .. })
+        ));
+        Ok(())
+    }
+}
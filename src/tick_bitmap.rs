@@ -29,6 +29,42 @@ pub fn flip_tick(
     Ok(())
 }
 
+//Marks a given tick as initialized, leaving the bitmap unchanged if it already is. Unlike flip_tick this is
+//idempotent, for building a bitmap from tick data rather than tracking liquidity transitions
+pub fn set_tick(
+    tick_bitmap: &mut HashMap<i16, U256>,
+    tick: i32,
+    tick_spacing: i32,
+) -> Result<(), UniswapV3MathError> {
+    if (tick % tick_spacing) != 0 {
+        return Err(UniswapV3MathError::TickSpacingError);
+    }
+
+    let (word_pos, bit_pos) = position(tick / tick_spacing);
+    let mask = U256_1 << bit_pos;
+    let word = tick_bitmap.entry(word_pos).or_default();
+    *word |= mask;
+    Ok(())
+}
+
+//Marks a given tick as uninitialized, leaving the bitmap unchanged if it already is
+pub fn unset_tick(
+    tick_bitmap: &mut HashMap<i16, U256>,
+    tick: i32,
+    tick_spacing: i32,
+) -> Result<(), UniswapV3MathError> {
+    if (tick % tick_spacing) != 0 {
+        return Err(UniswapV3MathError::TickSpacingError);
+    }
+
+    let (word_pos, bit_pos) = position(tick / tick_spacing);
+    let mask = U256_1 << bit_pos;
+    if let Some(word) = tick_bitmap.get_mut(&word_pos) {
+        *word &= !mask;
+    }
+    Ok(())
+}
+
 //Returns the next initialized tick contained in the same word (or adjacent word) as the tick that is either
 //to the left (less than or equal to) or right (greater than) of the given tick
 pub fn next_initialized_tick_within_one_word(
@@ -37,6 +73,25 @@ pub fn next_initialized_tick_within_one_word(
     tick_spacing: i32,
     lte: bool,
 ) -> Result<(i32, bool), UniswapV3MathError> {
+    next_initialized_tick_within_one_word_with(
+        |word_pos| Ok(tick_bitmap.get(&word_pos).copied().unwrap_or_default()),
+        tick,
+        tick_spacing,
+        lte,
+    )
+}
+
+//Same as next_initialized_tick_within_one_word, reading the word from `word_at`, which returns zero for a word
+//without initialized ticks. This lets the search run over any storage of the bitmap
+pub fn next_initialized_tick_within_one_word_with<F>(
+    word_at: F,
+    tick: i32,
+    tick_spacing: i32,
+    lte: bool,
+) -> Result<(i32, bool), UniswapV3MathError>
+where
+    F: FnOnce(i16) -> Result<U256, UniswapV3MathError>,
+{
     let compressed = if tick < 0 && tick % tick_spacing != 0 {
         (tick / tick_spacing) - 1
     } else {
@@ -48,7 +103,7 @@ pub fn next_initialized_tick_within_one_word(
 
         let mask = (U256_1 << bit_pos) - U256_1 + (U256_1 << bit_pos);
 
-        let masked = *tick_bitmap.get(&word_pos).unwrap_or(&U256::ZERO) & mask;
+        let masked = word_at(word_pos)? & mask;
 
         let initialized = !masked.is_zero();
 
@@ -68,7 +123,7 @@ pub fn next_initialized_tick_within_one_word(
 
         let mask = !((U256_1 << bit_pos) - U256_1);
 
-        let masked = *tick_bitmap.get(&word_pos).unwrap_or(&U256::ZERO) & mask;
+        let masked = word_at(word_pos)? & mask;
 
         let initialized = !masked.is_zero();
 
@@ -87,6 +142,38 @@ pub fn next_initialized_tick_within_one_word(
     }
 }
 
+//Returns all the initialized ticks in the word the search of next_initialized_tick_within_one_word looks at, in search
+//order: the ticks at or below `tick` from the nearest down when lte, else the ticks above `tick` from the nearest up.
+//A caller walking a word gets every tick it would find with one call per tick. Empty if the word has none
+pub fn initialized_ticks_within_one_word(
+    tick_bitmap: &HashMap<i16, U256>,
+    tick: i32,
+    tick_spacing: i32,
+    lte: bool,
+) -> Vec<i32> {
+    let compressed = if tick < 0 && tick % tick_spacing != 0 {
+        (tick / tick_spacing) - 1
+    } else {
+        tick / tick_spacing
+    };
+    let (word_pos, bit_pos) = position(if lte { compressed } else { compressed + 1 });
+    let word = tick_bitmap.get(&word_pos).copied().unwrap_or_default();
+    let to_tick = |bit_pos: usize| (word_pos as i32 * 256 + bit_pos as i32) * tick_spacing;
+
+    if lte {
+        (0..=bit_pos as usize)
+            .rev()
+            .filter(|bit_pos| word.bit(*bit_pos))
+            .map(to_tick)
+            .collect()
+    } else {
+        (bit_pos as usize..256)
+            .filter(|bit_pos| word.bit(*bit_pos))
+            .map(to_tick)
+            .collect()
+    }
+}
+
 //Returns next and initialized. This function calls the node to get the word at the word_pos.
 //current_word is the current word in the TickBitmap of the pool based on `tick`. TickBitmap[word_pos] = current_word
 //Where word_pos is the 256 bit offset of the ticks word_pos.. word_pos := tick >> 8
@@ -187,6 +274,20 @@ pub async fn next_initialized_tick_within_one_word_from_provider<P: Provider>(
     }
 }
 
+//Returns the number of initialized ticks, i.e. the number of set bits across all words
+pub fn initialized_tick_count(tick_bitmap: &HashMap<i16, U256>) -> u32 {
+    tick_bitmap
+        .values()
+        .map(|word| word.count_ones() as u32)
+        .sum()
+}
+
+//Returns the number of non-zero words, the words that have to be kept in storage. Words flipped back to zero
+//may still be present in the map and are not counted
+pub fn word_count(tick_bitmap: &HashMap<i16, U256>) -> usize {
+    tick_bitmap.values().filter(|word| !word.is_zero()).count()
+}
+
 //Computes the position in the mapping where the initialized bit for a tick lives
 pub fn position(tick: i32) -> (i16, u8) {
     ((tick >> 8) as i16, (tick % 256) as u8)
@@ -198,7 +299,11 @@ mod test {
 
     use alloy::primitives::U256;
 
-    use super::{flip_tick, next_initialized_tick_within_one_word};
+    use super::{
+        flip_tick, initialized_tick_count, initialized_ticks_within_one_word,
+        next_initialized_tick_within_one_word, position, set_tick, unset_tick, word_count,
+    };
+    use crate::error::UniswapV3MathError;
 
     pub fn init_test_ticks() -> eyre::Result<HashMap<i16, U256>> {
         let test_ticks = vec![-200, -55, -4, 70, 78, 84, 139, 240, 535];
@@ -352,6 +457,45 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    pub fn test_next_initialized_tick_within_one_word_large_tick_spacing() -> eyre::Result<()> {
+        //a word covers 256 compressed ticks whatever the spacing, here 131072 ticks
+        let tick_spacing = 512;
+        let mut tick_bitmap: HashMap<i16, U256> = HashMap::new();
+        for tick in [-886784, -1024, 0, 512, 131072, 886784] {
+            flip_tick(&mut tick_bitmap, tick, tick_spacing)?;
+        }
+        assert_eq!(position(131072 / tick_spacing), (1, 0));
+        assert_eq!(position(-1024 / tick_spacing), (-1, 254));
+        assert_eq!(position(-886784 / tick_spacing), (-7, 60));
+        assert_eq!(position(886784 / tick_spacing), (6, 196));
+        assert!(matches!(
+            flip_tick(&mut tick_bitmap, 256, tick_spacing),
+            Err(UniswapV3MathError::TickSpacingError)
+        ));
+
+        for (tick, lte, expected) in [
+            //to the right
+            (0, false, (512, true)),
+            (700, true, (512, true)),
+            (512, false, (130560, false)),
+            (130560, false, (131072, true)),
+            (886000, false, (886784, true)),
+            //to the left
+            (0, true, (0, true)),
+            (-1, true, (-1024, true)),
+            (-1025, true, (-131072, false)),
+            (-886000, true, (-886784, true)),
+        ] {
+            assert_eq!(
+                next_initialized_tick_within_one_word(&tick_bitmap, tick, tick_spacing, lte)?,
+                expected,
+                "from {tick} lte {lte}"
+            );
+        }
+        Ok(())
+    }
+
     #[test]
     pub fn test_initialized() -> eyre::Result<()> {
         //is false at first
@@ -429,4 +573,112 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    pub fn test_set_and_unset_tick() -> eyre::Result<()> {
+        //setting twice leaves the tick initialized, where flipping twice clears it
+        let mut tick_bitmap = HashMap::new();
+        set_tick(&mut tick_bitmap, -230, 1)?;
+        set_tick(&mut tick_bitmap, -230, 1)?;
+        assert!(initialized(-230, &tick_bitmap)?);
+        assert!(!initialized(-229, &tick_bitmap)?);
+
+        let mut flipped_bitmap = HashMap::new();
+        flip_tick(&mut flipped_bitmap, -230, 1)?;
+        flip_tick(&mut flipped_bitmap, -230, 1)?;
+        assert!(!initialized(-230, &flipped_bitmap)?);
+
+        //unsetting twice leaves the tick uninitialized and only clears itself
+        set_tick(&mut tick_bitmap, -229, 1)?;
+        unset_tick(&mut tick_bitmap, -230, 1)?;
+        unset_tick(&mut tick_bitmap, -230, 1)?;
+        assert!(!initialized(-230, &tick_bitmap)?);
+        assert!(initialized(-229, &tick_bitmap)?);
+
+        //unsetting a tick in a missing word does not add the word
+        unset_tick(&mut tick_bitmap, 500, 1)?;
+        assert_eq!(tick_bitmap.len(), 1);
+
+        assert!(matches!(
+            set_tick(&mut tick_bitmap, 61, 60),
+            Err(UniswapV3MathError::TickSpacingError)
+        ));
+        assert!(matches!(
+            unset_tick(&mut tick_bitmap, 61, 60),
+            Err(UniswapV3MathError::TickSpacingError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_initialized_ticks_within_one_word() -> eyre::Result<()> {
+        let tick_bitmap = init_test_ticks()?;
+        //the rest of the word in search order, 535 is in the next word
+        assert_eq!(
+            initialized_ticks_within_one_word(&tick_bitmap, 78, 1, false),
+            vec![84, 139, 240]
+        );
+        assert_eq!(
+            initialized_ticks_within_one_word(&tick_bitmap, 78, 1, true),
+            vec![78, 70]
+        );
+        assert_eq!(
+            initialized_ticks_within_one_word(&tick_bitmap, -55, 1, false),
+            vec![-4]
+        );
+        assert_eq!(
+            initialized_ticks_within_one_word(&tick_bitmap, -55, 1, true),
+            vec![-55, -200]
+        );
+        assert_eq!(
+            initialized_ticks_within_one_word(&tick_bitmap, 300, 1, true),
+            Vec::<i32>::new()
+        );
+
+        //every tick of the list is what the one tick search finds walking the word
+        let mut tick = 78;
+        for expected in initialized_ticks_within_one_word(&tick_bitmap, 78, 1, false) {
+            let (next, initialized) =
+                next_initialized_tick_within_one_word(&tick_bitmap, tick, 1, false)?;
+            assert_eq!((next, initialized), (expected, true));
+            tick = next;
+        }
+
+        //ticks are scaled by the tick spacing
+        let mut tick_bitmap = HashMap::new();
+        for tick in [-120, 60, 600] {
+            flip_tick(&mut tick_bitmap, tick, 60)?;
+        }
+        assert_eq!(
+            initialized_ticks_within_one_word(&tick_bitmap, 0, 60, false),
+            vec![60, 600]
+        );
+        assert_eq!(
+            initialized_ticks_within_one_word(&tick_bitmap, -1, 60, true),
+            vec![-120]
+        );
+        Ok(())
+    }
+
+    #[test]
+    pub fn test_initialized_tick_count_and_word_count() -> eyre::Result<()> {
+        let mut tick_bitmap = init_test_ticks()?;
+        //-200, -55 and -4 are in word -1, 70 to 240 in word 0 and 535 in word 2
+        assert_eq!(initialized_tick_count(&tick_bitmap), 9);
+        assert_eq!(word_count(&tick_bitmap), 3);
+
+        flip_tick(&mut tick_bitmap, -200, 1)?;
+        assert_eq!(initialized_tick_count(&tick_bitmap), 8);
+        assert_eq!(word_count(&tick_bitmap), 3);
+
+        //the emptied word stays in the map but is not counted
+        flip_tick(&mut tick_bitmap, 535, 1)?;
+        assert_eq!(initialized_tick_count(&tick_bitmap), 7);
+        assert_eq!(word_count(&tick_bitmap), 2);
+        assert_eq!(tick_bitmap.len(), 3);
+
+        assert_eq!(initialized_tick_count(&HashMap::new()), 0);
+        assert_eq!(word_count(&HashMap::new()), 0);
+        Ok(())
+    }
 }
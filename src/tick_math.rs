@@ -29,6 +29,30 @@ pub const TICK_HIGH: I256 = I256::from_raw(U256::from_limbs([
     0,
 ]));
 
+// 2^128 / sqrt(1.0001)^(2^i), the ratio multiplier for bit i of the absolute tick in TickMath.getSqrtRatioAtTick
+pub const SQRT_RATIO_MULTIPLIERS: [U256; 20] = [
+    U256::from_limbs([12262481743371124737, 18445821805675392311, 0, 0]),
+    U256::from_limbs([6459403834229662010, 18444899583751176498, 0, 0]),
+    U256::from_limbs([17226890335427755468, 18443055278223354162, 0, 0]),
+    U256::from_limbs([2032852871939366096, 18439367220385604838, 0, 0]),
+    U256::from_limbs([14545316742740207172, 18431993317065449817, 0, 0]),
+    U256::from_limbs([5129152022828963008, 18417254355718160513, 0, 0]),
+    U256::from_limbs([4894419605888772193, 18387811781193591352, 0, 0]),
+    U256::from_limbs([1280255884321894483, 18329067761203520168, 0, 0]),
+    U256::from_limbs([15924666964335305636, 18212142134806087854, 0, 0]),
+    U256::from_limbs([8010504389359918676, 17980523815641551639, 0, 0]),
+    U256::from_limbs([10668036004952895731, 17526086738831147013, 0, 0]),
+    U256::from_limbs([4878133418470705625, 16651378430235024244, 0, 0]),
+    U256::from_limbs([9537173718739605541, 15030750278693429944, 0, 0]),
+    U256::from_limbs([9972618978014552549, 12247334978882834399, 0, 0]),
+    U256::from_limbs([10428997489610666743, 8131365268884726200, 0, 0]),
+    U256::from_limbs([9305304367709015974, 3584323654723342297, 0, 0]),
+    U256::from_limbs([14301143598189091785, 696457651847595233, 0, 0]),
+    U256::from_limbs([7393154844743099908, 26294789957452057, 0, 0]),
+    U256::from_limbs([2209338891292245656, 37481735321082, 0, 0]),
+    U256::from_limbs([10518117631919034274, 76158723, 0, 0]),
+];
+
 pub fn get_sqrt_ratio_at_tick(tick: i32) -> Result<U256, UniswapV3MathError> {
     let abs_tick = if tick < 0 {
         U256::from(tick.neg())
@@ -37,75 +61,71 @@ pub fn get_sqrt_ratio_at_tick(tick: i32) -> Result<U256, UniswapV3MathError> {
     };
 
     if abs_tick > U256_MAX_TICK {
-        return Err(UniswapV3MathError::T);
+        return Err(UniswapV3MathError::TickOutOfRange(tick));
     }
 
     let mut ratio = if abs_tick & (U256_1) != U256::ZERO {
-        U256::from_limbs([12262481743371124737, 18445821805675392311, 0, 0])
+        SQRT_RATIO_MULTIPLIERS[0]
     } else {
         U256::from_limbs([0, 0, 1, 0])
     };
 
     if !(abs_tick & U256_2).is_zero() {
-        ratio = (ratio * U256::from_limbs([6459403834229662010, 18444899583751176498, 0, 0])) >> 128
+        ratio = (ratio * SQRT_RATIO_MULTIPLIERS[1]) >> 128
     }
     if !(abs_tick & U256_4).is_zero() {
-        ratio =
-            (ratio * U256::from_limbs([17226890335427755468, 18443055278223354162, 0, 0])) >> 128
+        ratio = (ratio * SQRT_RATIO_MULTIPLIERS[2]) >> 128
     }
     if !(abs_tick & U256_8).is_zero() {
-        ratio = (ratio * U256::from_limbs([2032852871939366096, 18439367220385604838, 0, 0])) >> 128
+        ratio = (ratio * SQRT_RATIO_MULTIPLIERS[3]) >> 128
     }
     if !(abs_tick & U256_16).is_zero() {
-        ratio =
-            (ratio * U256::from_limbs([14545316742740207172, 18431993317065449817, 0, 0])) >> 128
+        ratio = (ratio * SQRT_RATIO_MULTIPLIERS[4]) >> 128
     }
     if !(abs_tick & U256_32).is_zero() {
-        ratio = (ratio * U256::from_limbs([5129152022828963008, 18417254355718160513, 0, 0])) >> 128
+        ratio = (ratio * SQRT_RATIO_MULTIPLIERS[5]) >> 128
     }
     if !(abs_tick & U256_64).is_zero() {
-        ratio = (ratio * U256::from_limbs([4894419605888772193, 18387811781193591352, 0, 0])) >> 128
+        ratio = (ratio * SQRT_RATIO_MULTIPLIERS[6]) >> 128
     }
     if !(abs_tick & U256_128).is_zero() {
-        ratio = (ratio * U256::from_limbs([1280255884321894483, 18329067761203520168, 0, 0])) >> 128
+        ratio = (ratio * SQRT_RATIO_MULTIPLIERS[7]) >> 128
     }
     if !(abs_tick & U256_256).is_zero() {
-        ratio =
-            (ratio * U256::from_limbs([15924666964335305636, 18212142134806087854, 0, 0])) >> 128
+        ratio = (ratio * SQRT_RATIO_MULTIPLIERS[8]) >> 128
     }
     if !(abs_tick & U256_512).is_zero() {
-        ratio = (ratio * U256::from_limbs([8010504389359918676, 17980523815641551639, 0, 0])) >> 128
+        ratio = (ratio * SQRT_RATIO_MULTIPLIERS[9]) >> 128
     }
     if !(abs_tick & U256_1024).is_zero() {
-        ratio =
-            (ratio * U256::from_limbs([10668036004952895731, 17526086738831147013, 0, 0])) >> 128
+        ratio = (ratio * SQRT_RATIO_MULTIPLIERS[10]) >> 128
     }
     if !(abs_tick & U256_2048).is_zero() {
-        ratio = (ratio * U256::from_limbs([4878133418470705625, 16651378430235024244, 0, 0])) >> 128
+        ratio = (ratio * SQRT_RATIO_MULTIPLIERS[11]) >> 128
     }
     if !(abs_tick & U256_4096).is_zero() {
-        ratio = (ratio * U256::from_limbs([9537173718739605541, 15030750278693429944, 0, 0])) >> 128
+        ratio = (ratio * SQRT_RATIO_MULTIPLIERS[12]) >> 128
     }
     if !(abs_tick & U256_8192).is_zero() {
-        ratio = (ratio * U256::from_limbs([9972618978014552549, 12247334978882834399, 0, 0])) >> 128
+        ratio = (ratio * SQRT_RATIO_MULTIPLIERS[13]) >> 128
     }
     if !(abs_tick & U256_16384).is_zero() {
-        ratio = (ratio * U256::from_limbs([10428997489610666743, 8131365268884726200, 0, 0])) >> 128
+        ratio = (ratio * SQRT_RATIO_MULTIPLIERS[14]) >> 128
     }
     if !(abs_tick & U256_32768).is_zero() {
-        ratio = (ratio * U256::from_limbs([9305304367709015974, 3584323654723342297, 0, 0])) >> 128
+        ratio = (ratio * SQRT_RATIO_MULTIPLIERS[15]) >> 128
     }
     if !(abs_tick & U256_65536).is_zero() {
-        ratio = (ratio * U256::from_limbs([14301143598189091785, 696457651847595233, 0, 0])) >> 128
+        ratio = (ratio * SQRT_RATIO_MULTIPLIERS[16]) >> 128
     }
     if !(abs_tick & U256_131072).is_zero() {
-        ratio = (ratio * U256::from_limbs([7393154844743099908, 26294789957452057, 0, 0])) >> 128
+        ratio = (ratio * SQRT_RATIO_MULTIPLIERS[17]) >> 128
     }
     if !(abs_tick & U256_262144).is_zero() {
-        ratio = (ratio * U256::from_limbs([2209338891292245656, 37481735321082, 0, 0])) >> 128
+        ratio = (ratio * SQRT_RATIO_MULTIPLIERS[18]) >> 128
     }
     if !(abs_tick & U256_524288).is_zero() {
-        ratio = (ratio * U256::from_limbs([10518117631919034274, 76158723, 0, 0])) >> 128
+        ratio = (ratio * SQRT_RATIO_MULTIPLIERS[19]) >> 128
     }
 
     if tick > 0 {
@@ -120,6 +140,105 @@ pub fn get_sqrt_ratio_at_tick(tick: i32) -> Result<U256, UniswapV3MathError> {
         })
 }
 
+//Computes get_sqrt_ratio_at_tick for many ticks, e.g. every tick of a depth chart. The ratio is a product over the bits
+//of |tick| taken from the lowest bit up, so the partial product of the low bits is computed once and shared by all
+//ticks with the same low bits. The results are the same as calling get_sqrt_ratio_at_tick for each tick
+pub fn sqrt_ratios_at_ticks(ticks: &[i32]) -> Result<Vec<U256>, UniswapV3MathError> {
+    const LOW_BITS: usize = 10;
+    let mut low_bit_ratios: Vec<Option<U256>> = vec![None; 1 << LOW_BITS];
+
+    ticks
+        .iter()
+        .map(|&tick| {
+            let abs_tick = tick.unsigned_abs() as usize;
+            if abs_tick > MAX_TICK as usize {
+                return Err(UniswapV3MathError::TickOutOfRange(tick));
+            }
+
+            let low_bits = abs_tick & ((1 << LOW_BITS) - 1);
+            let mut ratio = *low_bit_ratios[low_bits].get_or_insert_with(|| {
+                let mut ratio = if low_bits & 1 != 0 {
+                    SQRT_RATIO_MULTIPLIERS[0]
+                } else {
+                    U256::from_limbs([0, 0, 1, 0])
+                };
+                for (bit, &multiplier) in SQRT_RATIO_MULTIPLIERS
+                    .iter()
+                    .enumerate()
+                    .take(LOW_BITS)
+                    .skip(1)
+                {
+                    if low_bits & (1 << bit) != 0 {
+                        ratio = (ratio * multiplier) >> 128;
+                    }
+                }
+                ratio
+            });
+            for (bit, &multiplier) in SQRT_RATIO_MULTIPLIERS.iter().enumerate().skip(LOW_BITS) {
+                if abs_tick & (1 << bit) != 0 {
+                    ratio = (ratio * multiplier) >> 128;
+                }
+            }
+            Ok(sqrt_ratio_from_product(ratio, tick))
+        })
+        .collect()
+}
+
+//Computes get_sqrt_ratio_at_tick for the 256 ticks of the tick bitmap word `word_pos` with `tick_spacing`, as
+//(tick, sqrt_ratio) in ascending order of tick, skipping the ticks of the word outside [MIN_TICK, MAX_TICK]. The
+//partial products of the lowest 8 bits of |tick| are built once for the word, each from the one without its highest
+//bit, so a tick only multiplies in its higher bits. The results are the same as calling get_sqrt_ratio_at_tick
+pub fn sqrt_ratios_in_word(
+    word_pos: i16,
+    tick_spacing: i32,
+) -> Result<Vec<(i32, U256)>, UniswapV3MathError> {
+    if tick_spacing <= 0 {
+        return Err(UniswapV3MathError::TickSpacingError);
+    }
+
+    let mut low_byte_ratios = [U256::ZERO; 256];
+    low_byte_ratios[0] = U256::from_limbs([0, 0, 1, 0]);
+    low_byte_ratios[1] = SQRT_RATIO_MULTIPLIERS[0];
+    for low_byte in 2..256 {
+        let highest_bit = 7 - (low_byte as u8).leading_zeros() as usize;
+        low_byte_ratios[low_byte] = (low_byte_ratios[low_byte & !(1 << highest_bit)]
+            * SQRT_RATIO_MULTIPLIERS[highest_bit])
+            >> 128;
+    }
+
+    let mut ratios = Vec::with_capacity(256);
+    for bit_pos in 0..256_i64 {
+        let tick = (word_pos as i64 * 256 + bit_pos) * tick_spacing as i64;
+        if tick < MIN_TICK as i64 || tick > MAX_TICK as i64 {
+            continue;
+        }
+        let tick = tick as i32;
+        let abs_tick = tick.unsigned_abs() as usize;
+        let mut ratio = low_byte_ratios[abs_tick & 0xFF];
+        for (bit, &multiplier) in SQRT_RATIO_MULTIPLIERS.iter().enumerate().skip(8) {
+            if abs_tick & (1 << bit) != 0 {
+                ratio = (ratio * multiplier) >> 128;
+            }
+        }
+        ratios.push((tick, sqrt_ratio_from_product(ratio, tick)));
+    }
+    Ok(ratios)
+}
+
+// the last steps of get_sqrt_ratio_at_tick from the Q128.128 product over the bits of |tick|: inverts it for positive
+// ticks and rounds up to a Q64.96
+fn sqrt_ratio_from_product(mut ratio: U256, tick: i32) -> U256 {
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+    (ratio >> 32)
+        + if (ratio.wrapping_rem(U256_1 << 32)).is_zero() {
+            U256::ZERO
+        } else {
+            U256_1
+        }
+}
+
 pub fn get_tick_at_sqrt_ratio(sqrt_price_x_96: U256) -> Result<i32, UniswapV3MathError> {
     if !(sqrt_price_x_96 >= MIN_SQRT_RATIO && sqrt_price_x_96 < MAX_SQRT_RATIO) {
         return Err(UniswapV3MathError::R);
@@ -215,6 +334,8 @@ pub fn get_tick_at_sqrt_ratio(sqrt_price_x_96: U256) -> Result<i32, UniswapV3Mat
 
     let tick_high = ((log_sqrt10001 + TICK_HIGH) >> 128_u8).low_i32();
 
+    // the log approximation brackets the result between tick_low and tick_high, checking tick_high against
+    // its exact sqrt ratio resolves prices sitting exactly on a tick to that tick
     let tick = if tick_low == tick_high {
         tick_low
     } else if get_sqrt_ratio_at_tick(tick_high)? <= sqrt_price_x_96 {
@@ -226,26 +347,286 @@ pub fn get_tick_at_sqrt_ratio(sqrt_price_x_96: U256) -> Result<i32, UniswapV3Mat
     Ok(tick)
 }
 
+// how price_to_tick picks a tick for a price that falls between two ticks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    // the tick whose price is closest to the target in ratio, ties go to the lower tick
+    Nearest,
+    // the greatest tick whose price is <= the target
+    Down,
+    // the smallest tick whose price is >= the target
+    Up,
+}
+
+//Returns the raw price of token0 in token1 at a tick, 1.0001^tick, computed from the exact sqrt ratio of the tick
+pub fn tick_to_price(tick: i32) -> Result<f64, UniswapV3MathError> {
+    Ok(sqrt_price_x_96_to_price(get_sqrt_ratio_at_tick(tick)?))
+}
+
+//Returns the tick for a raw price of token0 in token1, rounded as requested. Ticks are compared through
+//tick_to_price, so price_to_tick(tick_to_price(tick)?, rounding) is `tick` in every mode. Fails with
+//PriceOutOfRange if the price is not a positive finite number or no tick in the tick range satisfies the rounding, a
+//nearest tick always exists for a positive price
+pub fn price_to_tick(price: f64, rounding: Rounding) -> Result<i32, UniswapV3MathError> {
+    if !price.is_finite() || price <= 0.0 {
+        return Err(UniswapV3MathError::PriceOutOfRange(price));
+    }
+
+    // the log estimate is off by at most a tick, walk to the greatest tick at or below the price
+    let estimate = (price.ln() / 1.0001_f64.ln()).floor();
+    let mut tick = estimate.clamp(MIN_TICK as f64, MAX_TICK as f64) as i32;
+    while tick > MIN_TICK && tick_to_price(tick)? > price {
+        tick -= 1;
+    }
+    while tick < MAX_TICK && tick_to_price(tick + 1)? <= price {
+        tick += 1;
+    }
+    let price_lower = tick_to_price(tick)?;
+    // None below the price of MIN_TICK
+    let lower = (price_lower <= price).then_some(tick);
+    // None above the price of MAX_TICK
+    let upper = match lower {
+        None => Some(MIN_TICK),
+        Some(tick) if price_lower == price => Some(tick),
+        Some(tick) if tick < MAX_TICK => Some(tick + 1),
+        Some(_) => None,
+    };
+
+    let tick = match (rounding, lower, upper) {
+        (Rounding::Down, lower, _) => lower,
+        (Rounding::Up, _, upper) => upper,
+        (Rounding::Nearest, Some(lower), Some(upper)) => {
+            if price / tick_to_price(lower)? <= tick_to_price(upper)? / price {
+                Some(lower)
+            } else {
+                Some(upper)
+            }
+        }
+        (Rounding::Nearest, lower, upper) => lower.or(upper),
+    };
+    tick.ok_or(UniswapV3MathError::PriceOutOfRange(price))
+}
+
+// the raw price of token0 in token1 at a sqrt price in Q64.96, sqrt_price^2 as a float
+pub(crate) fn sqrt_price_x_96_to_price(sqrt_price_x_96: U256) -> f64 {
+    let sqrt_price = sqrt_price_x_96_to_f64(sqrt_price_x_96);
+    sqrt_price * sqrt_price
+}
+
+// converts a sqrt price in Q64.96 to a float, keeping the 64 most significant bits
+fn sqrt_price_x_96_to_f64(sqrt_price_x_96: U256) -> f64 {
+    let bits = sqrt_price_x_96.bit_len();
+    if bits <= 64 {
+        return sqrt_price_x_96.as_limbs()[0] as f64 / 2_f64.powi(96);
+    }
+    let shift = bits - 64;
+    (sqrt_price_x_96 >> shift).as_limbs()[0] as f64 * 2_f64.powi(shift as i32 - 96)
+}
+
+// a small least recently used cache of get_tick_at_sqrt_ratio results keyed by the sqrt price, for tooling that
+// runs many simulations ending at the same prices. Entries are kept in recency order and looked up linearly, so the
+// capacity is meant to be small
+#[derive(Debug, Clone, Default)]
+pub struct TickCache {
+    capacity: usize,
+    // (sqrt_price_x_96, tick), least recently used first
+    entries: Vec<(U256, i32)>,
+    hits: u64,
+    misses: u64,
+}
+
+impl TickCache {
+    pub fn new(capacity: usize) -> Self {
+        TickCache {
+            capacity,
+            entries: Vec::with_capacity(capacity),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // returns (hits, misses)
+    pub fn stats(&self) -> (u64, u64) {
+        (self.hits, self.misses)
+    }
+}
+
+//get_tick_at_sqrt_ratio through an optional cache. Errors are not cached
+pub fn get_tick_at_sqrt_ratio_cached(
+    sqrt_price_x_96: U256,
+    cache: Option<&mut TickCache>,
+) -> Result<i32, UniswapV3MathError> {
+    let Some(cache) = cache else {
+        return get_tick_at_sqrt_ratio(sqrt_price_x_96);
+    };
+
+    if let Some(index) = cache
+        .entries
+        .iter()
+        .position(|(sqrt_price, _)| *sqrt_price == sqrt_price_x_96)
+    {
+        let entry = cache.entries.remove(index);
+        cache.entries.push(entry);
+        cache.hits += 1;
+        return Ok(entry.1);
+    }
+
+    let tick = get_tick_at_sqrt_ratio(sqrt_price_x_96)?;
+    cache.misses += 1;
+    if cache.capacity > 0 {
+        if cache.entries.len() >= cache.capacity {
+            cache.entries.remove(0);
+        }
+        cache.entries.push((sqrt_price_x_96, tick));
+    }
+    Ok(tick)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use proptest::prelude::*;
     use std::{ops::Sub, str::FromStr};
 
+    #[test]
+    fn test_tick_to_price() -> eyre::Result<()> {
+        assert_eq!(tick_to_price(0)?, 1.0);
+        assert!((tick_to_price(1)? - 1.0001).abs() < 1e-12);
+        assert!((tick_to_price(-1)? - 1.0 / 1.0001).abs() < 1e-12);
+        assert!((tick_to_price(100_000)? / 1.0001_f64.powi(100_000) - 1.0).abs() < 1e-9);
+        assert!(tick_to_price(MIN_TICK)? > 0.0);
+        assert!(matches!(
+            tick_to_price(MAX_TICK + 1),
+            Err(UniswapV3MathError::TickOutOfRange(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_price_to_tick() -> eyre::Result<()> {
+        for tick in [-100, 100] {
+            let lower = tick_to_price(tick)?;
+            let upper = tick_to_price(tick + 1)?;
+            //a quarter of the way from the price of tick to the next one
+            let price = lower + (upper - lower) * 0.25;
+            assert_eq!(price_to_tick(price, Rounding::Down)?, tick);
+            assert_eq!(price_to_tick(price, Rounding::Up)?, tick + 1);
+            assert_eq!(price_to_tick(price, Rounding::Nearest)?, tick);
+            //three quarters of the way rounds to the next tick
+            let price = lower + (upper - lower) * 0.75;
+            assert_eq!(price_to_tick(price, Rounding::Down)?, tick);
+            assert_eq!(price_to_tick(price, Rounding::Up)?, tick + 1);
+            assert_eq!(price_to_tick(price, Rounding::Nearest)?, tick + 1);
+        }
+
+        //the price of a tick is that tick in every mode
+        for tick in [MIN_TICK, -887000, -1, 0, 1, 200_000, MAX_TICK] {
+            let price = tick_to_price(tick)?;
+            for rounding in [Rounding::Nearest, Rounding::Down, Rounding::Up] {
+                assert_eq!(price_to_tick(price, rounding)?, tick);
+            }
+        }
+
+        //below the price of MIN_TICK and above the price of MAX_TICK only one side exists
+        let below = tick_to_price(MIN_TICK)? / 2.0;
+        assert!(matches!(
+            price_to_tick(below, Rounding::Down),
+            Err(UniswapV3MathError::PriceOutOfRange(_))
+        ));
+        assert_eq!(price_to_tick(below, Rounding::Up)?, MIN_TICK);
+        assert_eq!(price_to_tick(below, Rounding::Nearest)?, MIN_TICK);
+        let above = tick_to_price(MAX_TICK)? * 2.0;
+        assert_eq!(price_to_tick(above, Rounding::Down)?, MAX_TICK);
+        assert!(matches!(
+            price_to_tick(above, Rounding::Up),
+            Err(UniswapV3MathError::PriceOutOfRange(_))
+        ));
+        assert_eq!(price_to_tick(above, Rounding::Nearest)?, MAX_TICK);
+
+        for price in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(matches!(
+                price_to_tick(price, Rounding::Nearest),
+                Err(UniswapV3MathError::PriceOutOfRange(_))
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_tick_math_constants() -> eyre::Result<()> {
+        //the values of TickMath.sol, an off bound shifts the whole price domain
+        assert_eq!(MIN_TICK, -887272);
+        assert_eq!(MAX_TICK, 887272);
+        assert_eq!(MIN_SQRT_RATIO, U256::from(4295128739_u64));
+        assert_eq!(
+            MAX_SQRT_RATIO,
+            U256::from_str("1461446703485210103287273052203988822378723970342")?
+        );
+        assert_eq!(U256_MAX_TICK, U256::from(MAX_TICK));
+
+        //the sqrt ratios are the ones of the bounding ticks
+        assert_eq!(get_sqrt_ratio_at_tick(MIN_TICK)?, MIN_SQRT_RATIO);
+        assert_eq!(get_sqrt_ratio_at_tick(MAX_TICK)?, MAX_SQRT_RATIO);
+        Ok(())
+    }
+
     #[test]
     fn test_get_sqrt_ratio_at_tick_bounds() {
         // the function should return an error if the tick is out of bounds
         if let Err(err) = get_sqrt_ratio_at_tick(MIN_TICK - 1) {
-            assert!(matches!(err, UniswapV3MathError::T));
+            assert!(matches!(err, UniswapV3MathError::TickOutOfRange(-887273)));
         } else {
             panic!("get_qrt_ratio_at_tick did not respect lower tick bound")
         }
         if let Err(err) = get_sqrt_ratio_at_tick(MAX_TICK + 1) {
-            assert!(matches!(err, UniswapV3MathError::T));
+            assert!(matches!(err, UniswapV3MathError::TickOutOfRange(887273)));
         } else {
             panic!("get_qrt_ratio_at_tick did not respect upper tick bound")
         }
     }
 
+    #[test]
+    fn test_sqrt_ratio_multipliers() {
+        // the magic numbers of TickMath.getSqrtRatioAtTick, in bit order
+        let solidity = [
+            "0xfffcb933bd6fad37aa2d162d1a594001",
+            "0xfff97272373d413259a46990580e213a",
+            "0xfff2e50f5f656932ef12357cf3c7fdcc",
+            "0xffe5caca7e10e4e61c3624eaa0941cd0",
+            "0xffcb9843d60f6159c9db58835c926644",
+            "0xff973b41fa98c081472e6896dfb254c0",
+            "0xff2ea16466c96a3843ec78b326b52861",
+            "0xfe5dee046a99a2a811c461f1969c3053",
+            "0xfcbe86c7900a88aedcffc83b479aa3a4",
+            "0xf987a7253ac413176f2b074cf7815e54",
+            "0xf3392b0822b70005940c7a398e4b70f3",
+            "0xe7159475a2c29b7443b29c7fa6e889d9",
+            "0xd097f3bdfd2022b8845ad8f792aa5825",
+            "0xa9f746462d870fdf8a65dc1f90e061e5",
+            "0x70d869a156d2a1b890bb3df62baf32f7",
+            "0x31be135f97d08fd981231505542fcfa6",
+            "0x9aa508b5b7a84e1c677de54f3e99bc9",
+            "0x5d6af8dedb81196699c329225ee604",
+            "0x2216e584f5fa1ea926041bedfe98",
+            "0x48a170391f7dc42444e8fa2",
+        ];
+        for (i, multiplier) in solidity.iter().enumerate() {
+            assert_eq!(
+                SQRT_RATIO_MULTIPLIERS[i],
+                U256::from_str(multiplier).unwrap(),
+                "multiplier for bit {i} incorrect"
+            );
+        }
+    }
+
     #[test]
     fn test_get_sqrt_ratio_at_tick_values() {
         // test individual values for correct results
@@ -360,4 +741,195 @@ mod test {
         let result = get_tick_at_sqrt_ratio(U256::from_str("4295343490").unwrap()).unwrap();
         assert_eq!(result, MIN_TICK + 1);
     }
+
+    #[test]
+    pub fn test_get_tick_at_sqrt_ratio_exact_tick_prices() {
+        let ticks = (MIN_TICK..MAX_TICK)
+            .step_by(997)
+            .chain([-1, 0, 1, MAX_TICK - 1]);
+        for tick in ticks {
+            let sqrt_ratio = get_sqrt_ratio_at_tick(tick).unwrap();
+            assert_eq!(get_tick_at_sqrt_ratio(sqrt_ratio).unwrap(), tick);
+            assert_eq!(
+                get_tick_at_sqrt_ratio(sqrt_ratio + U256_1).unwrap(),
+                tick,
+                "price just above tick {tick}"
+            );
+            if tick > MIN_TICK {
+                assert_eq!(
+                    get_tick_at_sqrt_ratio(sqrt_ratio - U256_1).unwrap(),
+                    tick - 1,
+                    "price just below tick {tick}"
+                );
+            }
+        }
+    }
+
+    // a price in [MIN_SQRT_RATIO, MAX_SQRT_RATIO) with a random bit length so that low prices are sampled as often as
+    // high ones, a uniform U256 would almost always land in the top ticks
+    fn sqrt_price_strategy() -> impl Strategy<Value = U256> {
+        (32_usize..=161, any::<[u64; 3]>()).prop_map(|(bits, limbs)| {
+            let value = U256::from_limbs([limbs[0], limbs[1], limbs[2], 0]) >> (192 - bits);
+            value.clamp(MIN_SQRT_RATIO, MAX_SQRT_RATIO - U256_1)
+        })
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(5000))]
+
+        #[test]
+        fn test_get_tick_at_sqrt_ratio_monotonic(
+            a in sqrt_price_strategy(),
+            b in sqrt_price_strategy(),
+        ) {
+            let (lower, upper) = if a <= b { (a, b) } else { (b, a) };
+            let tick_lower = get_tick_at_sqrt_ratio(lower).unwrap();
+            let tick_upper = get_tick_at_sqrt_ratio(upper).unwrap();
+            prop_assert!(
+                tick_lower <= tick_upper,
+                "{} at tick {} above {} at tick {}",
+                lower,
+                tick_lower,
+                upper,
+                tick_upper
+            );
+            //the price is within the tick it is placed in
+            prop_assert!(get_sqrt_ratio_at_tick(tick_lower).unwrap() <= lower);
+            prop_assert!(get_sqrt_ratio_at_tick(tick_lower + 1).unwrap() > lower);
+        }
+    }
+
+    #[test]
+    fn test_get_tick_at_sqrt_ratio_monotonic_boundaries() -> eyre::Result<()> {
+        let mut prices = vec![MIN_SQRT_RATIO, MAX_SQRT_RATIO - U256_1];
+        for tick in [MIN_TICK + 1, -524288, -1, 0, 1, 524287, MAX_TICK - 1] {
+            let sqrt_price = get_sqrt_ratio_at_tick(tick)?;
+            prices.extend([sqrt_price - U256_1, sqrt_price, sqrt_price + U256_1]);
+        }
+        prices.sort();
+        let ticks = prices
+            .iter()
+            .map(|price| get_tick_at_sqrt_ratio(*price))
+            .collect::<Result<Vec<i32>, _>>()?;
+        for (pair, price_pair) in ticks.windows(2).zip(prices.windows(2)) {
+            assert!(
+                pair[0] <= pair[1],
+                "inversion between {} and {}",
+                price_pair[0],
+                price_pair[1]
+            );
+        }
+        assert_eq!(ticks.first(), Some(&MIN_TICK));
+        assert_eq!(ticks.last(), Some(&(MAX_TICK - 1)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sqrt_ratios_at_ticks() -> eyre::Result<()> {
+        let mut ticks: Vec<i32> = (-3000..=3000).collect();
+        ticks.extend([
+            MIN_TICK,
+            MIN_TICK + 1,
+            -524288,
+            524287,
+            MAX_TICK - 1,
+            MAX_TICK,
+        ]);
+
+        let ratios = sqrt_ratios_at_ticks(&ticks)?;
+        assert_eq!(ratios.len(), ticks.len());
+        for (tick, ratio) in ticks.iter().zip(ratios) {
+            assert_eq!(ratio, get_sqrt_ratio_at_tick(*tick)?, "tick {tick}");
+        }
+
+        assert_eq!(sqrt_ratios_at_ticks(&[])?, vec![]);
+        assert!(matches!(
+            sqrt_ratios_at_ticks(&[0, MAX_TICK + 1]),
+            Err(UniswapV3MathError::TickOutOfRange(887273))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sqrt_ratios_in_word() -> eyre::Result<()> {
+        for (word_pos, tick_spacing) in [(0, 1), (-1, 1), (-3, 1), (2, 60), (-57, 60), (3, 10)] {
+            let ratios = sqrt_ratios_in_word(word_pos, tick_spacing)?;
+            assert_eq!(ratios.len(), 256);
+            for (bit_pos, (tick, ratio)) in ratios.into_iter().enumerate() {
+                assert_eq!(
+                    tick,
+                    (word_pos as i32 * 256 + bit_pos as i32) * tick_spacing
+                );
+                assert_eq!(ratio, get_sqrt_ratio_at_tick(tick)?, "tick {tick}");
+            }
+        }
+
+        //the words at the ends of the tick range only hold the ticks within it
+        let lowest = sqrt_ratios_in_word(-58, 60)?;
+        assert_eq!(
+            lowest.first(),
+            Some(&(-887220, get_sqrt_ratio_at_tick(-887220)?))
+        );
+        let highest = sqrt_ratios_in_word(3465, 1)?;
+        assert_eq!(highest.len(), (MAX_TICK - 3465 * 256 + 1) as usize);
+        assert_eq!(highest.last(), Some(&(MAX_TICK, MAX_SQRT_RATIO)));
+        assert_eq!(sqrt_ratios_in_word(i16::MAX, 60)?, vec![]);
+        assert!(matches!(
+            sqrt_ratios_in_word(0, 0),
+            Err(UniswapV3MathError::TickSpacingError)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_tick_at_sqrt_ratio_cached() -> eyre::Result<()> {
+        let prices = [
+            MIN_SQRT_RATIO,
+            U256::from_str("79228162514264337593543950336")?,
+            U256::from_str("79149250711305166342700278159")?,
+            MAX_SQRT_RATIO - U256_1,
+        ];
+
+        let mut cache = TickCache::new(2);
+        for _ in 0..2 {
+            for price in prices {
+                assert_eq!(
+                    get_tick_at_sqrt_ratio_cached(price, Some(&mut cache))?,
+                    get_tick_at_sqrt_ratio(price)?
+                );
+            }
+        }
+        //cycling through more prices than the capacity evicts every entry before it is reused
+        assert_eq!(cache.stats(), (0, 8));
+        assert_eq!(cache.len(), 2);
+
+        //repeated prices hit, and a hit makes the entry the most recently used
+        let mut cache = TickCache::new(2);
+        get_tick_at_sqrt_ratio_cached(prices[0], Some(&mut cache))?;
+        get_tick_at_sqrt_ratio_cached(prices[1], Some(&mut cache))?;
+        assert_eq!(
+            get_tick_at_sqrt_ratio_cached(prices[0], Some(&mut cache))?,
+            MIN_TICK
+        );
+        get_tick_at_sqrt_ratio_cached(prices[2], Some(&mut cache))?;
+        assert_eq!(
+            get_tick_at_sqrt_ratio_cached(prices[0], Some(&mut cache))?,
+            MIN_TICK
+        );
+        assert_eq!(cache.stats(), (2, 3));
+        assert_eq!(
+            get_tick_at_sqrt_ratio_cached(prices[2], Some(&mut cache))?,
+            -20
+        );
+        assert_eq!(cache.stats(), (3, 3));
+
+        //errors are passed through and not cached
+        assert!(matches!(
+            get_tick_at_sqrt_ratio_cached(MAX_SQRT_RATIO, Some(&mut cache)),
+            Err(UniswapV3MathError::R)
+        ));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(get_tick_at_sqrt_ratio_cached(prices[1], None)?, 0);
+        Ok(())
+    }
 }
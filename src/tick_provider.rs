@@ -0,0 +1,391 @@
+use crate::error::UniswapV3MathError;
+use crate::tick::Tick;
+use crate::tick_bitmap;
+use alloy::primitives::U256;
+use std::{cell::Cell, collections::HashMap};
+
+// the tick state a swap reads while walking the price, implement this to fetch ticks on demand
+// (e.g. from contract storage or an RPC node) instead of loading every tick of a pool up front.
+// Fetch failures should be reported as UniswapV3MathError::MiddlewareError
+pub trait TickProvider {
+    // returns the tick info, None if the tick is not initialized
+    fn tick(&self, tick: i32) -> Result<Option<Tick>, UniswapV3MathError>;
+
+    // returns (next, initialized) with the semantics of tick_bitmap::next_initialized_tick_within_one_word
+    fn next_initialized(
+        &self,
+        tick: i32,
+        tick_spacing: i32,
+        lte: bool,
+    ) -> Result<(i32, bool), UniswapV3MathError>;
+
+    // returns the tick bitmap word at word_pos, zero if it has no initialized ticks, or None if the provider does not
+    // read single words. A swap over a provider returning words fetches each word it walks through once and searches
+    // it itself instead of calling next_initialized for every step within the word
+    fn word(&self, _word_pos: i16) -> Result<Option<U256>, UniswapV3MathError> {
+        Ok(None)
+    }
+}
+
+// the last tick bitmap word a swap read through TickProvider::word
+#[derive(Debug, Default)]
+pub(crate) struct WordCache {
+    word: Option<(i16, U256)>,
+}
+
+impl WordCache {
+    //Same as TickProvider::next_initialized, searching the word returned by TickProvider::word and only fetching it
+    //again once the search moved on to another word. Falls back to next_initialized if the provider returns no words
+    pub(crate) fn next_initialized<P: TickProvider>(
+        &mut self,
+        provider: &P,
+        tick: i32,
+        tick_spacing: i32,
+        lte: bool,
+    ) -> Result<(i32, bool), UniswapV3MathError> {
+        let mut unsupported = false;
+        let next = tick_bitmap::next_initialized_tick_within_one_word_with(
+            |word_pos| {
+                if let Some((cached_pos, word)) = self.word {
+                    if cached_pos == word_pos {
+                        return Ok(word);
+                    }
+                }
+                match provider.word(word_pos)? {
+                    Some(word) => {
+                        self.word = Some((word_pos, word));
+                        Ok(word)
+                    }
+                    None => {
+                        unsupported = true;
+                        Ok(U256::ZERO)
+                    }
+                }
+            },
+            tick,
+            tick_spacing,
+            lte,
+        )?;
+        if unsupported {
+            return provider.next_initialized(tick, tick_spacing, lte);
+        }
+        Ok(next)
+    }
+}
+
+// a provider over ticks and a tick bitmap that are fully loaded in memory
+pub struct HashMapTickProvider<'a> {
+    pub ticks: &'a HashMap<i32, Tick>,
+    pub tick_bitmap: &'a HashMap<i16, U256>,
+}
+
+impl<'a> HashMapTickProvider<'a> {
+    pub fn new(ticks: &'a HashMap<i32, Tick>, tick_bitmap: &'a HashMap<i16, U256>) -> Self {
+        HashMapTickProvider { ticks, tick_bitmap }
+    }
+}
+
+impl TickProvider for HashMapTickProvider<'_> {
+    fn tick(&self, tick: i32) -> Result<Option<Tick>, UniswapV3MathError> {
+        Ok(self.ticks.get(&tick).cloned())
+    }
+
+    fn next_initialized(
+        &self,
+        tick: i32,
+        tick_spacing: i32,
+        lte: bool,
+    ) -> Result<(i32, bool), UniswapV3MathError> {
+        tick_bitmap::next_initialized_tick_within_one_word(
+            self.tick_bitmap,
+            tick,
+            tick_spacing,
+            lte,
+        )
+    }
+
+    fn word(&self, word_pos: i16) -> Result<Option<U256>, UniswapV3MathError> {
+        Ok(Some(
+            self.tick_bitmap.get(&word_pos).copied().unwrap_or_default(),
+        ))
+    }
+}
+
+// a provider over ticks and tick bitmap words in caller owned slices sorted by tick and word position and looked
+// up with a binary search, for targets without a hasher or heap. Words missing from the slice have no initialized
+// ticks
+pub struct SliceTickProvider<'a> {
+    pub ticks: &'a [(i32, Tick)],
+    pub tick_bitmap: &'a [(i16, U256)],
+}
+
+impl<'a> SliceTickProvider<'a> {
+    pub fn new(ticks: &'a [(i32, Tick)], tick_bitmap: &'a [(i16, U256)]) -> Self {
+        debug_assert!(
+            ticks.windows(2).all(|pair| pair[0].0 < pair[1].0),
+            "ticks are not sorted"
+        );
+        debug_assert!(
+            tick_bitmap.windows(2).all(|pair| pair[0].0 < pair[1].0),
+            "tick bitmap words are not sorted"
+        );
+        SliceTickProvider { ticks, tick_bitmap }
+    }
+
+    fn word_at(&self, word_pos: i16) -> U256 {
+        self.tick_bitmap
+            .binary_search_by_key(&word_pos, |(word_pos, _)| *word_pos)
+            .map_or(U256::ZERO, |index| self.tick_bitmap[index].1)
+    }
+}
+
+impl TickProvider for SliceTickProvider<'_> {
+    fn tick(&self, tick: i32) -> Result<Option<Tick>, UniswapV3MathError> {
+        Ok(self
+            .ticks
+            .binary_search_by_key(&tick, |(tick, _)| *tick)
+            .ok()
+            .map(|index| self.ticks[index].1.clone()))
+    }
+
+    fn next_initialized(
+        &self,
+        tick: i32,
+        tick_spacing: i32,
+        lte: bool,
+    ) -> Result<(i32, bool), UniswapV3MathError> {
+        tick_bitmap::next_initialized_tick_within_one_word_with(
+            |word_pos| Ok(self.word_at(word_pos)),
+            tick,
+            tick_spacing,
+            lte,
+        )
+    }
+
+    fn word(&self, word_pos: i16) -> Result<Option<U256>, UniswapV3MathError> {
+        Ok(Some(self.word_at(word_pos)))
+    }
+}
+
+// wraps a provider to bound the number of bitmap words a swap may scan, e.g. in a metered context where walking
+// sparse liquidity towards a far price limit is too expensive. Each next_initialized call scans one word, once the
+// budget is used up the next scan fails with UniswapV3MathError::ScanBudgetExceeded
+pub struct ScanBudgetTickProvider<P> {
+    pub inner: P,
+    max_words_scanned: u32,
+    words_scanned: Cell<u32>,
+}
+
+impl<P: TickProvider> ScanBudgetTickProvider<P> {
+    pub fn new(inner: P, max_words_scanned: u32) -> Self {
+        ScanBudgetTickProvider {
+            inner,
+            max_words_scanned,
+            words_scanned: Cell::new(0),
+        }
+    }
+
+    pub fn words_scanned(&self) -> u32 {
+        self.words_scanned.get()
+    }
+}
+
+impl<P: TickProvider> TickProvider for ScanBudgetTickProvider<P> {
+    fn tick(&self, tick: i32) -> Result<Option<Tick>, UniswapV3MathError> {
+        self.inner.tick(tick)
+    }
+
+    fn next_initialized(
+        &self,
+        tick: i32,
+        tick_spacing: i32,
+        lte: bool,
+    ) -> Result<(i32, bool), UniswapV3MathError> {
+        if self.words_scanned.get() >= self.max_words_scanned {
+            return Err(UniswapV3MathError::ScanBudgetExceeded(
+                self.max_words_scanned,
+            ));
+        }
+        self.words_scanned.set(self.words_scanned.get() + 1);
+        self.inner.next_initialized(tick, tick_spacing, lte)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{HashMapTickProvider, TickProvider};
+    use crate::{
+        error::UniswapV3MathError,
+        sqrt_price_math::Q96,
+        swap::{swap_with_tick_provider, Slot0},
+        tick::Tick,
+        tick_bitmap::flip_tick,
+        tick_math,
+    };
+    use alloy::primitives::{I256, U256};
+    use std::{
+        cell::{Cell, RefCell},
+        collections::HashMap,
+        str::FromStr,
+    };
+
+    // counts the reads a swap makes, like a provider backed by storage or RPC would pay for them
+    struct CountingTickProvider<'a> {
+        inner: HashMapTickProvider<'a>,
+        tick_fetches: Cell<usize>,
+        bitmap_fetches: Cell<usize>,
+    }
+
+    impl TickProvider for CountingTickProvider<'_> {
+        fn tick(&self, tick: i32) -> Result<Option<Tick>, UniswapV3MathError> {
+            self.tick_fetches.set(self.tick_fetches.get() + 1);
+            self.inner.tick(tick)
+        }
+
+        fn next_initialized(
+            &self,
+            tick: i32,
+            tick_spacing: i32,
+            lte: bool,
+        ) -> Result<(i32, bool), UniswapV3MathError> {
+            self.bitmap_fetches.set(self.bitmap_fetches.get() + 1);
+            self.inner.next_initialized(tick, tick_spacing, lte)
+        }
+    }
+
+    #[test]
+    fn test_swap_fetches_ticks_on_demand() -> eyre::Result<()> {
+        // (-600, 600, 1e18), (-1200, -120, 5e17), (120, 1800, 5e17)
+        let mut ticks: HashMap<i32, Tick> = HashMap::new();
+        for (tick, liquidity_gross, liquidity_net) in [
+            (-1200, 500_000_000_000_000_000, 500_000_000_000_000_000),
+            (-600, 1_000_000_000_000_000_000, 1_000_000_000_000_000_000),
+            (-120, 500_000_000_000_000_000, -500_000_000_000_000_000),
+            (120, 500_000_000_000_000_000, 500_000_000_000_000_000),
+            (600, 1_000_000_000_000_000_000, -1_000_000_000_000_000_000),
+            (1800, 500_000_000_000_000_000, -500_000_000_000_000_000),
+        ] {
+            ticks.insert(
+                tick,
+                Tick {
+                    liquidity_gross,
+                    liquidity_net,
+                    initialized: true,
+                    ..Default::default()
+                },
+            );
+        }
+        let mut tick_bitmap: HashMap<i16, U256> = HashMap::new();
+        for tick in ticks.keys() {
+            flip_tick(&mut tick_bitmap, *tick, 60)?;
+        }
+
+        let provider = CountingTickProvider {
+            inner: HashMapTickProvider::new(&ticks, &tick_bitmap),
+            tick_fetches: Cell::new(0),
+            bitmap_fetches: Cell::new(0),
+        };
+        let result = swap_with_tick_provider(
+            &provider,
+            60,
+            true,
+            I256::from_str("50000000000000000")?,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+            &Slot0 {
+                sqrt_price: Q96,
+                liquidity: 1_000_000_000_000_000_000,
+                tick: 0,
+            },
+            3000,
+        )?;
+        assert_eq!(result.amount1_delta, I256::from_str("-48007037308774480")?);
+        assert_eq!(result.tick_after, -877);
+
+        // four steps: the word boundary at tick 0, -120, -600 and the partial step towards -1200,
+        // only the two crossed initialized ticks are read
+        assert_eq!(provider.bitmap_fetches.get(), 4);
+        assert_eq!(provider.tick_fetches.get(), 2);
+        Ok(())
+    }
+
+    // serves single words like a lazy provider reading bitmap words from storage, recording every word read
+    struct WordFetchingTickProvider<'a> {
+        inner: HashMapTickProvider<'a>,
+        word_fetches: RefCell<Vec<i16>>,
+    }
+
+    impl TickProvider for WordFetchingTickProvider<'_> {
+        fn tick(&self, tick: i32) -> Result<Option<Tick>, UniswapV3MathError> {
+            self.inner.tick(tick)
+        }
+
+        fn next_initialized(
+            &self,
+            _tick: i32,
+            _tick_spacing: i32,
+            _lte: bool,
+        ) -> Result<(i32, bool), UniswapV3MathError> {
+            Err(UniswapV3MathError::MiddlewareError(
+                "the swap reads single words".to_string(),
+            ))
+        }
+
+        fn word(&self, word_pos: i16) -> Result<Option<U256>, UniswapV3MathError> {
+            self.word_fetches.borrow_mut().push(word_pos);
+            self.inner.word(word_pos)
+        }
+    }
+
+    #[test]
+    fn test_swap_fetches_each_word_once() -> eyre::Result<()> {
+        // a position on (-600, 600) and one on (-300, -120), the negative ticks are all in word -1
+        let mut ticks: HashMap<i32, Tick> = HashMap::new();
+        let mut tick_bitmap: HashMap<i16, U256> = HashMap::new();
+        for (tick, liquidity_gross, liquidity_net) in [
+            (-600, 1_000_000_000_000_000_000, 1_000_000_000_000_000_000),
+            (-300, 500_000_000_000_000_000, 500_000_000_000_000_000),
+            (-120, 500_000_000_000_000_000, -500_000_000_000_000_000),
+            (600, 1_000_000_000_000_000_000, -1_000_000_000_000_000_000),
+        ] {
+            ticks.insert(tick, Tick::new(liquidity_gross, liquidity_net)?);
+            flip_tick(&mut tick_bitmap, tick, 60)?;
+        }
+        let slot0 = Slot0 {
+            sqrt_price: Q96,
+            liquidity: 1_000_000_000_000_000_000,
+            tick: 0,
+        };
+
+        let provider = WordFetchingTickProvider {
+            inner: HashMapTickProvider::new(&ticks, &tick_bitmap),
+            word_fetches: RefCell::new(Vec::new()),
+        };
+        let result = swap_with_tick_provider(
+            &provider,
+            60,
+            true,
+            I256::from_str("30000000000000000")?,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+            &slot0,
+            3000,
+        )?;
+        let expected = swap_with_tick_provider(
+            &HashMapTickProvider::new(&ticks, &tick_bitmap),
+            60,
+            true,
+            I256::from_str("30000000000000000")?,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+            &slot0,
+            3000,
+        )?;
+        assert_eq!(result, expected);
+
+        assert_eq!(result.tick_after, -501);
+
+        // four steps: the word boundary at tick 0, -120, -300 and the partial step towards -600, over two words
+        assert_eq!(result.cost.iterations, 4);
+        assert_eq!(result.tick_crossings.len(), 2);
+        assert_eq!(*provider.word_fetches.borrow(), vec![0, -1]);
+        Ok(())
+    }
+}
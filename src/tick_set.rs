@@ -0,0 +1,113 @@
+use crate::tick::Tick;
+use alloy::primitives::U256;
+use std::collections::HashMap;
+
+// the initialized ticks of a pool with their tick bitmap, borrowed from the pool, iterating over the ticks in
+// ascending order rather than in the arbitrary order of the map
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickSet<'a> {
+    ticks: &'a HashMap<i32, Tick>,
+    tick_bitmap: &'a HashMap<i16, U256>,
+}
+
+impl<'a> TickSet<'a> {
+    pub fn new(ticks: &'a HashMap<i32, Tick>, tick_bitmap: &'a HashMap<i16, U256>) -> Self {
+        TickSet { ticks, tick_bitmap }
+    }
+
+    pub fn ticks(&self) -> &'a HashMap<i32, Tick> {
+        self.ticks
+    }
+
+    pub fn tick_bitmap(&self) -> &'a HashMap<i16, U256> {
+        self.tick_bitmap
+    }
+
+    // the number of initialized ticks
+    pub fn len(&self) -> usize {
+        self.ticks.values().filter(|info| info.initialized).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    //Returns the initialized ticks in ascending order
+    pub fn iter(&self) -> std::vec::IntoIter<(i32, &'a Tick)> {
+        let mut ticks: Vec<(i32, &'a Tick)> = self
+            .ticks
+            .iter()
+            .filter(|(_, info)| info.initialized)
+            .map(|(tick, info)| (*tick, info))
+            .collect();
+        ticks.sort_unstable_by_key(|(tick, _)| *tick);
+        ticks.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for TickSet<'a> {
+    type Item = (i32, &'a Tick);
+    type IntoIter = std::vec::IntoIter<(i32, &'a Tick)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &TickSet<'a> {
+    type Item = (i32, &'a Tick);
+    type IntoIter = std::vec::IntoIter<(i32, &'a Tick)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TickSet;
+    use crate::{tick::Tick, tick_bitmap::flip_tick};
+    use alloy::primitives::U256;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_tick_set_ascending() -> eyre::Result<()> {
+        // the ticks of (-600, 600, 1e18), (-1200, -120, 5e17) and (120, 1800, 5e17), inserted out of order
+        let mut ticks: HashMap<i32, Tick> = HashMap::new();
+        let mut tick_bitmap: HashMap<i16, U256> = HashMap::new();
+        for (tick, liquidity_gross, liquidity_net) in [
+            (600, 1_000_000_000_000_000_000, -1_000_000_000_000_000_000),
+            (-120, 500_000_000_000_000_000, -500_000_000_000_000_000),
+            (1800, 500_000_000_000_000_000, -500_000_000_000_000_000),
+            (-1200, 500_000_000_000_000_000, 500_000_000_000_000_000),
+            (120, 500_000_000_000_000_000, 500_000_000_000_000_000),
+            (-600, 1_000_000_000_000_000_000, 1_000_000_000_000_000_000),
+        ] {
+            ticks.insert(tick, Tick::new(liquidity_gross, liquidity_net)?);
+            flip_tick(&mut tick_bitmap, tick, 60)?;
+        }
+        // an uninitialized entry is skipped
+        ticks.insert(60, Tick::default());
+        let tick_set = TickSet::new(&ticks, &tick_bitmap);
+        assert_eq!(tick_set.len(), 6);
+
+        let expected = vec![-1200, -600, -120, 120, 600, 1800];
+        let borrowed: Vec<i32> = tick_set.iter().map(|(tick, _)| tick).collect();
+        assert_eq!(borrowed, expected);
+        let mut liquidity = 0;
+        for (_, info) in &tick_set {
+            liquidity += info.liquidity_net;
+            assert!(liquidity >= 0);
+        }
+        assert_eq!(liquidity, 0);
+
+        let by_value: Vec<(i32, &Tick)> = tick_set.into_iter().collect();
+        assert_eq!(
+            by_value.iter().map(|(tick, _)| *tick).collect::<Vec<i32>>(),
+            expected
+        );
+        assert_eq!(by_value[0].1.liquidity_net, 500_000_000_000_000_000);
+        assert!(TickSet::new(&HashMap::new(), &HashMap::new()).is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+//JS bindings for simulating swaps in the browser, enabled by the wasm feature. Big integers cross the boundary as
+//decimal strings inside the JSON accepted by swap_json, see SwapJsonInput. The package is built from the cdylib of the
+//wasm crate, which re-exports these bindings
+//
+//    wasm-pack build wasm --target web
+//
+//The error paths are tested with `wasm-pack test --node --features wasm`, a JsError can only be created on wasm32
+use crate::error::UniswapV3MathError;
+use crate::swap_json::{swap_json, SwapJsonInput};
+use wasm_bindgen::prelude::*;
+
+fn to_js_error(err: UniswapV3MathError) -> JsError {
+    JsError::new(&err.to_string())
+}
+
+//Runs the swap described by a SwapJsonInput JSON and returns the SwapResult as JSON
+#[wasm_bindgen(js_name = swap)]
+pub fn swap_js(input: &str) -> Result<String, JsError> {
+    swap_json(input).map_err(to_js_error)
+}
+
+//Returns the amount out of an exact input swap described by a SwapJsonInput JSON as a decimal string
+#[wasm_bindgen(js_name = quoteExactInput)]
+pub fn quote_exact_input(input: &str) -> Result<String, JsError> {
+    quote_exact_input_amount(input).map_err(to_js_error)
+}
+
+fn quote_exact_input_amount(input: &str) -> Result<String, UniswapV3MathError> {
+    let input = SwapJsonInput::from_json(input)?;
+    if !input.amount_specified.is_positive() {
+        return Err(UniswapV3MathError::SwapJsonError(
+            "amount_specified must be a positive exact input".to_string(),
+        ));
+    }
+
+    let result = input.swap()?;
+    let amount_out = if input.zero_for_one {
+        result.amount1_delta
+    } else {
+        result.amount0_delta
+    };
+    Ok(amount_out.unsigned_abs().to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{quote_exact_input, quote_exact_input_amount, swap_js};
+    use crate::error::UniswapV3MathError;
+
+    // a 0.3% pool at tick 0 with a single full range position of 1e18
+    const WASM_FIXTURE: &str = r#"{
+        "ticks": {
+            "-887220": { "liquidity_gross": 1000000000000000000, "liquidity_net": 1000000000000000000, "initialized": true },
+            "887220": { "liquidity_gross": 1000000000000000000, "liquidity_net": -1000000000000000000, "initialized": true }
+        },
+        "tick_spacing": 60,
+        "fee": 3000,
+        "slot0": {
+            "sqrt_price": "79228162514264337593543950336",
+            "liquidity": 1000000000000000000,
+            "tick": 0
+        },
+        "zero_for_one": true,
+        "amount_specified": "1000000000000000",
+        "sqrt_price_limit": "4295128740"
+    }"#;
+
+    // only the success paths, creating a JsError needs a JS host, see test_wasm_binding_errors
+    #[test]
+    fn test_wasm_bindings() -> eyre::Result<()> {
+        assert_eq!(
+            quote_exact_input(WASM_FIXTURE).map_err(|_| eyre::eyre!("quote failed"))?,
+            "996006981039903"
+        );
+
+        let output = swap_js(WASM_FIXTURE).map_err(|_| eyre::eyre!("swap failed"))?;
+        let value: serde_json::Value = serde_json::from_str(&output)?;
+        assert_eq!(value["amount1_delta"], "-996006981039903");
+        assert_eq!(value["tick_after"], -20);
+
+        let exact_output = WASM_FIXTURE.replace("\"1000000000000000\"", "\"-1000000000000000\"");
+        assert!(matches!(
+            quote_exact_input_amount(&exact_output),
+            Err(UniswapV3MathError::SwapJsonError(_))
+        ));
+        Ok(())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    #[wasm_bindgen_test::wasm_bindgen_test]
+    fn test_wasm_binding_errors() {
+        let exact_output = WASM_FIXTURE.replace("\"1000000000000000\"", "\"-1000000000000000\"");
+        assert!(quote_exact_input(&exact_output).is_err());
+        assert!(swap_js("{}").is_err());
+        assert!(quote_exact_input("not json").is_err());
+    }
+}
@@ -0,0 +1,105 @@
+//Replays swaps captured from mainnet pools and checks that the simulation matches the Swap event exactly.
+//
+//Each fixture in tests/fixtures/mainnet_replay is a JSON file with the pool state right before the swap, in the
+//SwapJsonInput format, and the values of the Swap event it emitted:
+//
+//    {
+//        "pool": "0x88e6a0c2ddd26feeb64f039a2c41296fcb3f5640",
+//        "tx_hash": "0x...",
+//        "block_number": 19000000,
+//        "input": { "ticks": { ... }, "tick_spacing": 10, "fee": 500, "slot0": { ... },
+//                   "zero_for_one": false, "amount_specified": "...", "sqrt_price_limit": "..." },
+//        "expected": { "amount0": "...", "amount1": "...", "sqrt_price_x96": "...",
+//                      "liquidity": "...", "tick": ... }
+//    }
+//
+//The state is read at block_number - 1 plus the transactions before the swap in its block, i.e. slot0 and
+//liquidity() of the pool and ticks(i) of every initialized tick between the price before and after the swap (the
+//tick bitmap is derived from the ticks). An exact input or output amount is the amountIn/amountOut of the router call.
+//The swap has to be the first one on the pool in its block, or the state has to be taken after the swaps before it
+#![cfg(feature = "serde")]
+
+use alloy::primitives::{I256, U256};
+use cw_uniswap_v3::swap_json::SwapJsonInput;
+use serde::Deserialize;
+use std::{fs, path::Path};
+
+#[derive(Debug, Deserialize)]
+struct ExpectedSwapEvent {
+    amount0: String,
+    amount1: String,
+    sqrt_price_x96: String,
+    liquidity: String,
+    tick: i32,
+}
+
+#[derive(Debug, Deserialize)]
+struct MainnetSwapFixture {
+    pool: String,
+    tx_hash: String,
+    // deserialized directly, a serde_json::Value would hold the u128 liquidities above u64::MAX as f64
+    input: SwapJsonInput,
+    expected: ExpectedSwapEvent,
+}
+
+fn replay_fixture(path: &Path) -> eyre::Result<()> {
+    let fixture: MainnetSwapFixture = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let context = format!(
+        "{} in {} ({})",
+        fixture.tx_hash,
+        fixture.pool,
+        path.display()
+    );
+
+    let result = fixture.input.swap()?;
+
+    assert_eq!(
+        result.amount0_delta,
+        I256::from_dec_str(&fixture.expected.amount0)?,
+        "amount0 of {context}"
+    );
+    assert_eq!(
+        result.amount1_delta,
+        I256::from_dec_str(&fixture.expected.amount1)?,
+        "amount1 of {context}"
+    );
+    assert_eq!(
+        result.sqrt_price_after,
+        U256::from_str_radix(&fixture.expected.sqrt_price_x96, 10)?,
+        "sqrt_price_x96 of {context}"
+    );
+    assert_eq!(
+        result.liquidity_after,
+        fixture.expected.liquidity.parse::<u128>()?,
+        "liquidity of {context}"
+    );
+    assert_eq!(
+        result.tick_after, fixture.expected.tick,
+        "tick of {context}"
+    );
+    Ok(())
+}
+
+#[test]
+#[ignore = "no mainnet swap is captured in tests/fixtures/mainnet_replay yet"]
+fn test_mainnet_replay() -> eyre::Result<()> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/mainnet_replay");
+    let mut paths: Vec<_> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    paths.retain(|path| {
+        path.extension()
+            .is_some_and(|extension| extension == "json")
+    });
+    paths.sort();
+    // an empty directory would pass without checking anything
+    eyre::ensure!(
+        !paths.is_empty(),
+        "no fixture in tests/fixtures/mainnet_replay"
+    );
+
+    for path in &paths {
+        replay_fixture(path)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,163 @@
+//Checks that swap_no_alloc over a SliceTickProvider runs a swap across several initialized ticks without touching
+//the heap. The test binary counts the allocations made on the test thread while the swap runs
+#![cfg(not(feature = "tracing"))]
+
+use alloy::primitives::{I256, U256};
+use cw_uniswap_v3::{
+    error::UniswapV3MathError,
+    swap::{swap, swap_no_alloc, Slot0, TickCrossing},
+    tick::Tick,
+    tick_bitmap::flip_tick,
+    tick_math,
+    tick_provider::SliceTickProvider,
+};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    collections::HashMap,
+    str::FromStr,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    // only the thread running the measured code counts, the test harness allocates on its own threads
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+}
+
+fn count_allocation() {
+    if COUNTING.try_with(Cell::get).unwrap_or(false) {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count_allocation();
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation();
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+// runs f and returns its output with the number of allocations it made
+fn allocations_during<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    COUNTING.with(|counting| counting.set(true));
+    let output = f();
+    COUNTING.with(|counting| counting.set(false));
+    (output, ALLOCATIONS.load(Ordering::Relaxed) - before)
+}
+
+// (-600, 600, 1e18), (-1200, -120, 5e17), (120, 1800, 5e17) in a 0.3% pool at tick 0, sorted for the slice provider
+fn fixture() -> eyre::Result<(Vec<(i32, Tick)>, Vec<(i16, U256)>, Slot0)> {
+    let mut ticks = Vec::new();
+    let mut tick_bitmap: HashMap<i16, U256> = HashMap::new();
+    for (tick, liquidity_gross, liquidity_net) in [
+        (-1200, 500_000_000_000_000_000, 500_000_000_000_000_000),
+        (-600, 1_000_000_000_000_000_000, 1_000_000_000_000_000_000),
+        (-120, 500_000_000_000_000_000, -500_000_000_000_000_000),
+        (120, 500_000_000_000_000_000, 500_000_000_000_000_000),
+        (600, 1_000_000_000_000_000_000, -1_000_000_000_000_000_000),
+        (1800, 500_000_000_000_000_000, -500_000_000_000_000_000),
+    ] {
+        ticks.push((tick, Tick::new(liquidity_gross, liquidity_net)?));
+        flip_tick(&mut tick_bitmap, tick, 60)?;
+    }
+    let mut words: Vec<(i16, U256)> = tick_bitmap.into_iter().collect();
+    words.sort_by_key(|(word_pos, _)| *word_pos);
+    let slot0 = Slot0 {
+        sqrt_price: U256::from_str("79228162514264337593543950336")?,
+        liquidity: 1_000_000_000_000_000_000,
+        tick: 0,
+    };
+    Ok((ticks, words, slot0))
+}
+
+#[test]
+fn test_swap_no_alloc() -> eyre::Result<()> {
+    let (ticks, words, slot0) = fixture()?;
+    let amount_specified = I256::from_str("1000000000000000000")?;
+    let sqrt_price_limit = tick_math::MIN_SQRT_RATIO + U256::from(1);
+
+    let expected = swap(
+        &ticks.iter().cloned().collect(),
+        &words.iter().cloned().collect(),
+        60,
+        true,
+        amount_specified,
+        sqrt_price_limit,
+        &slot0,
+        3000,
+    )?;
+    // -120, -600 and -1200 on the way to the minimum price
+    assert_eq!(expected.tick_crossings.len(), 3);
+
+    let provider = SliceTickProvider::new(&ticks, &words);
+    let mut tick_crossings: [TickCrossing; 4] = Default::default();
+    let (result, allocations) = allocations_during(|| {
+        swap_no_alloc(
+            &provider,
+            60,
+            true,
+            amount_specified,
+            sqrt_price_limit,
+            &slot0,
+            3000,
+            &mut tick_crossings,
+        )
+    });
+    let (result, crossed) = result?;
+    assert_eq!(allocations, 0);
+
+    assert_eq!(crossed, 3);
+    assert_eq!(&tick_crossings[..crossed], &expected.tick_crossings[..]);
+    assert!(result.tick_crossings.is_empty());
+    assert_eq!(result.cost, expected.cost);
+    assert_eq!(result.amount0_delta, expected.amount0_delta);
+    assert_eq!(result.amount1_delta, expected.amount1_delta);
+    assert_eq!(result.sqrt_price_after, expected.sqrt_price_after);
+    assert_eq!(result.liquidity_after, expected.liquidity_after);
+    assert_eq!(result.tick_after, expected.tick_after);
+    assert_eq!(result.fee_growth_x_128, expected.fee_growth_x_128);
+    Ok(())
+}
+
+#[test]
+fn test_swap_no_alloc_buffer_full() -> eyre::Result<()> {
+    let (ticks, words, slot0) = fixture()?;
+    let provider = SliceTickProvider::new(&ticks, &words);
+    let mut tick_crossings: [TickCrossing; 2] = Default::default();
+    assert!(matches!(
+        swap_no_alloc(
+            &provider,
+            60,
+            true,
+            I256::from_str("1000000000000000000")?,
+            tick_math::MIN_SQRT_RATIO + U256::from(1),
+            &slot0,
+            3000,
+            &mut tick_crossings,
+        ),
+        Err(UniswapV3MathError::TickCrossingBufferFull(2))
+    ));
+    Ok(())
+}
@@ -0,0 +1,3 @@
+//The wasm-pack package of the cw-uniswap-v3 JS bindings, see cw_uniswap_v3::wasm. Only this crate is a cdylib, the
+//core crate and its dependents build as an rlib. Build with `wasm-pack build wasm --target web` from the repository
+pub use cw_uniswap_v3::wasm::*;